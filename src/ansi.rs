@@ -0,0 +1,922 @@
+use slack_morphism::prelude::*;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    emoji::{self, RenderedEmoji},
+    options::RenderOptions,
+    references::{mrkdwn_mention_label, replace_slack_mrkdwn_tokens, SlackReferences},
+    time::format_rich_text_date,
+    visitor::{
+        visit_slack_block_mark_down_text, visit_slack_block_plain_text, visit_slack_context_block,
+        visit_slack_divider_block, visit_slack_header_block, visit_slack_markdown_block,
+        visit_slack_section_block, visit_slack_video_block, SlackRichTextBlock, Visitor,
+    },
+};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "1";
+const DIM: &str = "2";
+const ITALIC: &str = "3";
+const UNDERLINE: &str = "4";
+const STRIKE: &str = "9";
+const CODE: &str = "36";
+const MENTION: &str = "34";
+const HIGHLIGHTED_MENTION: &str = "1;33";
+const DIVIDER_WIDTH: usize = 40;
+
+/// Terminal rendering options.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct AnsiOptions {
+    /// Wrap lines to this number of columns, e.g. the width of the terminal.
+    pub width: Option<usize>,
+    /// Style text and mentions with ANSI SGR codes.
+    pub colors: bool,
+    /// Render links as OSC 8 hyperlinks, rather than their text followed by their URL.
+    pub hyperlinks: bool,
+}
+
+impl Default for AnsiOptions {
+    fn default() -> Self {
+        AnsiOptions {
+            width: None,
+            colors: true,
+            hyperlinks: true,
+        }
+    }
+}
+
+impl AnsiOptions {
+    /// Options for terminals without colors nor hyperlinks, e.g. when `NO_COLOR` is set.
+    pub fn no_color() -> AnsiOptions {
+        AnsiOptions {
+            colors: false,
+            hyperlinks: false,
+            ..AnsiOptions::default()
+        }
+    }
+}
+
+/// Render Slack blocks for a terminal, with ANSI styles and hyperlinks.
+pub fn render_blocks_as_ansi(blocks: Vec<SlackBlock>, slack_references: SlackReferences) -> String {
+    render_blocks_as_ansi_with_options(&blocks, &slack_references, &RenderOptions::default())
+}
+
+/// Render borrowed Slack blocks for a terminal, as configured by `options.ansi`.
+/// Neither the blocks nor the references are cloned.
+pub fn render_blocks_as_ansi_with_options(
+    blocks: &[SlackBlock],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let mut block_renderer = AnsiRenderer::new(slack_references, options);
+    for block in blocks {
+        block_renderer.visit_slack_block(block);
+    }
+    block_renderer.finish()
+}
+
+struct AnsiRenderer<'a> {
+    sub_texts: Vec<String>,
+    slack_references: &'a SlackReferences,
+    options: &'a RenderOptions,
+}
+
+impl<'a> AnsiRenderer<'a> {
+    fn new(slack_references: &'a SlackReferences, options: &'a RenderOptions) -> Self {
+        AnsiRenderer {
+            sub_texts: vec![],
+            slack_references,
+            options,
+        }
+    }
+
+    fn finish(self) -> String {
+        self.sub_texts.join("\n")
+    }
+
+    /// Wrap `text` with SGR `codes`, reset at the end. The codes are applied again after the
+    /// resets of the styles nested in `text`.
+    fn style(&self, text: &str, codes: &[&str]) -> String {
+        if !self.options.ansi.colors || codes.is_empty() || text.is_empty() {
+            return text.to_string();
+        }
+        let open = format!("\x1b[{}m", codes.join(";"));
+        let text = text.strip_suffix(RESET).unwrap_or(text);
+        let text = text.replace(RESET, &format!("{RESET}{open}"));
+        format!("{open}{text}{RESET}")
+    }
+
+    fn link(&self, url: &str, text: &str) -> String {
+        let url = sanitize(url);
+        if self.options.ansi.hyperlinks {
+            format!(
+                "\x1b]8;;{url}\x1b\\{}\x1b]8;;\x1b\\",
+                self.style(text, &[UNDERLINE])
+            )
+        } else if text == url {
+            text.to_string()
+        } else {
+            format!("{text} ({url})")
+        }
+    }
+
+    fn wrap(&self, text: &str, prefix_width: usize) -> Vec<String> {
+        let width = self
+            .options
+            .ansi
+            .width
+            .map(|width| width.saturating_sub(prefix_width).max(1));
+        wrap(text, width)
+    }
+}
+
+impl Visitor for AnsiRenderer<'_> {
+    fn visit_slack_section_block(&mut self, slack_section_block: &SlackSectionBlock) {
+        let mut section_renderer = AnsiRenderer::new(self.slack_references, self.options);
+        visit_slack_section_block(&mut section_renderer, slack_section_block);
+        let section = section_renderer.sub_texts.join("\n");
+        self.sub_texts.push(self.wrap(&section, 0).join("\n"));
+    }
+
+    fn visit_slack_block_plain_text(&mut self, slack_block_plain_text: &SlackBlockPlainText) {
+        self.sub_texts.push(sanitize(&slack_block_plain_text.text));
+        visit_slack_block_plain_text(self, slack_block_plain_text);
+    }
+
+    fn visit_slack_header_block(&mut self, slack_header_block: &SlackHeaderBlock) {
+        let mut header_renderer = AnsiRenderer::new(self.slack_references, self.options);
+        visit_slack_header_block(&mut header_renderer, slack_header_block);
+        let header = self.style(&header_renderer.sub_texts.join(""), &[BOLD, UNDERLINE]);
+        self.sub_texts.push(self.wrap(&header, 0).join("\n"));
+    }
+
+    fn visit_slack_divider_block(&mut self, slack_divider_block: &SlackDividerBlock) {
+        let width = self.options.ansi.width.unwrap_or(DIVIDER_WIDTH);
+        self.sub_texts.push(self.style(&"─".repeat(width), &[DIM]));
+        visit_slack_divider_block(self, slack_divider_block);
+    }
+
+    fn visit_slack_block_mark_down_text(
+        &mut self,
+        slack_block_mark_down_text: &SlackBlockMarkDownText,
+    ) {
+        self.sub_texts.push(replace_slack_mrkdwn_tokens(
+            &sanitize(&slack_block_mark_down_text.text),
+            &self.options.time,
+            |date, link| match link {
+                Some(link) => self.link(&link, &date),
+                None => date,
+            },
+            |element| render_rich_text_section_element(element, self),
+        ));
+        visit_slack_block_mark_down_text(self, slack_block_mark_down_text);
+    }
+
+    fn visit_slack_context_block(&mut self, slack_context_block: &SlackContextBlock) {
+        let mut context_renderer = AnsiRenderer::new(self.slack_references, self.options);
+        visit_slack_context_block(&mut context_renderer, slack_context_block);
+        let context = self.style(&context_renderer.sub_texts.join(" "), &[DIM]);
+        self.sub_texts.push(self.wrap(&context, 0).join("\n"));
+    }
+
+    fn visit_slack_rich_text_block(&mut self, slack_rich_text_block: &SlackRichTextBlock) {
+        self.sub_texts.push(render_rich_text_block_as_ansi(
+            &slack_rich_text_block.json_value,
+            self,
+        ));
+    }
+
+    fn visit_slack_video_block(&mut self, slack_video_block: &SlackVideoBlock) {
+        let title: SlackBlockText = slack_video_block.title.clone().into();
+        let title = match title {
+            SlackBlockText::Plain(plain_text) => plain_text.text,
+            SlackBlockText::MarkDown(md_text) => md_text.text,
+        };
+        let title = self.style(&sanitize(&title), &[BOLD]);
+        let mut video = match &slack_video_block.title_url {
+            Some(title_url) => self.link(title_url.as_str(), &title),
+            None => title,
+        };
+        if let Some(description) = slack_video_block.description.clone() {
+            let description: SlackBlockText = description.into();
+            let description = match description {
+                SlackBlockText::Plain(plain_text) => plain_text.text,
+                SlackBlockText::MarkDown(md_text) => md_text.text,
+            };
+            video.push_str(&format!("\n{}", sanitize(&description)));
+        }
+        self.sub_texts.push(self.wrap(&video, 0).join("\n"));
+        visit_slack_video_block(self, slack_video_block);
+    }
+
+    fn visit_slack_markdown_block(&mut self, slack_markdown_block: &SlackMarkdownBlock) {
+        let markdown = sanitize(&slack_markdown_block.text);
+        self.sub_texts.push(self.wrap(&markdown, 0).join("\n"));
+        visit_slack_markdown_block(self, slack_markdown_block);
+    }
+}
+
+fn render_rich_text_block_as_ansi(
+    json_value: &serde_json::Value,
+    renderer: &AnsiRenderer,
+) -> String {
+    let Some(serde_json::Value::Array(elements)) = json_value.get("elements") else {
+        return "".to_string();
+    };
+    elements
+        .iter()
+        .map(|element| {
+            let Some(serde_json::Value::Array(elements)) = element.get("elements") else {
+                return "".to_string();
+            };
+            match element.get("type").and_then(|t| t.as_str()) {
+                Some("rich_text_section") => renderer
+                    .wrap(&render_rich_text_section_elements(elements, renderer), 0)
+                    .join("\n"),
+                Some("rich_text_list") => {
+                    render_rich_text_list_elements(element, elements, renderer)
+                }
+                Some("rich_text_preformatted") => {
+                    render_rich_text_preformatted_elements(elements, renderer)
+                }
+                Some("rich_text_quote") => {
+                    let quote = render_rich_text_section_elements(elements, renderer);
+                    let bar = renderer.style("│", &[DIM]);
+                    renderer
+                        .wrap(&quote, 2)
+                        .iter()
+                        .map(|line| format!("{bar} {line}").trim_end().to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+                _ => "".to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_rich_text_section_elements(
+    elements: &[serde_json::Value],
+    renderer: &AnsiRenderer,
+) -> String {
+    elements
+        .iter()
+        .map(|element| render_rich_text_section_element(element, renderer))
+        .collect::<Vec<_>>()
+        .join("")
+        .trim_end_matches('\n')
+        .to_string()
+}
+
+/// Render list items with a hanging indent, so that wrapped lines are aligned with the text of
+/// their item.
+fn render_rich_text_list_elements(
+    list: &serde_json::Value,
+    elements: &[serde_json::Value],
+    renderer: &AnsiRenderer,
+) -> String {
+    let ordered = list.get("style").and_then(|s| s.as_str()) == Some("ordered");
+    let indent = list.get("indent").and_then(|i| i.as_u64()).unwrap_or(0);
+    let offset = list.get("offset").and_then(|o| o.as_u64()).unwrap_or(0);
+    let indent_prefix = "  ".repeat(indent.try_into().unwrap_or_default());
+    elements
+        .iter()
+        .filter_map(|element| match element.get("elements") {
+            Some(serde_json::Value::Array(elements)) => {
+                Some(render_rich_text_section_elements(elements, renderer))
+            }
+            _ => None,
+        })
+        .enumerate()
+        .map(|(index, item)| {
+            let marker = if ordered {
+                format!("{}. ", offset + index as u64 + 1)
+            } else {
+                "• ".to_string()
+            };
+            let prefix_width = display_width(&indent_prefix) + display_width(&marker);
+            renderer
+                .wrap(&item, prefix_width)
+                .iter()
+                .enumerate()
+                .map(|(line_index, line)| {
+                    if line_index == 0 {
+                        format!("{indent_prefix}{marker}{line}")
+                    } else {
+                        format!("{}{line}", " ".repeat(prefix_width))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render preformatted text in a box, hard-wrapping lines wider than the terminal.
+fn render_rich_text_preformatted_elements(
+    elements: &[serde_json::Value],
+    renderer: &AnsiRenderer,
+) -> String {
+    let plain_renderer_options = RenderOptions {
+        ansi: AnsiOptions::no_color(),
+        ..renderer.options.clone()
+    };
+    let plain_renderer = AnsiRenderer::new(renderer.slack_references, &plain_renderer_options);
+    let code = render_rich_text_section_elements(elements, &plain_renderer);
+    let max_width = renderer
+        .options
+        .ansi
+        .width
+        .map(|width| width.saturating_sub(4).max(1));
+    let lines = code
+        .split('\n')
+        .flat_map(|line| hard_wrap(line, max_width))
+        .collect::<Vec<_>>();
+    let inner_width = lines
+        .iter()
+        .map(|line| display_width(line))
+        .max()
+        .unwrap_or(0);
+    let border = |text: &str| renderer.style(text, &[DIM]);
+    let mut boxed = vec![border(&format!("╭{}╮", "─".repeat(inner_width + 2)))];
+    for line in &lines {
+        let padding = " ".repeat(inner_width - display_width(line));
+        boxed.push(format!(
+            "{} {}{padding} {}",
+            border("│"),
+            renderer.style(line, &[CODE]),
+            border("│")
+        ));
+    }
+    boxed.push(border(&format!("╰{}╯", "─".repeat(inner_width + 2))));
+    boxed.join("\n")
+}
+
+fn render_rich_text_section_element(
+    element: &serde_json::Value,
+    renderer: &AnsiRenderer,
+) -> String {
+    let field = |name: &str| element.get(name).and_then(|value| value.as_str());
+    let codes = style_codes(element.get("style"));
+    match field("type") {
+        Some("text") => {
+            let Some(text) = field("text") else {
+                return "".to_string();
+            };
+            renderer.style(&sanitize(text), &codes)
+        }
+        Some("channel") => {
+            let Some(channel_id) = field("channel_id") else {
+                return "".to_string();
+            };
+            let slack_channel_id = SlackChannelId(channel_id.to_string());
+            let channel = match renderer.slack_references.channels.get(&slack_channel_id) {
+                Some(Some(channel_name)) => Some(format!("#{channel_name}")),
+                _ => mrkdwn_mention_label(element, '#').or_else(|| {
+                    renderer
+                        .options
+                        .unresolved_mention_placeholders
+                        .channel(&slack_channel_id)
+                }),
+            };
+            let highlighted = renderer
+                .slack_references
+                .is_channel_highlighted(&slack_channel_id);
+            render_mention(channel, highlighted, renderer)
+        }
+        Some("user") => {
+            let Some(user_id) = field("user_id") else {
+                return "".to_string();
+            };
+            let slack_user_id = SlackUserId(user_id.to_string());
+            let user = match renderer
+                .slack_references
+                .user_name(&slack_user_id, renderer.options.user_display_policy)
+            {
+                Some(user_name) => Some(format!("@{user_name}")),
+                None => mrkdwn_mention_label(element, '@').or_else(|| {
+                    renderer
+                        .options
+                        .unresolved_mention_placeholders
+                        .user(&slack_user_id)
+                }),
+            };
+            let highlighted = renderer
+                .slack_references
+                .is_user_highlighted(&slack_user_id);
+            render_mention(user, highlighted, renderer)
+        }
+        Some("usergroup") => {
+            let Some(usergroup_id) = field("usergroup_id") else {
+                return "".to_string();
+            };
+            let slack_usergroup_id = SlackUserGroupId(usergroup_id.to_string());
+            let usergroup = match renderer
+                .slack_references
+                .usergroups
+                .get(&slack_usergroup_id)
+            {
+                Some(Some(usergroup_name)) => Some(format!("@{usergroup_name}")),
+                _ => mrkdwn_mention_label(element, '@').or_else(|| {
+                    renderer
+                        .options
+                        .unresolved_mention_placeholders
+                        .usergroup(&slack_usergroup_id)
+                }),
+            };
+            let highlighted = renderer
+                .slack_references
+                .is_usergroup_highlighted(&slack_usergroup_id);
+            render_mention(usergroup, highlighted, renderer)
+        }
+        Some("broadcast") => {
+            let Some(range) = field("range") else {
+                return "".to_string();
+            };
+            let highlighted = renderer.slack_references.is_broadcast_highlighted(range);
+            render_mention(Some(format!("@{range}")), highlighted, renderer)
+        }
+        Some("emoji") => {
+            let Some(name) = field("name") else {
+                return "".to_string();
+            };
+            render_emoji(&SlackEmojiName(name.to_string()), renderer)
+        }
+        Some("link") => {
+            let Some(url) = field("url") else {
+                return "".to_string();
+            };
+            let text = sanitize(field("text").unwrap_or(url));
+            renderer.link(url, &renderer.style(&text, &codes))
+        }
+        Some("date") => {
+            let date = format_rich_text_date(element, &renderer.options.time).unwrap_or_default();
+            renderer.style(&date, &codes)
+        }
+        _ => "".to_string(),
+    }
+}
+
+fn render_mention(mention: Option<String>, highlighted: bool, renderer: &AnsiRenderer) -> String {
+    let Some(mention) = mention else {
+        return "".to_string();
+    };
+    let codes = if highlighted {
+        [HIGHLIGHTED_MENTION]
+    } else {
+        [MENTION]
+    };
+    renderer.style(&sanitize(&mention), &codes)
+}
+
+fn render_emoji(emoji_name: &SlackEmojiName, renderer: &AnsiRenderer) -> String {
+    match emoji::render_emoji(emoji_name, renderer.slack_references, renderer.options) {
+        RenderedEmoji::Text(text) | RenderedEmoji::Raw(text) => sanitize(&text),
+        RenderedEmoji::Shortcode(name) | RenderedEmoji::Image { name, .. } => {
+            format!(":{}:", sanitize(&name))
+        }
+    }
+}
+
+fn style_codes(style: Option<&serde_json::Value>) -> Vec<&'static str> {
+    [
+        ("bold", BOLD),
+        ("italic", ITALIC),
+        ("strike", STRIKE),
+        ("code", CODE),
+    ]
+    .into_iter()
+    .filter(|(name, _)| {
+        style
+            .and_then(|style| style.get(name))
+            .and_then(|styled| styled.as_bool())
+            .unwrap_or_default()
+    })
+    .map(|(_, code)| code)
+    .collect()
+}
+
+/// Remove control characters from Slack content, so that it cannot send escape sequences to
+/// the terminal.
+fn sanitize(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || *c == '\n' || *c == '\t')
+        .collect()
+}
+
+enum Token<'a> {
+    Escape(&'a str),
+    Newline,
+    Space(&'a str),
+    Word(&'a str),
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let len = match c {
+            '\x1b' => escape_len(rest),
+            '\n' => 1,
+            ' ' => rest.find(|c| c != ' ').unwrap_or(rest.len()),
+            _ => rest.find([' ', '\n', '\x1b']).unwrap_or(rest.len()),
+        };
+        let (token, tail) = rest.split_at(len);
+        tokens.push(match c {
+            '\x1b' => Token::Escape(token),
+            '\n' => Token::Newline,
+            ' ' => Token::Space(token),
+            _ => Token::Word(token),
+        });
+        rest = tail;
+    }
+    tokens
+}
+
+/// Length of the CSI (`ESC [ ... m`) or OSC (`ESC ] ... ESC \`) sequence starting `text`.
+fn escape_len(text: &str) -> usize {
+    match text.as_bytes().get(1) {
+        Some(b'[') => text[2..]
+            .find(|c: char| ('@'..='~').contains(&c))
+            .map(|end| end + 3)
+            .unwrap_or(text.len()),
+        Some(b']') => text[2..]
+            .find("\x1b\\")
+            .map(|end| end + 4)
+            .unwrap_or(text.len()),
+        _ => 1,
+    }
+}
+
+/// Wrap `text` on spaces to `width` columns. Styles and hyperlinks spanning several lines are
+/// closed at the end of each line and reopened on the next one.
+fn wrap(text: &str, width: Option<usize>) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    let mut line_width = 0;
+    let mut pending_space = "";
+    // Styles and hyperlinks opened after a pending space, applied with the next word so that
+    // they are not left at the end of a wrapped line
+    let mut pending_escapes: Vec<&str> = vec![];
+    // SGR sequences applied since the last reset, which add up
+    let mut styles: Vec<&str> = vec![];
+    let mut hyperlink: Option<&str> = None;
+
+    let break_line =
+        |lines: &mut Vec<String>, line: &mut String, styles: &[&str], hyperlink: Option<&str>| {
+            if hyperlink.is_some() {
+                line.push_str("\x1b]8;;\x1b\\");
+            }
+            if !styles.is_empty() {
+                line.push_str(RESET);
+            }
+            lines.push(std::mem::take(line));
+            line.extend(styles.iter().copied());
+            line.extend(hyperlink);
+        };
+
+    for token in tokenize(text) {
+        match token {
+            Token::Escape(escape) if width.is_some() && !pending_space.is_empty() => {
+                if is_opening_escape(escape) {
+                    pending_escapes.push(escape);
+                } else {
+                    apply_escape(escape, &mut styles, &mut hyperlink);
+                    line.push_str(escape);
+                }
+            }
+            Token::Escape(escape) => {
+                apply_escape(escape, &mut styles, &mut hyperlink);
+                line.push_str(escape);
+            }
+            Token::Newline => {
+                break_line(&mut lines, &mut line, &styles, hyperlink);
+                line_width = 0;
+                pending_space = "";
+            }
+            Token::Space(space) if width.is_none() => {
+                line.push_str(space);
+            }
+            Token::Space(space) => pending_space = space,
+            Token::Word(word) => {
+                let word_width = display_width(word);
+                let space_width = pending_space.len();
+                match width {
+                    Some(width)
+                        if line_width > 0 && line_width + space_width + word_width > width =>
+                    {
+                        break_line(&mut lines, &mut line, &styles, hyperlink);
+                        line_width = 0;
+                    }
+                    _ => {
+                        line.push_str(pending_space);
+                        line_width += space_width;
+                    }
+                }
+                pending_space = "";
+                for escape in pending_escapes.drain(..) {
+                    apply_escape(escape, &mut styles, &mut hyperlink);
+                    line.push_str(escape);
+                }
+                let chunks = hard_wrap(word, width);
+                for (index, chunk) in chunks.iter().enumerate() {
+                    if index > 0 {
+                        break_line(&mut lines, &mut line, &styles, hyperlink);
+                        line_width = 0;
+                    }
+                    line.push_str(chunk);
+                    line_width += display_width(chunk);
+                }
+            }
+        }
+    }
+    line.push_str(pending_space);
+    line.extend(pending_escapes);
+    lines.push(line);
+    lines
+}
+
+fn is_opening_escape(escape: &str) -> bool {
+    escape != RESET && escape != "\x1b]8;;\x1b\\"
+}
+
+/// Track the styles and hyperlink active after `escape`.
+fn apply_escape<'a>(escape: &'a str, styles: &mut Vec<&'a str>, hyperlink: &mut Option<&'a str>) {
+    if escape == RESET {
+        styles.clear();
+    } else if escape.starts_with("\x1b[") {
+        styles.push(escape);
+    } else if escape.starts_with("\x1b]8;") {
+        *hyperlink = is_opening_escape(escape).then_some(escape);
+    }
+}
+
+/// Split `text`, without escape sequences, in chunks of at most `width` columns, between
+/// grapheme clusters.
+fn hard_wrap(text: &str, width: Option<usize>) -> Vec<String> {
+    let Some(width) = width else {
+        return vec![text.to_string()];
+    };
+    let mut chunks = vec![];
+    let mut chunk = String::new();
+    let mut chunk_width = 0;
+    for grapheme in text.graphemes(true) {
+        let grapheme_width = grapheme.width();
+        if chunk_width + grapheme_width > width && chunk_width > 0 {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_width = 0;
+        }
+        chunk.push_str(grapheme);
+        chunk_width += grapheme_width;
+    }
+    chunks.push(chunk);
+    chunks
+}
+
+/// Number of terminal columns of `text`, ignoring escape sequences.
+fn display_width(text: &str) -> usize {
+    tokenize(text)
+        .iter()
+        .map(|token| match token {
+            Token::Escape(_) | Token::Newline => 0,
+            Token::Space(space) => space.len(),
+            Token::Word(word) => word.width(),
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{message::mrkdwn_section, test_utils::rich_text_block};
+
+    fn render(blocks: &[SlackBlock], ansi: AnsiOptions) -> String {
+        let slack_references = SlackReferences {
+            users: HashMap::from([
+                (SlackUserId("U1".to_string()), Some("alice".to_string())),
+                (SlackUserId("U2".to_string()), Some("bob".to_string())),
+            ]),
+            user_ids_to_highlight: Some(vec![SlackUserId("U2".to_string())]),
+            ..SlackReferences::default()
+        };
+        render_blocks_as_ansi_with_options(
+            blocks,
+            &slack_references,
+            &RenderOptions {
+                ansi,
+                ..RenderOptions::default()
+            },
+        )
+    }
+
+    fn section(elements: serde_json::Value) -> Vec<SlackBlock> {
+        vec![rich_text_block(serde_json::json!({
+            "type": "rich_text",
+            "elements": [{ "type": "rich_text_section", "elements": elements }]
+        }))]
+    }
+
+    #[test]
+    fn test_with_styles_and_mentions() {
+        let blocks = section(serde_json::json!([
+            { "type": "text", "text": "Hi ", "style": { "bold": true } },
+            { "type": "user", "user_id": "U1" },
+            { "type": "text", "text": " and " },
+            { "type": "user", "user_id": "U2" },
+            { "type": "text", "text": ", run " },
+            { "type": "text", "text": "make", "style": { "code": true, "italic": true } },
+            { "type": "text", "text": " not ", "style": { "strike": true } }
+        ]));
+        assert_eq!(
+            render(&blocks, AnsiOptions::default()),
+            "\x1b[1mHi \x1b[0m\x1b[34m@alice\x1b[0m and \x1b[1;33m@bob\x1b[0m, run \
+            \x1b[3;36mmake\x1b[0m\x1b[9m not \x1b[0m"
+        );
+        assert_eq!(
+            render(&blocks, AnsiOptions::no_color()),
+            "Hi @alice and @bob, run make not "
+        );
+    }
+
+    #[test]
+    fn test_with_links() {
+        let blocks = section(serde_json::json!([
+            { "type": "link", "url": "https://example.com/", "text": "docs" },
+            { "type": "text", "text": " " },
+            { "type": "link", "url": "https://example.com/raw" }
+        ]));
+        assert_eq!(
+            render(&blocks, AnsiOptions::default()),
+            "\x1b]8;;https://example.com/\x1b\\\x1b[4mdocs\x1b[0m\x1b]8;;\x1b\\ \
+            \x1b]8;;https://example.com/raw\x1b\\\x1b[4mhttps://example.com/raw\x1b[0m\x1b]8;;\x1b\\"
+        );
+        assert_eq!(
+            render(&blocks, AnsiOptions::no_color()),
+            "docs (https://example.com/) https://example.com/raw"
+        );
+    }
+
+    #[test]
+    fn test_with_mrkdwn_mentions_and_links() {
+        let blocks = vec![mrkdwn_section(
+            "<@U1> see <https://example.com/|the docs> &amp; <!here>",
+        )];
+        assert_eq!(
+            render(&blocks, AnsiOptions::no_color()),
+            "@alice see the docs (https://example.com/) & @here"
+        );
+    }
+
+    #[test]
+    fn test_strips_escape_sequences_from_content() {
+        let blocks = section(serde_json::json!([
+            { "type": "text", "text": "evil\x1b]8;;https://evil.example\x1b\\ text\x07" }
+        ]));
+        assert_eq!(
+            render(&blocks, AnsiOptions::no_color()),
+            "evil]8;;https://evil.example\\ text"
+        );
+    }
+
+    #[test]
+    fn test_wraps_to_width() {
+        let blocks = section(serde_json::json!([
+            { "type": "text", "text": "The quick brown " },
+            { "type": "text", "text": "fox jumps over", "style": { "bold": true } },
+            { "type": "text", "text": " the lazy dog" }
+        ]));
+        assert_eq!(
+            render(
+                &blocks,
+                AnsiOptions {
+                    width: Some(12),
+                    ..AnsiOptions::default()
+                }
+            ),
+            "The quick\nbrown \x1b[1mfox\x1b[0m\n\x1b[1mjumps over\x1b[0m\nthe lazy dog"
+        );
+    }
+
+    #[test]
+    fn test_wraps_nested_styles() {
+        let blocks = section(serde_json::json!([
+            {
+                "type": "link",
+                "url": "https://example.com",
+                "text": "quick brown",
+                "style": { "bold": true, "italic": true }
+            },
+            { "type": "text", "text": " fox jumps", "style": { "code": true, "bold": true } }
+        ]));
+        assert_eq!(
+            render(
+                &blocks,
+                AnsiOptions {
+                    width: Some(6),
+                    ..AnsiOptions::default()
+                }
+            ),
+            "\x1b]8;;https://example.com/\x1b\\\x1b[4m\x1b[1;3mquick\x1b]8;;\x1b\\\x1b[0m\n\
+            \x1b[4m\x1b[1;3m\x1b]8;;https://example.com/\x1b\\brown\x1b[0m\x1b]8;;\x1b\\\
+            \x1b[1;36m\x1b[0m\n\
+            \x1b[1;36mfox\x1b[0m\n\
+            \x1b[1;36mjumps\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_styles_reapplied_after_nested_resets() {
+        let slack_references = SlackReferences::default();
+        let options = RenderOptions::default();
+        let renderer = AnsiRenderer::new(&slack_references, &options);
+        let text = format!("see {} now", renderer.style("this", &[BOLD]));
+        assert_eq!(
+            renderer.style(&text, &[UNDERLINE]),
+            "\x1b[4msee \x1b[1mthis\x1b[0m\x1b[4m now\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_wraps_wide_characters_and_long_words() {
+        assert_eq!(
+            wrap("日本語の文章 abcdefghij", Some(6)),
+            vec!["日本語", "の文章", "abcdef", "ghij"]
+        );
+        assert_eq!(display_width("👍🏽 ok"), 5);
+    }
+
+    #[test]
+    fn test_wraps_emoji_sequences_as_grapheme_clusters() {
+        assert_eq!(display_width("☕"), 2);
+        assert_eq!(display_width("👨‍👩‍👧"), 2);
+        assert_eq!(display_width("🇫🇷"), 2);
+        assert_eq!(wrap("👨‍👩‍👧🇫🇷☕e\u{301}", Some(4)), vec!["👨‍👩‍👧🇫🇷", "☕e\u{301}"]);
+    }
+
+    #[test]
+    fn test_with_lists_quotes_and_preformatted() {
+        let blocks = vec![rich_text_block(serde_json::json!({
+            "type": "rich_text",
+            "elements": [
+                {
+                    "type": "rich_text_list",
+                    "style": "bullet",
+                    "elements": [
+                        { "type": "rich_text_section", "elements": [{ "type": "text", "text": "a long first item" }] },
+                        { "type": "rich_text_section", "elements": [{ "type": "text", "text": "two" }] }
+                    ]
+                },
+                {
+                    "type": "rich_text_list",
+                    "style": "ordered",
+                    "indent": 1,
+                    "elements": [
+                        { "type": "rich_text_section", "elements": [{ "type": "text", "text": "first" }] }
+                    ]
+                },
+                {
+                    "type": "rich_text_quote",
+                    "elements": [{ "type": "text", "text": "quoted text here" }]
+                },
+                {
+                    "type": "rich_text_preformatted",
+                    "elements": [{ "type": "text", "text": "let x = 1;\nx" }]
+                }
+            ]
+        }))];
+        assert_eq!(
+            render(
+                &blocks,
+                AnsiOptions {
+                    width: Some(14),
+                    ..AnsiOptions::no_color()
+                }
+            ),
+            "• a long first\n  item\n• two\n  1. first\n│ quoted text\n│ here\n\
+            ╭────────────╮\n│ let x = 1; │\n│ x          │\n╰────────────╯"
+        );
+    }
+
+    #[test]
+    fn test_with_header_and_divider() {
+        let blocks = vec![
+            SlackBlock::Header(SlackHeaderBlock::new("Release".into())),
+            SlackBlock::Divider(SlackDividerBlock::new()),
+        ];
+        assert_eq!(
+            render(
+                &blocks,
+                AnsiOptions {
+                    width: Some(10),
+                    ..AnsiOptions::default()
+                }
+            ),
+            "\x1b[1;4mRelease\x1b[0m\n\x1b[2m──────────\x1b[0m"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use slack_morphism::prelude::*;
+
+/// Legacy attachment of a message.
+///
+/// It can be deserialized from the attachments of the Slack API, e.g. with
+/// [`find_attachments_in_message`], or converted from a slack-morphism `SlackMessageAttachment`,
+/// which lacks the pretext, title link, images and footer.
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlackAttachment {
+    /// Color bar: a hex code or one of `good`, `warning` and `danger`.
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Plain text summary, rendered when the attachment has nothing else.
+    #[serde(default)]
+    pub fallback: Option<String>,
+    /// mrkdwn text shown above the attachment.
+    #[serde(default)]
+    pub pretext: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub title_link: Option<String>,
+    /// mrkdwn text.
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub fields: Option<Vec<SlackMessageAttachmentFieldObject>>,
+    #[serde(default)]
+    pub image_url: Option<String>,
+    #[serde(default)]
+    pub thumb_url: Option<String>,
+    /// mrkdwn text shown below the attachment.
+    #[serde(default)]
+    pub footer: Option<String>,
+    #[serde(default)]
+    pub blocks: Option<Vec<SlackBlock>>,
+    /// URL of the link preview this attachment is, if it is an unfurl.
+    #[serde(default)]
+    pub from_url: Option<String>,
+    #[serde(default)]
+    pub original_url: Option<String>,
+}
+
+impl From<&SlackMessageAttachment> for SlackAttachment {
+    fn from(attachment: &SlackMessageAttachment) -> Self {
+        SlackAttachment {
+            color: attachment.color.clone(),
+            fallback: attachment.fallback.clone(),
+            title: attachment.title.clone(),
+            text: attachment.text.clone(),
+            fields: attachment.fields.clone(),
+            blocks: attachment.blocks.clone(),
+            ..SlackAttachment::default()
+        }
+    }
+}
+
+impl SlackAttachment {
+    /// Whether the attachment has nothing but its `fallback` text to render.
+    pub(crate) fn is_fallback_only(&self) -> bool {
+        self.pretext.is_none()
+            && self.title.is_none()
+            && self.text.is_none()
+            && self.fields.as_ref().is_none_or(|fields| fields.is_empty())
+            && self.image().is_none()
+            && self.footer.is_none()
+            && self.blocks.as_ref().is_none_or(|blocks| blocks.is_empty())
+    }
+
+    /// Whether the attachment is a link preview, rendered as a card by the `render_unfurls_as_*`
+    /// functions instead.
+    pub(crate) fn is_unfurl(&self) -> bool {
+        self.from_url.is_some() || self.original_url.is_some()
+    }
+
+    /// URL of the image, or of its thumbnail.
+    pub(crate) fn image(&self) -> Option<&str> {
+        self.image_url.as_deref().or(self.thumb_url.as_deref())
+    }
+}
+
+/// Find the attachments of a message in the JSON format of the Slack API.
+pub fn find_attachments_in_message(message: &serde_json::Value) -> Vec<SlackAttachment> {
+    let Some(serde_json::Value::Array(attachments)) = message.get("attachments") else {
+        return vec![];
+    };
+    attachments
+        .iter()
+        .filter_map(|attachment| serde_json::from_value(attachment.clone()).ok())
+        .collect()
+}
+
+/// Attachments of a message, converted from the slack-morphism model.
+pub(crate) fn message_attachments(content: &SlackMessageContent) -> Vec<SlackAttachment> {
+    content
+        .attachments
+        .iter()
+        .flatten()
+        .map(SlackAttachment::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_attachments_in_message() {
+        let attachments = find_attachments_in_message(&serde_json::json!({
+            "ts": "1720000000.000100",
+            "attachments": [
+                {
+                    "color": "good",
+                    "pretext": "Build report",
+                    "title": "api v1.2.0",
+                    "title_link": "https://ci.example.com/1",
+                    "image_url": "https://ci.example.com/1.png",
+                    "footer": "CI"
+                }
+            ]
+        }));
+        assert_eq!(
+            attachments,
+            vec![SlackAttachment {
+                color: Some("good".to_string()),
+                pretext: Some("Build report".to_string()),
+                title: Some("api v1.2.0".to_string()),
+                title_link: Some("https://ci.example.com/1".to_string()),
+                image_url: Some("https://ci.example.com/1.png".to_string()),
+                footer: Some("CI".to_string()),
+                ..SlackAttachment::default()
+            }]
+        );
+        assert!(!attachments[0].is_fallback_only());
+    }
+
+    #[test]
+    fn test_find_attachments_in_message_without_attachments() {
+        assert!(find_attachments_in_message(&serde_json::json!({ "text": "Hello" })).is_empty());
+    }
+}
@@ -0,0 +1,812 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hash::Hash,
+    io,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use slack_morphism::prelude::*;
+
+use crate::{
+    references::{SlackReferences, SlackUserReference},
+    resolver::SlackReferenceResolver,
+    time::Clock,
+};
+
+/// Time to live of cached entries.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct SlackReferencesCacheTtl {
+    /// Time to live of resolved entries.
+    pub resolved: Duration,
+    /// Time to live of IDs confirmed as not found (negative caching).
+    pub unresolved: Duration,
+}
+
+impl Default for SlackReferencesCacheTtl {
+    fn default() -> Self {
+        SlackReferencesCacheTtl {
+            resolved: Duration::from_secs(24 * 60 * 60),
+            unresolved: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry<V> {
+    value: Option<V>,
+    /// Expiration date, in seconds since the Unix epoch.
+    expires_at: u64,
+}
+
+/// Resolved Slack references with a per-entry expiration date, persisted as JSON.
+///
+/// IDs a [`CachedSlackReferenceResolver`] was told do not exist are cached as well, with their
+/// own time to live, so that they are not resolved again on every run.
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlackReferencesCache {
+    #[serde(default = "HashMap::new")]
+    channels: HashMap<SlackChannelId, CacheEntry<String>>,
+    #[serde(default = "HashMap::new")]
+    users: HashMap<SlackUserId, CacheEntry<String>>,
+    #[serde(default = "HashMap::new")]
+    usergroups: HashMap<SlackUserGroupId, CacheEntry<String>>,
+    #[serde(default = "HashMap::new")]
+    emojis: HashMap<SlackEmojiName, CacheEntry<SlackEmojiRef>>,
+    #[serde(default = "HashMap::new")]
+    teams: HashMap<SlackTeamId, CacheEntry<String>>,
+    #[serde(default = "HashMap::new")]
+    user_profiles: HashMap<SlackUserId, CacheEntry<SlackUserReference>>,
+}
+
+impl SlackReferencesCache {
+    pub fn new() -> SlackReferencesCache {
+        SlackReferencesCache::default()
+    }
+
+    /// Load a cache from a JSON file. A missing file is an empty cache.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<SlackReferencesCache> {
+        match fs::read(path) {
+            Ok(content) => Ok(serde_json::from_slice(&content)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Save the cache as a JSON file, replacing it atomically.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, serde_json::to_vec(self)?)?;
+        fs::rename(&tmp_path, path)
+    }
+
+    /// Store the resolved entries and the user profiles of `slack_references`.
+    ///
+    /// Unresolved entries are skipped, as they may not have been resolved at all: only the IDs a
+    /// [`CachedSlackReferenceResolver`] was told do not exist are negatively cached.
+    pub fn insert(
+        &mut self,
+        slack_references: &SlackReferences,
+        ttl: SlackReferencesCacheTtl,
+        now: SystemTime,
+    ) {
+        insert_entries(&mut self.users, resolved(&slack_references.users), ttl, now);
+        insert_entries(
+            &mut self.channels,
+            resolved(&slack_references.channels),
+            ttl,
+            now,
+        );
+        insert_entries(
+            &mut self.usergroups,
+            resolved(&slack_references.usergroups),
+            ttl,
+            now,
+        );
+        insert_entries(
+            &mut self.emojis,
+            resolved(&slack_references.emojis),
+            ttl,
+            now,
+        );
+        insert_entries(&mut self.teams, resolved(&slack_references.teams), ttl, now);
+        let user_profiles = slack_references
+            .user_profiles
+            .iter()
+            .map(|(user_id, profile)| (user_id.clone(), Some(profile.clone())));
+        insert_entries(&mut self.user_profiles, user_profiles, ttl, now);
+    }
+
+    /// Resolved entries that have not expired at `now`, to be merged with
+    /// [`SlackReferences::extend`].
+    pub fn slack_references(&self, now: SystemTime) -> SlackReferences {
+        SlackReferences {
+            users: fresh_entries(&self.users, now),
+            channels: fresh_entries(&self.channels, now),
+            usergroups: fresh_entries(&self.usergroups, now),
+            emojis: fresh_entries(&self.emojis, now),
+            teams: fresh_entries(&self.teams, now),
+            user_profiles: fresh_entries(&self.user_profiles, now)
+                .into_iter()
+                .filter_map(|(user_id, profile)| Some((user_id, profile?)))
+                .collect(),
+            ..SlackReferences::default()
+        }
+    }
+
+    /// Remove the entries that have expired at `now`.
+    pub fn remove_expired(&mut self, now: SystemTime) {
+        let now = timestamp(now);
+        self.users.retain(|_, entry| entry.expires_at > now);
+        self.channels.retain(|_, entry| entry.expires_at > now);
+        self.usergroups.retain(|_, entry| entry.expires_at > now);
+        self.emojis.retain(|_, entry| entry.expires_at > now);
+        self.teams.retain(|_, entry| entry.expires_at > now);
+        self.user_profiles.retain(|_, entry| entry.expires_at > now);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+            && self.channels.is_empty()
+            && self.usergroups.is_empty()
+            && self.emojis.is_empty()
+            && self.teams.is_empty()
+            && self.user_profiles.is_empty()
+    }
+}
+
+fn timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Resolved entries of `entries`.
+fn resolved<K, V>(entries: &HashMap<K, Option<V>>) -> impl Iterator<Item = (K, Option<V>)> + '_
+where
+    K: Clone,
+    V: Clone,
+{
+    entries
+        .iter()
+        .filter(|(_, value)| value.is_some())
+        .map(|(key, value)| (key.clone(), value.clone()))
+}
+
+/// Cache `entries`, an unresolved entry never replacing an entry that has not expired.
+fn insert_entries<K, V>(
+    cache: &mut HashMap<K, CacheEntry<V>>,
+    entries: impl IntoIterator<Item = (K, Option<V>)>,
+    ttl: SlackReferencesCacheTtl,
+    now: SystemTime,
+) where
+    K: Eq + Hash,
+{
+    for (key, value) in entries {
+        let entry_ttl = if value.is_some() {
+            ttl.resolved
+        } else {
+            ttl.unresolved
+        };
+        let is_fresh = cache
+            .get(&key)
+            .is_some_and(|entry| entry.expires_at > timestamp(now));
+        if value.is_none() && is_fresh {
+            continue;
+        }
+        // A TTL too large for the clock, e.g. `Duration::MAX`, never expires
+        let expires_at = now.checked_add(entry_ttl).map_or(u64::MAX, timestamp);
+        cache.insert(key, CacheEntry { value, expires_at });
+    }
+}
+
+fn fresh_entries<K, V>(cache: &HashMap<K, CacheEntry<V>>, now: SystemTime) -> HashMap<K, Option<V>>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    let now = timestamp(now);
+    cache
+        .iter()
+        .filter(|(_, entry)| entry.expires_at > now && entry.value.is_some())
+        .map(|(key, entry)| (key.clone(), entry.value.clone()))
+        .collect()
+}
+
+/// [`SlackReferenceResolver`] answering from a [`SlackReferencesCache`] and forwarding cache
+/// misses to another resolver.
+///
+/// IDs the inner resolver maps to `None`, i.e. confirmed as not found, are cached too, so they
+/// are not requested again until their entry expires. IDs missing from its result and calls
+/// failing with an error are not cached, and are requested again on the next call.
+pub struct CachedSlackReferenceResolver<R> {
+    resolver: R,
+    cache: Mutex<SlackReferencesCache>,
+    ttl: SlackReferencesCacheTtl,
+    clock: Clock,
+}
+
+impl<R> CachedSlackReferenceResolver<R>
+where
+    R: SlackReferenceResolver,
+{
+    pub fn new(resolver: R, cache: SlackReferencesCache, ttl: SlackReferencesCacheTtl) -> Self {
+        CachedSlackReferenceResolver {
+            resolver,
+            cache: Mutex::new(cache),
+            ttl,
+            clock: Clock::system(),
+        }
+    }
+
+    /// Set the clock deciding when cached entries expire, the system clock by default.
+    pub fn with_clock(self, clock: Clock) -> Self {
+        CachedSlackReferenceResolver { clock, ..self }
+    }
+
+    fn now(&self) -> SystemTime {
+        self.clock.now().into()
+    }
+
+    /// Return the cache, updated with every entry resolved so far.
+    pub fn into_cache(self) -> SlackReferencesCache {
+        self.cache
+            .into_inner()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Split `ids` between fresh cached entries and IDs to resolve.
+    fn cached_entries<K, V>(
+        &self,
+        ids: &[K],
+        entries: fn(&mut SlackReferencesCache) -> &mut HashMap<K, CacheEntry<V>>,
+    ) -> (HashMap<K, Option<V>>, Vec<K>)
+    where
+        K: Eq + Hash + Clone,
+        V: Clone,
+    {
+        let now = timestamp(self.now());
+        let mut cache = self.cache.lock().unwrap_or_else(|err| err.into_inner());
+        let cached = entries(&mut cache);
+        let mut resolved = HashMap::new();
+        let mut missing_ids = vec![];
+        for id in ids {
+            match cached.get(id) {
+                Some(entry) if entry.expires_at > now => {
+                    resolved.insert(id.clone(), entry.value.clone());
+                }
+                _ => missing_ids.push(id.clone()),
+            }
+        }
+        (resolved, missing_ids)
+    }
+
+    /// Cache the entries returned by the inner resolver, IDs mapped to `None` being negatively
+    /// cached.
+    fn cache_entries<K, V>(
+        &self,
+        fetched: HashMap<K, Option<V>>,
+        entries: fn(&mut SlackReferencesCache) -> &mut HashMap<K, CacheEntry<V>>,
+    ) -> HashMap<K, Option<V>>
+    where
+        K: Eq + Hash + Clone,
+        V: Clone,
+    {
+        let mut cache = self.cache.lock().unwrap_or_else(|err| err.into_inner());
+        insert_entries(entries(&mut cache), fetched.clone(), self.ttl, self.now());
+        fetched
+    }
+}
+
+macro_rules! resolve_cached {
+    ($self:ident, $ids:ident, $field:ident, $resolve:ident) => {{
+        let (mut resolved, missing_ids) = $self.cached_entries($ids, |cache| &mut cache.$field);
+        if !missing_ids.is_empty() {
+            let fetched = $self.resolver.$resolve(&missing_ids).await?;
+            resolved.extend($self.cache_entries(fetched, |cache| &mut cache.$field));
+        }
+        Ok(resolved)
+    }};
+}
+
+#[async_trait]
+impl<R> SlackReferenceResolver for CachedSlackReferenceResolver<R>
+where
+    R: SlackReferenceResolver,
+{
+    type Error = R::Error;
+
+    async fn resolve_users(
+        &self,
+        user_ids: &[SlackUserId],
+    ) -> Result<HashMap<SlackUserId, Option<String>>, Self::Error> {
+        resolve_cached!(self, user_ids, users, resolve_users)
+    }
+
+    async fn resolve_channels(
+        &self,
+        channel_ids: &[SlackChannelId],
+    ) -> Result<HashMap<SlackChannelId, Option<String>>, Self::Error> {
+        resolve_cached!(self, channel_ids, channels, resolve_channels)
+    }
+
+    async fn resolve_usergroups(
+        &self,
+        usergroup_ids: &[SlackUserGroupId],
+    ) -> Result<HashMap<SlackUserGroupId, Option<String>>, Self::Error> {
+        resolve_cached!(self, usergroup_ids, usergroups, resolve_usergroups)
+    }
+
+    async fn resolve_emojis(
+        &self,
+        emoji_names: &[SlackEmojiName],
+    ) -> Result<HashMap<SlackEmojiName, Option<SlackEmojiRef>>, Self::Error> {
+        resolve_cached!(self, emoji_names, emojis, resolve_emojis)
+    }
+
+    async fn resolve_teams(
+        &self,
+        team_ids: &[SlackTeamId],
+    ) -> Result<HashMap<SlackTeamId, Option<String>>, Self::Error> {
+        resolve_cached!(self, team_ids, teams, resolve_teams)
+    }
+
+    async fn resolve_user_profiles(
+        &self,
+        user_ids: &[SlackUserId],
+    ) -> Result<HashMap<SlackUserId, Option<SlackUserReference>>, Self::Error> {
+        resolve_cached!(self, user_ids, user_profiles, resolve_user_profiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, sync::Arc};
+
+    use chrono::DateTime;
+
+    use super::*;
+    use crate::{
+        references::SlackReferenceKind,
+        resolver::{resolve_slack_references, InMemorySlackReferenceResolver},
+    };
+
+    fn requested_references() -> SlackReferences {
+        SlackReferences {
+            users: HashMap::from([
+                (SlackUserId("U1".to_string()), None),
+                (SlackUserId("U404".to_string()), None),
+            ]),
+            ..SlackReferences::default()
+        }
+    }
+
+    fn known_references() -> SlackReferences {
+        SlackReferences {
+            users: HashMap::from([(SlackUserId("U1".to_string()), Some("alice".to_string()))]),
+            ..SlackReferences::default()
+        }
+    }
+
+    type UsersResult = Result<HashMap<SlackUserId, Option<String>>, ()>;
+
+    /// Resolver answering user lookups with scripted results, one per call.
+    #[derive(Default)]
+    struct ScriptedResolver {
+        results: Mutex<VecDeque<UsersResult>>,
+        requests: Mutex<Vec<Vec<SlackUserId>>>,
+    }
+
+    impl ScriptedResolver {
+        fn new(results: Vec<UsersResult>) -> Self {
+            ScriptedResolver {
+                results: Mutex::new(results.into()),
+                ..ScriptedResolver::default()
+            }
+        }
+
+        fn requests(&self) -> Vec<Vec<SlackUserId>> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl SlackReferenceResolver for ScriptedResolver {
+        type Error = ();
+
+        async fn resolve_users(&self, user_ids: &[SlackUserId]) -> UsersResult {
+            let mut user_ids = user_ids.to_vec();
+            user_ids.sort_by(|a, b| a.0.cmp(&b.0));
+            self.requests.lock().unwrap().push(user_ids);
+            self.results
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or(Ok(HashMap::new()))
+        }
+
+        async fn resolve_channels(
+            &self,
+            _channel_ids: &[SlackChannelId],
+        ) -> Result<HashMap<SlackChannelId, Option<String>>, ()> {
+            Ok(HashMap::new())
+        }
+
+        async fn resolve_usergroups(
+            &self,
+            _usergroup_ids: &[SlackUserGroupId],
+        ) -> Result<HashMap<SlackUserGroupId, Option<String>>, ()> {
+            Ok(HashMap::new())
+        }
+
+        async fn resolve_emojis(
+            &self,
+            _emoji_names: &[SlackEmojiName],
+        ) -> Result<HashMap<SlackEmojiName, Option<SlackEmojiRef>>, ()> {
+            Ok(HashMap::new())
+        }
+
+        async fn resolve_teams(
+            &self,
+            _team_ids: &[SlackTeamId],
+        ) -> Result<HashMap<SlackTeamId, Option<String>>, ()> {
+            Ok(HashMap::new())
+        }
+    }
+
+    fn user_ids(ids: &[&str]) -> Vec<SlackUserId> {
+        ids.iter().map(|id| SlackUserId(id.to_string())).collect()
+    }
+
+    #[test]
+    fn test_slack_references_skips_expired_and_unresolved_entries() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let ttl = SlackReferencesCacheTtl {
+            resolved: Duration::from_secs(60),
+            unresolved: Duration::from_secs(10),
+        };
+        let mut cache = SlackReferencesCache::new();
+        cache.insert(
+            &SlackReferences {
+                users: HashMap::from([
+                    (SlackUserId("U1".to_string()), Some("alice".to_string())),
+                    (SlackUserId("U404".to_string()), None),
+                ]),
+                ..SlackReferences::default()
+            },
+            ttl,
+            now,
+        );
+
+        assert_eq!(cache.slack_references(now), known_references());
+        assert_eq!(
+            cache.slack_references(now + Duration::from_secs(60)),
+            SlackReferences::default()
+        );
+        // Unresolved entries are only negatively cached by the cached resolver
+        assert_eq!(cache.users.len(), 1);
+
+        cache.remove_expired(now + Duration::from_secs(30));
+        assert_eq!(cache.users.len(), 1);
+        cache.remove_expired(now + Duration::from_secs(60));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_insert_with_ttl_overflowing_the_clock() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let ttl = SlackReferencesCacheTtl {
+            resolved: Duration::MAX,
+            unresolved: Duration::MAX,
+        };
+        let mut cache = SlackReferencesCache::new();
+        cache.insert(&known_references(), ttl, now);
+
+        assert_eq!(
+            cache.slack_references(now + Duration::from_secs(100 * 365 * 24 * 3600)),
+            known_references()
+        );
+    }
+
+    #[test]
+    fn test_insert_does_not_overwrite_resolved_entries() {
+        let now = SystemTime::now();
+        let mut cache = SlackReferencesCache::new();
+        cache.insert(&known_references(), SlackReferencesCacheTtl::default(), now);
+        // References found in messages but not resolved yet
+        cache.insert(
+            &requested_references(),
+            SlackReferencesCacheTtl::default(),
+            now,
+        );
+
+        assert_eq!(cache.slack_references(now), known_references());
+    }
+
+    #[test]
+    fn test_cache_user_profiles() {
+        let now = SystemTime::now();
+        let slack_references = SlackReferences {
+            user_profiles: HashMap::from([(
+                SlackUserId("U1".to_string()),
+                SlackUserReference {
+                    real_name: Some("Alice Smith".to_string()),
+                    avatar_url: Some("https://avatars.example.com/alice.png".to_string()),
+                    deleted: true,
+                    bot: true,
+                    ..SlackUserReference::default()
+                },
+            )]),
+            ..known_references()
+        };
+        let mut cache = SlackReferencesCache::new();
+        cache.insert(&slack_references, SlackReferencesCacheTtl::default(), now);
+        assert_eq!(cache.slack_references(now), slack_references);
+
+        // The profiles, with their flags, round-trip through JSON
+        let json = serde_json::to_string(&cache).unwrap();
+        let loaded: SlackReferencesCache = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.slack_references(now), slack_references);
+    }
+
+    #[test]
+    fn test_extend_with_cached_references() {
+        let now = SystemTime::now();
+        let mut cache = SlackReferencesCache::new();
+        cache.insert(&known_references(), SlackReferencesCacheTtl::default(), now);
+
+        let mut slack_references = requested_references();
+        slack_references.extend(cache.slack_references(now));
+
+        assert_eq!(
+            slack_references.users,
+            HashMap::from([
+                (SlackUserId("U1".to_string()), Some("alice".to_string())),
+                (SlackUserId("U404".to_string()), None),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!(
+            "slack-blocks-render-cache-{}.json",
+            std::process::id()
+        ));
+        let mut cache = SlackReferencesCache::new();
+        cache.insert(
+            &SlackReferences {
+                emojis: HashMap::from([(
+                    SlackEmojiName("partyparrot".to_string()),
+                    Some(SlackEmojiRef::Url(
+                        "https://emoji.example.com/partyparrot.gif".parse().unwrap(),
+                    )),
+                )]),
+                ..requested_references()
+            },
+            SlackReferencesCacheTtl::default(),
+            SystemTime::now(),
+        );
+
+        cache.save(&path).unwrap();
+        let loaded = SlackReferencesCache::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, cache);
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let path = std::env::temp_dir().join("slack-blocks-render-missing-cache.json");
+        assert_eq!(
+            SlackReferencesCache::load(path).unwrap(),
+            SlackReferencesCache::new()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cached_resolver_does_not_resolve_cached_ids_again() {
+        let resolver = CachedSlackReferenceResolver::new(
+            InMemorySlackReferenceResolver::new(known_references()),
+            SlackReferencesCache::new(),
+            SlackReferencesCacheTtl::default(),
+        );
+
+        let first = resolve_slack_references(&requested_references(), &resolver, 10)
+            .await
+            .unwrap();
+        let second = resolve_slack_references(&requested_references(), &resolver, 10)
+            .await
+            .unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(
+            second.users,
+            HashMap::from([
+                (SlackUserId("U1".to_string()), Some("alice".to_string())),
+                (SlackUserId("U404".to_string()), None),
+            ])
+        );
+        // The unresolvable U404 is negatively cached: the inner resolver is only called once
+        assert_eq!(
+            resolver.resolver.batches(),
+            vec![
+                (SlackReferenceKind::User, 2),
+                (SlackReferenceKind::UserProfile, 1)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cached_resolver_keeps_user_flags() {
+        let profile = SlackUserReference {
+            real_name: Some("Deploy Bot".to_string()),
+            deleted: true,
+            bot: true,
+            ..SlackUserReference::default()
+        };
+        let resolver = CachedSlackReferenceResolver::new(
+            InMemorySlackReferenceResolver::new(SlackReferences {
+                user_profiles: HashMap::from([(SlackUserId("U1".to_string()), profile.clone())]),
+                ..known_references()
+            }),
+            SlackReferencesCache::new(),
+            SlackReferencesCacheTtl::default(),
+        );
+
+        for _ in 0..2 {
+            let resolved = resolve_slack_references(&requested_references(), &resolver, 10)
+                .await
+                .unwrap();
+            assert_eq!(
+                resolved.user_profiles,
+                HashMap::from([(SlackUserId("U1".to_string()), profile.clone())])
+            );
+        }
+        assert_eq!(
+            resolver
+                .into_cache()
+                .slack_references(SystemTime::now())
+                .user_profiles,
+            HashMap::from([(SlackUserId("U1".to_string()), profile)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cached_resolver_resolves_expired_ids_again() {
+        let resolver = CachedSlackReferenceResolver::new(
+            InMemorySlackReferenceResolver::new(known_references()),
+            SlackReferencesCache::new(),
+            SlackReferencesCacheTtl {
+                resolved: Duration::from_secs(3600),
+                unresolved: Duration::ZERO,
+            },
+        );
+
+        resolve_slack_references(&requested_references(), &resolver, 10)
+            .await
+            .unwrap();
+        resolve_slack_references(&requested_references(), &resolver, 10)
+            .await
+            .unwrap();
+
+        // U1 has no profile either: it is requested again as well
+        assert_eq!(
+            resolver.resolver.batches(),
+            vec![
+                (SlackReferenceKind::User, 2),
+                (SlackReferenceKind::UserProfile, 1),
+                (SlackReferenceKind::User, 1),
+                (SlackReferenceKind::UserProfile, 1)
+            ]
+        );
+        assert_eq!(resolver.into_cache().users.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cached_resolver_expires_entries_with_its_clock() {
+        let now = Arc::new(Mutex::new(
+            DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        ));
+        let clock_now = now.clone();
+        let resolver = CachedSlackReferenceResolver::new(
+            InMemorySlackReferenceResolver::new(known_references()),
+            SlackReferencesCache::new(),
+            SlackReferencesCacheTtl {
+                resolved: Duration::from_secs(24 * 3600),
+                unresolved: Duration::from_secs(3600),
+            },
+        )
+        .with_clock(Clock::new(move || *clock_now.lock().unwrap()));
+
+        resolve_slack_references(&requested_references(), &resolver, 10)
+            .await
+            .unwrap();
+        *now.lock().unwrap() += chrono::Duration::minutes(30);
+        resolve_slack_references(&requested_references(), &resolver, 10)
+            .await
+            .unwrap();
+        // The negative entries of U404 and of the profile of U1 expire after an hour, the name
+        // of U1 is still cached
+        *now.lock().unwrap() += chrono::Duration::hours(1);
+        resolve_slack_references(&requested_references(), &resolver, 10)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            resolver.resolver.batches(),
+            vec![
+                (SlackReferenceKind::User, 2),
+                (SlackReferenceKind::UserProfile, 1),
+                (SlackReferenceKind::User, 1),
+                (SlackReferenceKind::UserProfile, 1)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cached_resolver_only_caches_confirmed_not_found_ids() {
+        let resolver = CachedSlackReferenceResolver::new(
+            ScriptedResolver::new(vec![
+                // U404 could not be looked up this time, e.g. after a transient failure
+                Ok(HashMap::from([(
+                    SlackUserId("U1".to_string()),
+                    Some("alice".to_string()),
+                )])),
+                Ok(HashMap::from([(SlackUserId("U404".to_string()), None)])),
+            ]),
+            SlackReferencesCache::new(),
+            SlackReferencesCacheTtl::default(),
+        );
+
+        for _ in 0..3 {
+            resolver
+                .resolve_users(&user_ids(&["U1", "U404"]))
+                .await
+                .unwrap();
+        }
+
+        // U404 is requested again until it is confirmed as not found
+        assert_eq!(
+            resolver.resolver.requests(),
+            vec![user_ids(&["U1", "U404"]), user_ids(&["U404"])]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cached_resolver_does_not_cache_errors() {
+        let resolver = CachedSlackReferenceResolver::new(
+            ScriptedResolver::new(vec![
+                Err(()),
+                Ok(HashMap::from([
+                    (SlackUserId("U1".to_string()), Some("alice".to_string())),
+                    (SlackUserId("U404".to_string()), None),
+                ])),
+            ]),
+            SlackReferencesCache::new(),
+            SlackReferencesCacheTtl::default(),
+        );
+
+        assert_eq!(
+            resolver.resolve_users(&user_ids(&["U1", "U404"])).await,
+            Err(())
+        );
+        let resolved = resolver
+            .resolve_users(&user_ids(&["U1", "U404"]))
+            .await
+            .unwrap();
+
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(
+            resolver.resolver.requests(),
+            vec![user_ids(&["U1", "U404"]), user_ids(&["U1", "U404"])]
+        );
+        assert_eq!(resolver.into_cache().users.len(), 2);
+    }
+}
@@ -0,0 +1,538 @@
+use slack_morphism::prelude::*;
+
+use crate::{
+    options::{EmojiPolicy, RenderOptions},
+    references::SlackReferences,
+};
+
+/// Emoji rendered according to an [`EmojiPolicy`], before being written in an output format.
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) enum RenderedEmoji {
+    /// Unicode characters, to escape in the output format.
+    Text(String),
+    /// Name of an emoji to render as its `:shortcode:`.
+    Shortcode(String),
+    /// Image of the emoji named `name`.
+    Image { url: String, name: String },
+    /// Output of an [`EmojiCallback`](crate::options::EmojiCallback), inserted as is.
+    Raw(String),
+}
+
+enum ResolvedEmoji<'a> {
+    Standard(&'static str),
+    Custom {
+        name: &'a SlackEmojiName,
+        url: &'a url::Url,
+    },
+    Unknown,
+    /// Alias that loops or points to an unknown emoji.
+    BrokenAlias,
+}
+
+pub(crate) fn render_emoji(
+    emoji_name: &SlackEmojiName,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> RenderedEmoji {
+    let shortcode = || RenderedEmoji::Shortcode(emoji_name.0.clone());
+    if options.emoji_policy == EmojiPolicy::Shortcode {
+        return shortcode();
+    }
+
+    let resolved = resolve_emoji(emoji_name, slack_references);
+    if let EmojiPolicy::Custom(callback) = &options.emoji_policy {
+        let unicode = match resolved {
+            ResolvedEmoji::Standard(unicode) => Some(unicode),
+            _ => None,
+        };
+        if let Some(rendered) = (callback.0)(emoji_name, unicode) {
+            return RenderedEmoji::Raw(rendered);
+        }
+    }
+    match resolved {
+        ResolvedEmoji::Standard(unicode) => match &options.emoji_policy {
+            EmojiPolicy::Image(cdn) => RenderedEmoji::Image {
+                url: cdn.image_url(unicode),
+                name: emoji_name.0.clone(),
+            },
+            _ => RenderedEmoji::Text(unicode.to_string()),
+        },
+        ResolvedEmoji::Custom { name, url } => RenderedEmoji::Image {
+            url: url.to_string(),
+            name: name.0.clone(),
+        },
+        ResolvedEmoji::Unknown | ResolvedEmoji::BrokenAlias => shortcode(),
+    }
+}
+
+/// Unicode characters of `emoji_name` if it is a standard emoji or an alias of one, whatever the
+/// [`EmojiPolicy`], e.g. to render emoji in text.
+pub(crate) fn find_standard_emoji_with_aliases(
+    emoji_name: &SlackEmojiName,
+    slack_references: &SlackReferences,
+) -> Option<&'static str> {
+    match resolve_emoji(emoji_name, slack_references) {
+        ResolvedEmoji::Standard(unicode) => Some(unicode),
+        _ => None,
+    }
+}
+
+fn resolve_emoji<'a>(
+    emoji_name: &'a SlackEmojiName,
+    slack_references: &'a SlackReferences,
+) -> ResolvedEmoji<'a> {
+    let mut visited = Vec::new();
+    let mut current = emoji_name;
+    loop {
+        match slack_references.emojis.get(current) {
+            Some(Some(SlackEmojiRef::Alias(alias))) => {
+                visited.push(current);
+                if visited.contains(&alias) {
+                    return ResolvedEmoji::BrokenAlias;
+                }
+                current = alias;
+            }
+            Some(Some(SlackEmojiRef::Url(url))) => {
+                return ResolvedEmoji::Custom { name: current, url };
+            }
+            _ => {
+                return match find_standard_emoji(&current.0) {
+                    Some(emoji) => ResolvedEmoji::Standard(emoji),
+                    None if visited.is_empty() => ResolvedEmoji::Unknown,
+                    None => ResolvedEmoji::BrokenAlias,
+                };
+            }
+        }
+    }
+}
+
+/// Whether `emoji_name` is an alias that loops or points to an unknown emoji.
+pub(crate) fn is_broken_alias(
+    emoji_name: &SlackEmojiName,
+    slack_references: &SlackReferences,
+) -> bool {
+    matches!(
+        resolve_emoji(emoji_name, slack_references),
+        ResolvedEmoji::BrokenAlias
+    )
+}
+
+/// Slack shortcodes of standard emoji that differ from the GitHub ones known to `emojis`.
+/// Gendered names like `man-shrugging` and flags like `flag-fr` are handled by
+/// [`find_standard_emoji`] instead.
+const SLACK_SHORTCODES: &[(&str, &str)] = &[
+    ("simple_smile", "🙂"),
+    ("slightly_smiling_face", "🙂"),
+    ("thinking_face", "🤔"),
+    ("hugging_face", "🤗"),
+    ("face_with_rolling_eyes", "🙄"),
+    ("robot_face", "🤖"),
+    ("white_frowning_face", "☹️"),
+    ("rolling_on_the_floor_laughing", "🤣"),
+    ("star-struck", "🤩"),
+    ("grinning_face_with_star_eyes", "🤩"),
+    ("grinning_face_with_one_large_and_one_small_eye", "🤪"),
+    ("face_with_symbols_on_mouth", "🤬"),
+    ("serious_face_with_symbols_covering_mouth", "🤬"),
+    ("face_with_hand_over_mouth", "🤭"),
+    (
+        "smiling_face_with_smiling_eyes_and_hand_covering_mouth",
+        "🤭",
+    ),
+    ("face_with_finger_covering_closed_lips", "🤫"),
+    ("face_vomiting", "🤮"),
+    ("face_with_open_mouth_vomiting", "🤮"),
+    ("shocked_face_with_exploding_head", "🤯"),
+    ("face_with_monocle", "🧐"),
+    ("face_with_cowboy_hat", "🤠"),
+    ("face_palm", "🤦"),
+    ("hand_with_index_and_middle_fingers_crossed", "🤞"),
+    ("the_horns", "🤘"),
+    ("sign_of_the_horns", "🤘"),
+    ("spock-hand", "🖖"),
+    ("reversed_hand_with_middle_finger_extended", "🖕"),
+    ("left-facing_fist", "🤛"),
+    ("right-facing_fist", "🤜"),
+    ("heavy_heart_exclamation_mark_ornament", "❣️"),
+    ("male-technologist", "👨‍💻"),
+    ("female-technologist", "👩‍💻"),
+    ("male-detective", "🕵️‍♂️"),
+    ("female-detective", "🕵️‍♀️"),
+    ("sleuth_or_spy", "🕵️"),
+    ("man_in_business_suit_levitating", "🕴️"),
+    ("weight_lifter", "🏋️"),
+    ("golfer", "🏌️"),
+    ("man-woman-boy", "👨‍👩‍👦"),
+    ("eye-in-speech-bubble", "👁️‍🗨️"),
+    ("speaking_head_in_silhouette", "🗣️"),
+    ("spiral_calendar_pad", "🗓️"),
+    ("spiral_note_pad", "🗒️"),
+    ("lower_left_ballpoint_pen", "🖊️"),
+    ("lower_left_fountain_pen", "🖋️"),
+    ("lower_left_paintbrush", "🖌️"),
+    ("lower_left_crayon", "🖍️"),
+    ("linked_paperclips", "🖇️"),
+    ("three_button_mouse", "🖱️"),
+    ("frame_with_picture", "🖼️"),
+    ("film_frames", "🎞️"),
+    ("admission_tickets", "🎟️"),
+    ("sports_medal", "🏅"),
+    ("dagger_knife", "🗡️"),
+    ("ballot_box_with_ballot", "🗳️"),
+    ("knife_fork_plate", "🍽️"),
+    ("dove_of_peace", "🕊️"),
+    ("derelict_house_building", "🏚️"),
+    ("house_buildings", "🏘️"),
+    ("racing_motorcycle", "🏍️"),
+    ("satellite_antenna", "📡"),
+    ("staff_of_aesculapius", "⚕️"),
+    ("om_symbol", "🕉️"),
+    ("menorah_with_nine_branches", "🕎"),
+    ("umbrella_with_rain_drops", "☔"),
+    ("mostly_sunny", "🌤️"),
+    ("sun_small_cloud", "🌤️"),
+    ("white_sun_with_small_cloud", "🌤️"),
+    ("barely_sunny", "🌥️"),
+    ("sun_behind_cloud", "🌥️"),
+    ("white_sun_behind_cloud", "🌥️"),
+    ("partly_sunny_rain", "🌦️"),
+    ("white_sun_behind_cloud_with_rain", "🌦️"),
+    ("rain_cloud", "🌧️"),
+    ("snow_cloud", "🌨️"),
+    ("lightning", "🌩️"),
+    ("lightning_cloud", "🌩️"),
+    ("tornado_cloud", "🌪️"),
+    ("thunder_cloud_and_rain", "⛈️"),
+    ("double_vertical_bar", "⏸️"),
+    (
+        "black_right_pointing_double_triangle_with_vertical_bar",
+        "⏭️",
+    ),
+    (
+        "black_left_pointing_double_triangle_with_vertical_bar",
+        "⏮️",
+    ),
+    (
+        "black_right_pointing_triangle_with_double_vertical_bar",
+        "⏯️",
+    ),
+    ("black_circle_for_record", "⏺️"),
+];
+
+/// Part of mrkdwn text.
+#[derive(PartialEq, Debug)]
+pub(crate) enum MrkdwnPart<'a> {
+    Text(&'a str),
+    /// Name of an emoji `:shortcode:`.
+    Emoji(&'a str),
+}
+
+/// Split mrkdwn text around its emoji `:shortcode:`s. Like in the HTML renderer, shortcodes in
+/// code and in `<...>` tokens are left in the text.
+pub(crate) fn split_mrkdwn_emojis(text: &str) -> Vec<MrkdwnPart<'_>> {
+    let mut parts = vec![];
+    let mut in_code = false;
+    let mut text_start = 0;
+    let mut position = 0;
+    while let Some(ch) = text[position..].chars().next() {
+        let after = position + ch.len_utf8();
+        match ch {
+            '`' => in_code = !in_code,
+            '<' if !in_code => {
+                if let Some(end) = text[after..].find('>') {
+                    position = after + end + 1;
+                    continue;
+                }
+            }
+            // `::` and colons after a letter or digit do not start an emoji, e.g. in
+            // `Foo::new::bar` or `10:30:00`
+            ':' if !in_code && text[after..].starts_with(':') => {
+                position = after + 1;
+                continue;
+            }
+            ':' if text[..position].ends_with(|c: char| c.is_ascii_alphanumeric()) => {}
+            ':' if !in_code => {
+                if let Some(end) = text[after..].find(':') {
+                    if is_emoji_name(&text[after..after + end]) {
+                        if text_start < position {
+                            parts.push(MrkdwnPart::Text(&text[text_start..position]));
+                        }
+                        // The skin tone is part of the name, like in rich text
+                        let skin_tone_len = mrkdwn_skin_tone_len(&text[after + end + 1..]);
+                        let end = end + skin_tone_len.unwrap_or_default();
+                        parts.push(MrkdwnPart::Emoji(&text[after..after + end]));
+                        position = after + end + 1;
+                        text_start = position;
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        position = after;
+    }
+    if text_start < text.len() {
+        parts.push(MrkdwnPart::Text(&text[text_start..]));
+    }
+    parts
+}
+
+/// Length of the `:skin-tone-N:` shortcode at the start of `text`, which is part of the emoji
+/// right before it in mrkdwn, like in `:+1::skin-tone-3:`.
+pub(crate) fn mrkdwn_skin_tone_len(text: &str) -> Option<usize> {
+    let skin_tone = text.strip_prefix(":skin-tone-")?;
+    let digits = skin_tone.find(|c: char| !c.is_ascii_digit())?;
+    (digits > 0 && skin_tone[digits..].starts_with(':')).then_some(":skin-tone-".len() + digits + 1)
+}
+
+/// Whether `name` can be the name of an emoji `:shortcode:`: Slack emoji names only have
+/// lowercase letters, digits, `_`, `+`, `'` and `-`.
+pub(crate) fn is_emoji_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| matches!(c, 'a'..='z' | '0'..='9' | '_' | '+' | '\'' | '-'))
+}
+
+/// Find a standard emoji by its Slack or GitHub shortcode, with an optional `::skin-tone-N`
+/// suffix.
+pub(crate) fn find_standard_emoji(name: &str) -> Option<&'static str> {
+    let splitted = name.split("::skin-tone-").collect::<Vec<&str>>();
+    let emoji = get_by_slack_shortcode(splitted.first()?)?;
+    let skinned_emoji = splitted
+        .get(1)
+        .and_then(|s| s.parse::<usize>().ok())
+        .and_then(|skin_tone| emoji.skin_tones()?.nth(skin_tone.checked_sub(1)?));
+    Some(skinned_emoji.unwrap_or(emoji).as_str())
+}
+
+fn get_by_slack_shortcode(shortcode: &str) -> Option<&'static emojis::Emoji> {
+    if let Some(emoji) = emojis::get_by_shortcode(shortcode) {
+        return Some(emoji);
+    }
+    if let Some((_, emoji)) = SLACK_SHORTCODES.iter().find(|(name, _)| *name == shortcode) {
+        return emojis::get(emoji);
+    }
+    if let Some(country_code) = shortcode.strip_prefix("flag-") {
+        return emojis::get_by_shortcode(country_code);
+    }
+    if shortcode.contains('-') {
+        return emojis::get_by_shortcode(&shortcode.replace('-', "_"));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::options::{EmojiCallback, EmojiImageCdn};
+
+    #[test]
+    fn test_split_mrkdwn_emojis() {
+        assert_eq!(
+            split_mrkdwn_emojis("Ship :rocket: at 10:30 `:x:` <https://a.com/:b:|:c:>:tada:"),
+            vec![
+                MrkdwnPart::Text("Ship "),
+                MrkdwnPart::Emoji("rocket"),
+                MrkdwnPart::Text(" at 10:30 `:x:` <https://a.com/:b:|:c:>"),
+                MrkdwnPart::Emoji("tada"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_mrkdwn_emojis_skips_non_emoji_names() {
+        assert_eq!(
+            split_mrkdwn_emojis("Call Foo::new::bar, :a\nb: or :a.b: at 10:30:00"),
+            vec![MrkdwnPart::Text(
+                "Call Foo::new::bar, :a\nb: or :a.b: at 10:30:00"
+            )]
+        );
+        assert_eq!(
+            split_mrkdwn_emojis(":Tada: :white_check_mark: :+1: :don't:"),
+            vec![
+                MrkdwnPart::Text(":Tada: "),
+                MrkdwnPart::Emoji("white_check_mark"),
+                MrkdwnPart::Text(" "),
+                MrkdwnPart::Emoji("+1"),
+                MrkdwnPart::Text(" "),
+                MrkdwnPart::Emoji("don't"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_mrkdwn_emojis_with_skin_tones() {
+        assert_eq!(
+            split_mrkdwn_emojis(":+1::skin-tone-3: :man-facepalming::skin-tone-2:: :skin-tone-2:"),
+            vec![
+                MrkdwnPart::Emoji("+1::skin-tone-3"),
+                MrkdwnPart::Text(" "),
+                MrkdwnPart::Emoji("man-facepalming::skin-tone-2"),
+                MrkdwnPart::Text(": "),
+                MrkdwnPart::Emoji("skin-tone-2"),
+            ]
+        );
+        assert_eq!(
+            split_mrkdwn_emojis(":wave: :skin-tone-3:"),
+            vec![
+                MrkdwnPart::Emoji("wave"),
+                MrkdwnPart::Text(" "),
+                MrkdwnPart::Emoji("skin-tone-3")
+            ]
+        );
+    }
+
+    fn alias(name: &str) -> Option<SlackEmojiRef> {
+        Some(SlackEmojiRef::Alias(SlackEmojiName(name.to_string())))
+    }
+
+    fn slack_references() -> SlackReferences {
+        SlackReferences {
+            emojis: HashMap::from([
+                (SlackEmojiName("ping".to_string()), alias("pong")),
+                (SlackEmojiName("pong".to_string()), alias("ping")),
+                (SlackEmojiName("self".to_string()), alias("self")),
+                (SlackEmojiName("yes".to_string()), alias("thumbsup")),
+                (SlackEmojiName("gone".to_string()), alias("deleted")),
+                (
+                    SlackEmojiName("partyparrot".to_string()),
+                    Some(SlackEmojiRef::Url(
+                        "https://emoji.example.com/partyparrot.gif".parse().unwrap(),
+                    )),
+                ),
+                (
+                    SlackEmojiName("parrot".to_string()),
+                    Some(SlackEmojiRef::Alias(SlackEmojiName(
+                        "partyparrot".to_string(),
+                    ))),
+                ),
+            ]),
+            ..SlackReferences::default()
+        }
+    }
+
+    fn render(name: &str, emoji_policy: EmojiPolicy) -> RenderedEmoji {
+        render_emoji(
+            &SlackEmojiName(name.to_string()),
+            &slack_references(),
+            &RenderOptions {
+                emoji_policy,
+                ..RenderOptions::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_find_standard_emoji() {
+        assert_eq!(find_standard_emoji("wave"), Some("👋"));
+        assert_eq!(find_standard_emoji("wave::skin-tone-2"), Some("👋🏻"));
+        assert_eq!(find_standard_emoji("wave::skin-tone-0"), Some("👋"));
+        assert_eq!(find_standard_emoji("partyparrot"), None);
+    }
+
+    #[test]
+    fn test_find_slack_shortcodes() {
+        assert_eq!(find_standard_emoji("simple_smile"), Some("🙂"));
+        assert_eq!(find_standard_emoji("thinking_face"), Some("🤔"));
+        assert_eq!(find_standard_emoji("+1::skin-tone-3"), Some("👍🏼"));
+        assert_eq!(find_standard_emoji("the_horns::skin-tone-6"), Some("🤘🏿"));
+        assert_eq!(find_standard_emoji("man-shrugging"), Some("🤷‍♂️"));
+        assert_eq!(find_standard_emoji("flag-fr"), Some("🇫🇷"));
+        assert_eq!(find_standard_emoji("not-an-emoji"), None);
+    }
+
+    #[test]
+    fn test_slack_shortcodes_are_known_emoji() {
+        for (name, emoji) in SLACK_SHORTCODES {
+            assert!(emojis::get(emoji).is_some(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_unicode_policy() {
+        assert_eq!(
+            render("wave", EmojiPolicy::Unicode),
+            RenderedEmoji::Text("👋".to_string())
+        );
+        assert_eq!(
+            render("parrot", EmojiPolicy::Unicode),
+            RenderedEmoji::Image {
+                url: "https://emoji.example.com/partyparrot.gif".to_string(),
+                name: "partyparrot".to_string()
+            }
+        );
+        assert_eq!(
+            render("unknown", EmojiPolicy::Unicode),
+            RenderedEmoji::Shortcode("unknown".to_string())
+        );
+    }
+
+    #[test]
+    fn test_alias_resolution() {
+        assert_eq!(
+            render("yes", EmojiPolicy::Unicode),
+            RenderedEmoji::Text("👍".to_string())
+        );
+        for name in ["ping", "pong", "self", "gone"] {
+            assert_eq!(
+                render(name, EmojiPolicy::Unicode),
+                RenderedEmoji::Shortcode(name.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_broken_alias() {
+        let slack_references = slack_references();
+        let is_broken =
+            |name: &str| is_broken_alias(&SlackEmojiName(name.to_string()), &slack_references);
+        assert!(is_broken("ping"));
+        assert!(is_broken("self"));
+        assert!(is_broken("gone"));
+        assert!(!is_broken("yes"));
+        assert!(!is_broken("parrot"));
+        assert!(!is_broken("unknown"));
+    }
+
+    #[test]
+    fn test_shortcode_policy() {
+        assert_eq!(
+            render("wave::skin-tone-2", EmojiPolicy::Shortcode),
+            RenderedEmoji::Shortcode("wave::skin-tone-2".to_string())
+        );
+        assert_eq!(
+            render("parrot", EmojiPolicy::Shortcode),
+            RenderedEmoji::Shortcode("parrot".to_string())
+        );
+    }
+
+    #[test]
+    fn test_image_policy() {
+        assert_eq!(
+            render("wave", EmojiPolicy::Image(EmojiImageCdn::twemoji())),
+            RenderedEmoji::Image {
+                url: "https://cdn.jsdelivr.net/gh/jdecked/twemoji@15.1.0/assets/72x72/1f44b.png"
+                    .to_string(),
+                name: "wave".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        let policy = EmojiPolicy::Custom(EmojiCallback::new(|name, unicode| {
+            unicode.map(|unicode| format!("[{}={unicode}]", name.0))
+        }));
+        assert_eq!(
+            render("wave", policy.clone()),
+            RenderedEmoji::Raw("[wave=👋]".to_string())
+        );
+        assert_eq!(
+            render("unknown", policy),
+            RenderedEmoji::Shortcode("unknown".to_string())
+        );
+    }
+}
@@ -0,0 +1,404 @@
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use serde::{Deserialize, Serialize};
+use slack_morphism::prelude::*;
+
+use crate::options::RenderOptions;
+
+/// File shared in a message.
+///
+/// It can be deserialized from the file objects of the Slack API, or converted from a
+/// slack-morphism `SlackFile`, which lacks the size, thumbnails, snippet preview and mode.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct SlackFileShare {
+    pub id: SlackFileId,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub mimetype: Option<String>,
+    pub filetype: Option<String>,
+    /// `hosted`, `external`, `snippet`, `post` or `quip` (canvases).
+    pub mode: Option<String>,
+    /// Size in bytes.
+    pub size: Option<u64>,
+    pub url_private: Option<String>,
+    pub url_private_download: Option<String>,
+    pub permalink: Option<String>,
+    pub thumb_360: Option<String>,
+    pub thumb_480: Option<String>,
+    pub thumb_720: Option<String>,
+    /// First lines of a snippet.
+    pub preview: Option<String>,
+}
+
+impl From<&SlackFile> for SlackFileShare {
+    fn from(file: &SlackFile) -> Self {
+        SlackFileShare {
+            id: file.id.clone(),
+            name: file.name.clone(),
+            title: file.title.clone(),
+            mimetype: file.mimetype.as_ref().map(|mimetype| mimetype.0.clone()),
+            filetype: file.filetype.as_ref().map(|filetype| filetype.0.clone()),
+            mode: None,
+            size: None,
+            url_private: file.url_private.as_ref().map(|url| url.to_string()),
+            url_private_download: file
+                .url_private_download
+                .as_ref()
+                .map(|url| url.to_string()),
+            permalink: file.permalink.as_ref().map(|url| url.to_string()),
+            thumb_360: None,
+            thumb_480: None,
+            thumb_720: None,
+            preview: None,
+        }
+    }
+}
+
+impl SlackFileShare {
+    fn display_name(&self) -> &str {
+        self.title
+            .as_deref()
+            .or(self.name.as_deref())
+            .unwrap_or(&self.id.0)
+    }
+
+    fn is_image(&self) -> bool {
+        self.mimetype
+            .as_deref()
+            .is_some_and(|mimetype| mimetype.starts_with("image/"))
+    }
+
+    fn snippet(&self) -> Option<&str> {
+        match self.mode.as_deref() {
+            Some("snippet") => self.preview.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// URL of the file, rewritten if it is private.
+    fn url(&self, options: &RenderOptions) -> Option<String> {
+        match self
+            .url_private
+            .as_deref()
+            .or(self.url_private_download.as_deref())
+        {
+            Some(url) => Some(self.rewrite_url(url, options)),
+            None => self.permalink.clone(),
+        }
+    }
+
+    /// URL of the largest thumbnail, rewritten.
+    fn thumbnail_url(&self, options: &RenderOptions) -> Option<String> {
+        self.thumb_720
+            .as_deref()
+            .or(self.thumb_480.as_deref())
+            .or(self.thumb_360.as_deref())
+            .map(|url| self.rewrite_url(url, options))
+    }
+
+    fn rewrite_url(&self, url: &str, options: &RenderOptions) -> String {
+        match &options.file_url_rewriter {
+            Some(rewriter) => rewriter.rewrite(&self.id, url),
+            None => url.to_string(),
+        }
+    }
+}
+
+/// Render shared files as Markdown: images inline, snippets as fenced code and other files as
+/// links with their size.
+pub fn render_files_as_markdown(files: &[SlackFileShare], options: &RenderOptions) -> String {
+    files
+        .iter()
+        .map(|file| {
+            let name = file.display_name();
+            if let Some(snippet) = file.snippet() {
+                let filetype = file.filetype.as_deref().unwrap_or_default();
+                let fence = code_fence(snippet);
+                return format!("{fence}{filetype}\n{snippet}\n{fence}");
+            }
+            let name = escape_link_text(name);
+            let Some(url) = file.url(options) else {
+                return name;
+            };
+            if file.is_image() {
+                return match file.thumbnail_url(options) {
+                    Some(thumbnail_url) => format!("[![{name}]({thumbnail_url})]({url})"),
+                    None => format!("![{name}]({url})"),
+                };
+            }
+            match file.size {
+                Some(size) => format!("[{name}]({url}) ({})", format_file_size(size)),
+                None => format!("[{name}]({url})"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render shared files as HTML: images inline, snippets as code blocks and other files as
+/// links with their size.
+pub fn render_files_as_html(files: &[SlackFileShare], options: &RenderOptions) -> String {
+    files
+        .iter()
+        .map(|file| {
+            let name = file.display_name();
+            // Matrix HTML has no classes and only allows images from `mxc://` URLs
+            let matrix = options.matrix.is_some();
+            let file_class = if matrix { "" } else { " class=\"slack-file\"" };
+            if let Some(snippet) = file.snippet() {
+                let class = file
+                    .filetype
+                    .as_deref()
+                    .map(|filetype| {
+                        format!(
+                            " class=\"language-{}\"",
+                            encode_double_quoted_attribute(filetype)
+                        )
+                    })
+                    .unwrap_or_default();
+                return format!(
+                    "<pre{file_class}><code{class}>{}\n</code></pre>\n",
+                    encode_text(snippet)
+                );
+            }
+            let Some(url) = file.url(options) else {
+                return format!("<p{file_class}>{}</p>\n", encode_text(name));
+            };
+            if file.is_image() && !matrix {
+                let src = file.thumbnail_url(options).unwrap_or_else(|| url.clone());
+                return format!(
+                    "<p class=\"slack-file\"><a href=\"{}\"><img class=\"slack-file-image\" src=\"{}\" alt=\"{}\" /></a></p>\n",
+                    encode_double_quoted_attribute(&url),
+                    encode_double_quoted_attribute(&src),
+                    encode_double_quoted_attribute(name)
+                );
+            }
+            let size = file
+                .size
+                .map(|size| format!(" ({})", format_file_size(size)))
+                .unwrap_or_default();
+            format!(
+                "<p{file_class}><a href=\"{}\">{}</a>{size}</p>\n",
+                encode_double_quoted_attribute(&url),
+                encode_text(name)
+            )
+        })
+        .collect()
+}
+
+/// Render shared files as plain text: snippets as their content and other files as their name,
+/// size and URL.
+pub fn render_files_as_text(files: &[SlackFileShare], options: &RenderOptions) -> String {
+    files
+        .iter()
+        .map(|file| {
+            if let Some(snippet) = file.snippet() {
+                return snippet.to_string();
+            }
+            let mut text = file.display_name().to_string();
+            if let Some(size) = file.size {
+                text.push_str(&format!(" ({})", format_file_size(size)));
+            }
+            if let Some(url) = file.url(options) {
+                text.push_str(&format!(": {url}"));
+            }
+            text
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Find the files of a message in the JSON format of the Slack API, with their size,
+/// thumbnails and snippet previews.
+pub fn find_files_in_message(message: &serde_json::Value) -> Vec<SlackFileShare> {
+    let Some(serde_json::Value::Array(files)) = message.get("files") else {
+        return vec![];
+    };
+    files
+        .iter()
+        .filter_map(|file| serde_json::from_value(file.clone()).ok())
+        .collect()
+}
+
+/// Files of a message, converted from the slack-morphism model: they lack their size,
+/// thumbnails, snippet preview and mode, so images are linked and snippets are not shown.
+pub(crate) fn message_files(content: &SlackMessageContent) -> Vec<SlackFileShare> {
+    content
+        .files
+        .iter()
+        .flatten()
+        .map(SlackFileShare::from)
+        .collect()
+}
+
+/// Fence of a Markdown code block, longer than the backtick runs of `code`.
+fn code_fence(code: &str) -> String {
+    let longest_run = code
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or_default();
+    "`".repeat(longest_run.max(2) + 1)
+}
+
+/// Escape the brackets ending Markdown link texts early.
+pub(crate) fn escape_link_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
+
+fn format_file_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if size < 1024 {
+        return format!("{size} B");
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::FileUrlRewriter;
+
+    fn files() -> Vec<SlackFileShare> {
+        serde_json::from_value(serde_json::json!([
+            {
+                "id": "F1",
+                "name": "screenshot.png",
+                "mimetype": "image/png",
+                "filetype": "png",
+                "mode": "hosted",
+                "size": 52000,
+                "url_private": "https://files.slack.com/files-pri/T1-F1/screenshot.png",
+                "thumb_360": "https://files.slack.com/files-tmb/T1-F1/screenshot_360.png"
+            },
+            {
+                "id": "F2",
+                "name": "main.rs",
+                "title": "Main",
+                "filetype": "rust",
+                "mode": "snippet",
+                "preview": "fn main() {}"
+            },
+            {
+                "id": "F3",
+                "name": "report.pdf",
+                "mimetype": "application/pdf",
+                "size": 3500000,
+                "url_private": "https://files.slack.com/files-pri/T1-F3/report.pdf"
+            }
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_format_file_size() {
+        assert_eq!(format_file_size(512), "512 B");
+        assert_eq!(format_file_size(52000), "50.8 KB");
+        assert_eq!(format_file_size(3500000), "3.3 MB");
+    }
+
+    #[test]
+    fn test_from_slack_file() {
+        let file: SlackFile = serde_json::from_value(serde_json::json!({
+            "id": "F1",
+            "name": "report.pdf",
+            "mimetype": "application/pdf",
+            "url_private": "https://files.slack.com/files-pri/T1-F1/report.pdf"
+        }))
+        .unwrap();
+        assert_eq!(
+            render_files_as_text(&[SlackFileShare::from(&file)], &RenderOptions::default()),
+            "report.pdf: https://files.slack.com/files-pri/T1-F1/report.pdf"
+        );
+    }
+
+    #[test]
+    fn test_render_files_as_markdown() {
+        assert_eq!(
+            render_files_as_markdown(&files(), &RenderOptions::default()),
+            "[![screenshot.png](https://files.slack.com/files-tmb/T1-F1/screenshot_360.png)](https://files.slack.com/files-pri/T1-F1/screenshot.png)\n\
+            ```rust\nfn main() {}\n```\n\
+            [report.pdf](https://files.slack.com/files-pri/T1-F3/report.pdf) (3.3 MB)"
+        );
+    }
+
+    #[test]
+    fn test_render_files_as_markdown_escapes_names_and_fences() {
+        let files: Vec<SlackFileShare> = serde_json::from_value(serde_json::json!([
+            {
+                "id": "F1",
+                "name": "notes [draft].txt",
+                "url_private": "https://files.slack.com/files-pri/T1-F1/notes.txt"
+            },
+            {
+                "id": "F2",
+                "name": "README.md",
+                "filetype": "markdown",
+                "mode": "snippet",
+                "preview": "```sh\ncargo test\n```"
+            }
+        ]))
+        .unwrap();
+        assert_eq!(
+            render_files_as_markdown(&files, &RenderOptions::default()),
+            "[notes \\[draft\\].txt](https://files.slack.com/files-pri/T1-F1/notes.txt)\n\
+            ````markdown\n```sh\ncargo test\n```\n````"
+        );
+    }
+
+    #[test]
+    fn test_find_files_in_message() {
+        let files = find_files_in_message(&serde_json::json!({
+            "ts": "1720000000.000100",
+            "files": [
+                { "id": "F1", "name": "report.pdf", "size": 3500000, "mode": "hosted" }
+            ]
+        }));
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].size, Some(3500000));
+        assert_eq!(files[0].mode.as_deref(), Some("hosted"));
+    }
+
+    #[test]
+    fn test_render_files_as_html() {
+        assert_eq!(
+            render_files_as_html(&files(), &RenderOptions::default()),
+            "<p class=\"slack-file\"><a href=\"https://files.slack.com/files-pri/T1-F1/screenshot.png\">\
+            <img class=\"slack-file-image\" src=\"https://files.slack.com/files-tmb/T1-F1/screenshot_360.png\" alt=\"screenshot.png\" /></a></p>\n\
+            <pre class=\"slack-file\"><code class=\"language-rust\">fn main() {}\n</code></pre>\n\
+            <p class=\"slack-file\"><a href=\"https://files.slack.com/files-pri/T1-F3/report.pdf\">report.pdf</a> (3.3 MB)</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_files_as_text() {
+        assert_eq!(
+            render_files_as_text(&files(), &RenderOptions::default()),
+            "screenshot.png (50.8 KB): https://files.slack.com/files-pri/T1-F1/screenshot.png\n\
+            fn main() {}\n\
+            report.pdf (3.3 MB): https://files.slack.com/files-pri/T1-F3/report.pdf"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_private_urls() {
+        let options = RenderOptions {
+            file_url_rewriter: Some(FileUrlRewriter::new(|file_id, url| {
+                let name = url.rsplit('/').next().unwrap_or_default();
+                format!("https://proxy.example.com/{}/{name}", file_id.0)
+            })),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render_files_as_markdown(&files()[..1], &options),
+            "[![screenshot.png](https://proxy.example.com/F1/screenshot_360.png)](https://proxy.example.com/F1/screenshot.png)"
+        );
+    }
+}
@@ -1,9 +1,20 @@
-use html_escape::encode_text;
+use html_escape::{encode_double_quoted_attribute, encode_text};
 use serde_json::Value;
 use slack_morphism::prelude::*;
 
 use crate::{
-    references::SlackReferences,
+    attachment::SlackAttachment,
+    emoji::{self, is_emoji_name, mrkdwn_skin_tone_len, RenderedEmoji},
+    files::render_files_as_html,
+    matrix::matrix_to_url,
+    message::{attachment_color, MessageParts},
+    options::RenderOptions,
+    references::{
+        decode_mrkdwn_entities, mrkdwn_mention_element, mrkdwn_mention_label, SlackReferences,
+    },
+    report::{build_message_render_report, build_render_report, RenderReport},
+    time::{format_rfc3339, format_rich_text_date, format_slack_date_token},
+    unfurl::render_unfurls_as_html,
     visitor::{
         visit_slack_block_image_element, visit_slack_block_mark_down_text,
         visit_slack_block_plain_text, visit_slack_context_block, visit_slack_divider_block,
@@ -21,11 +32,12 @@ pub fn render_slack_mrkdwn_text_as_html(
     default_style_class: &str,
     highlight_style_class: &str,
 ) -> String {
-    let renderer = HtmlRenderer::new(
-        slack_references.clone(),
-        default_style_class.to_string(),
-        highlight_style_class.to_string(),
-    );
+    let options = RenderOptions {
+        default_style_class: default_style_class.to_string(),
+        highlight_style_class: highlight_style_class.to_string(),
+        ..RenderOptions::default()
+    };
+    let renderer = HtmlRenderer::new(slack_references, &options);
     render_slack_mrkdwn_as_html(text, &renderer)
 }
 
@@ -35,46 +47,283 @@ pub fn render_blocks_as_html(
     default_style_class: &str,
     highlight_style_class: &str,
 ) -> String {
-    let mut block_renderer = HtmlRenderer::new(
-        slack_references,
-        default_style_class.to_string(),
-        highlight_style_class.to_string(),
-    );
+    let options = RenderOptions {
+        default_style_class: default_style_class.to_string(),
+        highlight_style_class: highlight_style_class.to_string(),
+        ..RenderOptions::default()
+    };
+    render_blocks_as_html_with_options(&blocks, &slack_references, &options)
+}
+
+/// Render borrowed Slack blocks as HTML.
+/// Neither the blocks nor the references are cloned.
+pub fn render_blocks_as_html_with_options(
+    blocks: &[SlackBlock],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let mut block_renderer = HtmlRenderer::new(slack_references, options);
     for block in blocks {
-        block_renderer.visit_slack_block(&block);
+        block_renderer.visit_slack_block(block);
+    }
+    block_renderer.finish()
+}
+
+/// Render borrowed Slack blocks as HTML, along with the report of what could not be rendered
+/// faithfully.
+pub fn render_blocks_as_html_with_report(
+    blocks: &[SlackBlock],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> (String, RenderReport) {
+    (
+        render_blocks_as_html_with_options(blocks, slack_references, options),
+        build_render_report(blocks, slack_references, options),
+    )
+}
+
+/// Render a Slack message as HTML: its blocks, or its mrkdwn `text` when it has no blocks, then
+/// its files, its legacy attachments with their color bar as a left border and its reactions.
+pub fn render_message_as_html(
+    message: &SlackHistoryMessage,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    render_message_parts_as_html(
+        &MessageParts::new(&message.content),
+        slack_references,
+        options,
+    )
+}
+
+/// Render a Slack message in the JSON format of the Slack API as HTML, like
+/// [`render_message_as_html`] but with the attachments and files read from the raw JSON, and the
+/// unfurls rendered as cards after the attachments.
+pub fn render_message_json_as_html(
+    message: &serde_json::Value,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    render_message_parts_as_html(&MessageParts::from_json(message), slack_references, options)
+}
+
+/// Render a Slack message in the JSON format of the Slack API as HTML, with the report of what
+/// could not be rendered, including the blocks which could not be read.
+pub fn render_message_json_as_html_with_report(
+    message: &serde_json::Value,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> (String, RenderReport) {
+    let message = MessageParts::from_json(message);
+    (
+        render_message_parts_as_html(&message, slack_references, options),
+        build_message_render_report(&message, slack_references, options),
+    )
+}
+
+/// Render the parts of a message.
+fn render_message_parts_as_html(
+    message: &MessageParts,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let mut html = render_blocks_as_html_with_options(&message.blocks, slack_references, options);
+    html.push_str(&render_files_as_html(&message.files, options));
+    html.push_str(&render_attachments_as_html(
+        &message.attachments,
+        slack_references,
+        options,
+    ));
+    html.push_str(&render_unfurls_as_html(
+        &message.unfurls,
+        slack_references,
+        options,
+    ));
+    // Matrix has its own reactions
+    if options.matrix.is_none() {
+        html.push_str(&render_reactions_as_html(
+            &message.reactions,
+            &HtmlRenderer::new(slack_references, options),
+        ));
+    }
+    html
+}
+
+/// Render reactions as `slack-reaction` spans with their emoji and count, and the names of
+/// the reacting users as tooltip.
+fn render_reactions_as_html(reactions: &[SlackReaction], renderer: &HtmlRenderer) -> String {
+    if reactions.is_empty() {
+        return String::new();
+    }
+    let reactions = reactions
+        .iter()
+        .map(|reaction| {
+            let mut users = reaction
+                .users
+                .iter()
+                .map(|user_id| {
+                    renderer
+                        .slack_references
+                        .user_name(user_id, renderer.options.user_display_policy)
+                        .unwrap_or(&user_id.0)
+                        .to_string()
+                })
+                .collect::<Vec<_>>();
+            // Slack only lists the first users of popular reactions
+            let others = reaction.count.saturating_sub(reaction.users.len());
+            match others {
+                0 => {}
+                1 => users.push("1 other".to_string()),
+                _ => users.push(format!("{others} others")),
+            }
+            format!(
+                "<span class=\"slack-reaction\" title=\"{}\">{} {}</span>",
+                encode_double_quoted_attribute(&users.join(", ")),
+                render_emoji(&SlackEmojiName(reaction.name.0.clone()), renderer),
+                reaction.count
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("<p class=\"slack-reactions\">{reactions}</p>\n")
+}
+
+/// Render legacy attachments as HTML: their pretext, then their title, text, fields, blocks, image
+/// and footer in a `slack-attachment` div with their color bar as a left border. Unfurls are left
+/// to [`render_unfurls_as_html`].
+pub fn render_attachments_as_html(
+    attachments: &[SlackAttachment],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    attachments
+        .iter()
+        .filter(|attachment| !attachment.is_unfurl())
+        .map(|attachment| render_attachment_as_html(attachment, slack_references, options))
+        .collect()
+}
+
+fn render_attachment_as_html(
+    attachment: &SlackAttachment,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let renderer = HtmlRenderer::new(slack_references, options);
+    let mut html = String::new();
+    if attachment.is_fallback_only() {
+        if let Some(fallback) = &attachment.fallback {
+            html.push_str(&format!("<p>{}</p>\n", encode_text(fallback)));
+        }
+    }
+    match (&attachment.title, &attachment.title_link) {
+        (Some(title), Some(title_link)) => html.push_str(&format!(
+            "<p><strong>{}</strong></p>\n",
+            render_link_html(title_link, &encode_text(title), renderer.options)
+        )),
+        (Some(title), None) => {
+            html.push_str(&format!("<p><strong>{}</strong></p>\n", encode_text(title)))
+        }
+        (None, _) => {}
     }
-    block_renderer.sub_texts.join("")
+    if let Some(text) = &attachment.text {
+        html.push_str(&format!(
+            "<p>{}</p>\n",
+            render_slack_mrkdwn_as_html(text, &renderer)
+        ));
+    }
+    let field_class = class_attribute("slack-attachment-field", options);
+    for field in attachment.fields.iter().flatten() {
+        let title = field
+            .title
+            .as_ref()
+            .map(|title| format!("<strong>{}</strong><br />", encode_text(title)))
+            .unwrap_or_default();
+        let value = field
+            .value
+            .as_ref()
+            .map(|value| render_slack_mrkdwn_as_html(value, &renderer))
+            .unwrap_or_default();
+        html.push_str(&format!("<div{field_class}>{title}{value}</div>\n"));
+    }
+    if let Some(blocks) = &attachment.blocks {
+        html.push_str(&render_blocks_as_html_with_options(
+            blocks,
+            slack_references,
+            options,
+        ));
+    }
+    if let Some(image) = attachment.image() {
+        html.push_str(&format!(
+            "<p>{}</p>\n",
+            render_image_html(
+                image,
+                attachment.title.as_deref().unwrap_or_default(),
+                renderer.options
+            )
+        ));
+    }
+    if let Some(footer) = &attachment.footer {
+        let footer_class = class_attribute("slack-attachment-footer", options);
+        html.push_str(&format!(
+            "<p{footer_class}>{}</p>\n",
+            render_slack_mrkdwn_as_html(footer, &renderer)
+        ));
+    }
+    // The pretext is shown above the attachment, out of its color bar
+    let pretext = attachment
+        .pretext
+        .as_ref()
+        .map(|pretext| {
+            format!(
+                "<p>{}</p>\n",
+                render_slack_mrkdwn_as_html(pretext, &renderer)
+            )
+        })
+        .unwrap_or_default();
+    if html.is_empty() {
+        return pretext;
+    }
+    // Matrix HTML has no styles: the attachment is quoted instead of having a color bar
+    if options.matrix.is_some() {
+        return format!("{pretext}<blockquote>\n{html}</blockquote>\n");
+    }
+    let style = attachment
+        .color
+        .as_deref()
+        .and_then(attachment_color)
+        .map(|color| format!(" style=\"border-left: 4px solid {color}; padding-left: 8px;\""))
+        .unwrap_or_default();
+    format!("{pretext}<div class=\"slack-attachment\"{style}>\n{html}</div>\n")
 }
 
-struct HtmlRenderer {
+pub(crate) struct HtmlRenderer<'a> {
     pub sub_texts: Vec<String>,
-    pub slack_references: SlackReferences,
-    pub default_style_class: String,
-    pub highlight_style_class: String,
+    pub slack_references: &'a SlackReferences,
+    pub options: &'a RenderOptions,
 }
 
-impl HtmlRenderer {
-    pub fn new(
-        slack_references: SlackReferences,
-        default_style_class: String,
-        highlight_style_class: String,
-    ) -> Self {
+impl<'a> HtmlRenderer<'a> {
+    pub fn new(slack_references: &'a SlackReferences, options: &'a RenderOptions) -> Self {
         HtmlRenderer {
             sub_texts: vec![],
             slack_references,
-            default_style_class,
-            highlight_style_class,
+            options,
         }
     }
+
+    /// A renderer for nested content, with the same references and options.
+    pub fn nested(&self) -> Self {
+        HtmlRenderer::new(self.slack_references, self.options)
+    }
+
+    pub fn finish(self) -> String {
+        self.sub_texts.join("")
+    }
 }
 
-impl Visitor for HtmlRenderer {
+impl Visitor for HtmlRenderer<'_> {
     fn visit_slack_section_block(&mut self, slack_section_block: &SlackSectionBlock) {
-        let mut section_renderer = HtmlRenderer::new(
-            self.slack_references.clone(),
-            self.default_style_class.clone(),
-            self.highlight_style_class.clone(),
-        );
+        let mut section_renderer = self.nested();
         visit_slack_section_block(&mut section_renderer, slack_section_block);
         let content = section_renderer.sub_texts.join("");
         if !content.is_empty() {
@@ -89,11 +338,7 @@ impl Visitor for HtmlRenderer {
     }
 
     fn visit_slack_header_block(&mut self, slack_header_block: &SlackHeaderBlock) {
-        let mut header_renderer = HtmlRenderer::new(
-            self.slack_references.clone(),
-            self.default_style_class.clone(),
-            self.highlight_style_class.clone(),
-        );
+        let mut header_renderer = self.nested();
         visit_slack_header_block(&mut header_renderer, slack_header_block);
         self.sub_texts
             .push(format!("<h2>{}</h2>\n", header_renderer.sub_texts.join("")));
@@ -107,8 +352,12 @@ impl Visitor for HtmlRenderer {
     fn visit_slack_image_block(&mut self, slack_image_block: &SlackImageBlock) {
         if let Some(image_url) = slack_image_block.image_url_or_file.image_url() {
             self.sub_texts.push(format!(
-                "<p><img src=\"{image_url}\" alt=\"{}\" /></p>\n",
-                encode_text(&slack_image_block.alt_text)
+                "<p>{}</p>\n",
+                render_image_html(
+                    image_url.as_str(),
+                    &slack_image_block.alt_text,
+                    self.options
+                )
             ));
         }
         visit_slack_image_block(self, slack_image_block);
@@ -119,9 +368,10 @@ impl Visitor for HtmlRenderer {
         slack_block_image_element: &SlackBlockImageElement,
     ) {
         if let Some(image_url) = slack_block_image_element.image_url_or_file.image_url() {
-            self.sub_texts.push(format!(
-                "<img src=\"{image_url}\" alt=\"{}\" />",
-                encode_text(&slack_block_image_element.alt_text)
+            self.sub_texts.push(render_image_html(
+                image_url.as_str(),
+                &slack_block_image_element.alt_text,
+                self.options,
             ));
         }
         visit_slack_block_image_element(self, slack_block_image_element);
@@ -139,11 +389,7 @@ impl Visitor for HtmlRenderer {
     }
 
     fn visit_slack_context_block(&mut self, slack_context_block: &SlackContextBlock) {
-        let mut section_renderer = HtmlRenderer::new(
-            self.slack_references.clone(),
-            self.default_style_class.clone(),
-            self.highlight_style_class.clone(),
-        );
+        let mut section_renderer = self.nested();
         visit_slack_context_block(&mut section_renderer, slack_context_block);
         let content = section_renderer.sub_texts.join("");
         if !content.is_empty() {
@@ -153,7 +399,7 @@ impl Visitor for HtmlRenderer {
 
     fn visit_slack_rich_text_block(&mut self, slack_rich_text_block: &SlackRichTextBlock) {
         self.sub_texts.push(render_rich_text_block_as_html(
-            slack_rich_text_block.json_value.clone(),
+            &slack_rich_text_block.json_value,
             self,
         ));
     }
@@ -167,7 +413,8 @@ impl Visitor for HtmlRenderer {
         let escaped_title = encode_text(&title);
         if let Some(ref title_url) = slack_video_block.title_url {
             self.sub_texts.push(format!(
-                "<p><em><a target=\"_blank\" rel=\"noopener noreferrer\" href=\"{title_url}\">{escaped_title}</a></em></p>\n"
+                "<p><em>{}</em></p>\n",
+                render_link_html(title_url.as_ref(), &escaped_title, self.options)
             ));
         } else {
             self.sub_texts
@@ -185,9 +432,12 @@ impl Visitor for HtmlRenderer {
         }
 
         self.sub_texts.push(format!(
-            "<p><img src=\"{}\" alt=\"{}\" /></p>\n",
-            slack_video_block.thumbnail_url,
-            encode_text(&slack_video_block.alt_text)
+            "<p>{}</p>\n",
+            render_image_html(
+                slack_video_block.thumbnail_url.as_str(),
+                &slack_video_block.alt_text,
+                self.options
+            )
         ));
 
         visit_slack_video_block(self, slack_video_block);
@@ -211,7 +461,7 @@ struct ListItem {
 }
 
 fn render_rich_text_block_as_html(
-    json_value: serde_json::Value,
+    json_value: &serde_json::Value,
     renderer: &HtmlRenderer,
 ) -> String {
     let Some(serde_json::Value::Array(elements)) = json_value.get("elements") else {
@@ -361,6 +611,41 @@ fn join_html(parts: Vec<(String, Option<StyleSet>)>) -> String {
         .join("")
 }
 
+/// Render an already encoded mention as a link when it has a URL, as a `<span>` otherwise.
+/// Matrix HTML has no classes, so highlighted mentions are colored with `data-mx-color` instead.
+fn render_mention_html(
+    content: &str,
+    style_class: &str,
+    highlighted: bool,
+    attributes: &str,
+    url: Option<&str>,
+    renderer: &HtmlRenderer,
+) -> String {
+    if let Some(matrix) = &renderer.options.matrix {
+        let content = match &matrix.highlight_color {
+            Some(color) if highlighted => format!(
+                "<font data-mx-color=\"{}\">{content}</font>",
+                encode_double_quoted_attribute(color)
+            ),
+            _ => content.to_string(),
+        };
+        return match url {
+            Some(url) => format!(
+                "<a href=\"{}\">{content}</a>",
+                encode_double_quoted_attribute(url)
+            ),
+            None => content,
+        };
+    }
+    match url {
+        Some(url) => format!(
+            "<a class=\"{style_class}\" href=\"{}\"{attributes}>{content}</a>",
+            encode_double_quoted_attribute(url)
+        ),
+        None => format!("<span class=\"{style_class}\"{attributes}>{content}</span>"),
+    }
+}
+
 fn render_rich_text_section_element(
     element: &serde_json::Value,
     renderer: &HtmlRenderer,
@@ -378,48 +663,113 @@ fn render_rich_text_section_element(
             let Some(serde_json::Value::String(channel_id)) = element.get("channel_id") else {
                 return (String::new(), None);
             };
-            let channel_rendered = if let Some(Some(channel_name)) = renderer
-                .slack_references
-                .channels
-                .get(&SlackChannelId(channel_id.clone()))
-            {
-                channel_name
-            } else {
-                channel_id
+            let slack_channel_id = SlackChannelId(channel_id.clone());
+            let channel_rendered = match renderer.slack_references.channels.get(&slack_channel_id) {
+                Some(Some(channel_name)) => Some(format!("#{channel_name}")),
+                _ => mrkdwn_mention_label(element, '#').or_else(|| {
+                    renderer
+                        .options
+                        .unresolved_mention_placeholders
+                        .channel(&slack_channel_id)
+                }),
+            };
+            let Some(channel_rendered) = channel_rendered else {
+                return (String::new(), None);
             };
             let style = element.get("style");
             let styles = StyleSet::from_style(style);
-            (format!("#{}", encode_text(channel_rendered)), Some(styles))
+            let channel_rendered = encode_text(&channel_rendered).to_string();
+            let highlighted = renderer
+                .slack_references
+                .is_channel_highlighted(&slack_channel_id);
+            let channel_url = renderer
+                .options
+                .mention_url_templates
+                .channel_url(&slack_channel_id);
+            if !highlighted && channel_url.is_none() {
+                return (channel_rendered, Some(styles));
+            }
+            // Linked or highlighted mention is a raw HTML fragment — not mergeable with adjacent styled text
+            let html = render_mention_html(
+                &channel_rendered,
+                renderer.options.channel_style_class(highlighted),
+                highlighted,
+                "",
+                channel_url.as_deref(),
+                renderer,
+            );
+            (wrap_with_styles(html, &styles), None)
         }
         Some(Some("user")) => {
             let Some(serde_json::Value::String(user_id)) = element.get("user_id") else {
                 return (String::new(), None);
             };
-            let user_rendered = if let Some(Some(user_name)) = renderer
+            let slack_user_id = SlackUserId(user_id.clone());
+            let user_rendered = match renderer
                 .slack_references
-                .users
-                .get(&SlackUserId(user_id.clone()))
+                .user_name(&slack_user_id, renderer.options.user_display_policy)
             {
-                user_name
-            } else {
-                user_id
+                Some(user_name) => Some(format!("@{user_name}")),
+                None => mrkdwn_mention_label(element, '@').or_else(|| {
+                    renderer
+                        .options
+                        .unresolved_mention_placeholders
+                        .user(&slack_user_id)
+                }),
             };
-            let style_class = if renderer
-                .slack_references
-                .user_id_to_highlight
-                .as_ref()
-                .is_some_and(|id| id.0 == *user_id)
-            {
-                &renderer.highlight_style_class
-            } else {
-                &renderer.default_style_class
+            let Some(user_rendered) = user_rendered else {
+                return (String::new(), None);
             };
+            let user_profile = renderer.slack_references.user_profiles.get(&slack_user_id);
+            let highlighted = renderer
+                .slack_references
+                .is_user_highlighted(&slack_user_id);
+            let style_class = renderer.options.user_style_class(highlighted);
             let style = element.get("style");
             let styles = StyleSet::from_style(style);
             // Mention is a raw HTML fragment — not mergeable with adjacent styled text
-            let html = format!(
-                "<span class=\"{style_class}\">@{}</span>",
-                encode_text(user_rendered)
+            let matrix = renderer.options.matrix.as_ref();
+            // Matrix pill: a link to the Matrix ID of the user, highlighted by Matrix clients
+            if let Some(matrix_user_id) = matrix.and_then(|matrix| matrix.user_id(&slack_user_id)) {
+                let html = render_mention_html(
+                    &encode_text(&user_rendered),
+                    style_class,
+                    false,
+                    "",
+                    Some(&matrix_to_url(&[&matrix_user_id])),
+                    renderer,
+                );
+                return (wrap_with_styles(html, &styles), None);
+            }
+            let title = user_profile
+                .and_then(|profile| profile.real_name.as_ref())
+                .filter(|_| renderer.options.html_user_title && matrix.is_none())
+                .map(|real_name| {
+                    format!(" title=\"{}\"", encode_double_quoted_attribute(real_name))
+                })
+                .unwrap_or_default();
+            let avatar = user_profile
+                .and_then(|profile| profile.avatar_url.as_ref())
+                .filter(|_| renderer.options.html_user_avatar && matrix.is_none())
+                .map(|avatar_url| {
+                    format!(
+                        "<img class=\"slack-avatar\" src=\"{}\" alt=\"\" />",
+                        encode_double_quoted_attribute(avatar_url)
+                    )
+                })
+                .unwrap_or_default();
+            let user_url = renderer
+                .options
+                .mention_url_templates
+                .user_url(&slack_user_id)
+                .or_else(|| user_profile.and_then(|profile| profile.profile_url.clone()));
+            let html = render_mention_html(
+                &format!("{avatar}{}", encode_text(&user_rendered)),
+                style_class,
+                highlighted,
+                &title,
+                user_url.as_deref(),
+                renderer,
             );
             (wrap_with_styles(html, &styles), None)
         }
@@ -427,30 +777,58 @@ fn render_rich_text_section_element(
             let Some(serde_json::Value::String(usergroup_id)) = element.get("usergroup_id") else {
                 return (String::new(), None);
             };
-            let usergroup_rendered = if let Some(Some(usergroup_name)) = renderer
+            let slack_usergroup_id = SlackUserGroupId(usergroup_id.clone());
+            let usergroup_rendered = match renderer
                 .slack_references
                 .usergroups
-                .get(&SlackUserGroupId(usergroup_id.clone()))
+                .get(&slack_usergroup_id)
             {
-                usergroup_name
-            } else {
-                usergroup_id
+                Some(Some(usergroup_name)) => Some(format!("@{usergroup_name}")),
+                _ => mrkdwn_mention_label(element, '@').or_else(|| {
+                    renderer
+                        .options
+                        .unresolved_mention_placeholders
+                        .usergroup(&slack_usergroup_id)
+                }),
+            };
+            let Some(usergroup_rendered) = usergroup_rendered else {
+                return (String::new(), None);
             };
-            let style_class = if renderer
+            let highlighted = renderer
                 .slack_references
-                .usergroup_ids_to_highlight
-                .as_ref()
-                .is_some_and(|ids| ids.iter().any(|id| id.0 == *usergroup_id))
-            {
-                &renderer.highlight_style_class
-            } else {
-                &renderer.default_style_class
+                .is_usergroup_highlighted(&slack_usergroup_id);
+            let style_class = renderer.options.usergroup_style_class(highlighted);
+            let style = element.get("style");
+            let styles = StyleSet::from_style(style);
+            let usergroup_url = renderer
+                .options
+                .mention_url_templates
+                .usergroup_url(&slack_usergroup_id);
+            let html = render_mention_html(
+                &encode_text(&usergroup_rendered),
+                style_class,
+                highlighted,
+                "",
+                usergroup_url.as_deref(),
+                renderer,
+            );
+            (wrap_with_styles(html, &styles), None)
+        }
+        Some(Some("broadcast")) => {
+            let Some(serde_json::Value::String(range)) = element.get("range") else {
+                return (String::new(), None);
             };
+            let highlighted = renderer.slack_references.is_broadcast_highlighted(range);
+            let style_class = renderer.options.broadcast_style_class(highlighted);
             let style = element.get("style");
             let styles = StyleSet::from_style(style);
-            let html = format!(
-                "<span class=\"{style_class}\">@{}</span>",
-                encode_text(usergroup_rendered)
+            let html = render_mention_html(
+                &format!("@{}", encode_text(range)),
+                style_class,
+                highlighted,
+                "",
+                None,
+                renderer,
             );
             (wrap_with_styles(html, &styles), None)
         }
@@ -460,10 +838,7 @@ fn render_rich_text_section_element(
             };
             let style = element.get("style");
             let styles = StyleSet::from_style(style);
-            let html = render_emoji(
-                &SlackEmojiName(name.to_string()),
-                &renderer.slack_references,
-            );
+            let html = render_emoji(&SlackEmojiName(name.to_string()), renderer);
             (wrap_with_styles(html, &styles), None)
         }
         Some(Some("link")) => {
@@ -476,49 +851,118 @@ fn render_rich_text_section_element(
                 .unwrap_or(url.as_str());
             let style = element.get("style");
             let styles = StyleSet::from_style(style);
-            let html = format!(
-                "<a target=\"_blank\" rel=\"noopener noreferrer\" href=\"{url}\">{}</a>",
-                encode_text(text)
-            );
+            let html = render_link_html(url, &encode_text(text), renderer.options);
+            (wrap_with_styles(html, &styles), None)
+        }
+        Some(Some("date")) => {
+            let Some(date) = format_rich_text_date(element, &renderer.options.time) else {
+                return (String::new(), None);
+            };
+            let timestamp = element.get("timestamp").and_then(|t| t.as_i64());
+            let styles = StyleSet::from_style(element.get("style"));
+            let html = render_date_as_html(&date, None, timestamp, renderer);
             (wrap_with_styles(html, &styles), None)
         }
         _ => (String::new(), None),
     }
 }
 
-fn render_emoji(emoji_name: &SlackEmojiName, slack_references: &SlackReferences) -> String {
-    if let Some(Some(emoji)) = slack_references.emojis.get(emoji_name) {
-        match emoji {
-            SlackEmojiRef::Alias(alias) => {
-                return render_emoji(alias, slack_references);
-            }
-            SlackEmojiRef::Url(url) => {
-                return format!(
-                    "<img class=\"slack-emoji\" src=\"{url}\" alt=\":{}:\" />",
-                    encode_text(&emoji_name.0)
-                );
-            }
-        }
+/// Render a formatted date as a `<time>` element, linked to `link` if any.
+fn render_date_as_html(
+    date: &str,
+    link: Option<&str>,
+    timestamp: Option<i64>,
+    renderer: &HtmlRenderer,
+) -> String {
+    // Matrix HTML has no `<time>` element
+    let date_time = timestamp
+        .filter(|_| renderer.options.matrix.is_none())
+        .and_then(|t| format_rfc3339(t, &renderer.options.time));
+    let html = match date_time {
+        Some(date_time) => format!(
+            "<time datetime=\"{}\">{}</time>",
+            encode_double_quoted_attribute(&date_time),
+            encode_text(date)
+        ),
+        None => encode_text(date).to_string(),
+    };
+    match link {
+        Some(link) => render_link_html(link, &html, renderer.options),
+        None => html,
     }
-    let name = &emoji_name.0;
+}
 
-    let splitted = name.split("::skin-tone-").collect::<Vec<&str>>();
-    let Some(first) = splitted.first() else {
-        return format!(":{}:", encode_text(name));
-    };
-    let Some(emoji) = emojis::get_by_shortcode(first) else {
-        return format!(":{}:", encode_text(name));
-    };
-    let Some(skin_tone) = splitted.get(1).and_then(|s| s.parse::<usize>().ok()) else {
-        return emoji.to_string();
-    };
-    let Some(mut skin_tones) = emoji.skin_tones() else {
-        return emoji.to_string();
-    };
-    let Some(skinned_emoji) = skin_tones.nth(skin_tone - 1) else {
-        return emoji.to_string();
-    };
-    skinned_emoji.to_string()
+/// Render a link to a raw `url` with already encoded content, opened in a new tab except in
+/// Matrix HTML which only allows `href`.
+pub(crate) fn render_link_html(url: &str, content: &str, options: &RenderOptions) -> String {
+    let href = encode_double_quoted_attribute(url);
+    if options.matrix.is_some() {
+        format!("<a href=\"{href}\">{content}</a>")
+    } else {
+        format!("<a target=\"_blank\" rel=\"noopener noreferrer\" href=\"{href}\">{content}</a>")
+    }
+}
+
+/// Render an image from its raw URL, or a link to it in Matrix HTML which only allows images from
+/// `mxc://` URLs, labeled by its URL when it has no alt text.
+pub(crate) fn render_image_html(url: &str, alt_text: &str, options: &RenderOptions) -> String {
+    if !is_image_allowed(url, options) {
+        let content = if alt_text.is_empty() { url } else { alt_text };
+        return render_link_html(url, &encode_text(content), options);
+    }
+    format!(
+        "<img src=\"{}\" alt=\"{}\" />",
+        encode_double_quoted_attribute(url),
+        encode_double_quoted_attribute(alt_text)
+    )
+}
+
+/// Whether an image can be shown from `url`: Matrix HTML only allows images from `mxc://` URLs.
+pub(crate) fn is_image_allowed(url: &str, options: &RenderOptions) -> bool {
+    options.matrix.is_none() || url.starts_with("mxc://")
+}
+
+/// A `class` attribute, left out in Matrix HTML which has no classes.
+pub(crate) fn class_attribute(class: &str, options: &RenderOptions) -> String {
+    if options.matrix.is_some() {
+        String::new()
+    } else {
+        format!(" class=\"{class}\"")
+    }
+}
+
+fn render_emoji(emoji_name: &SlackEmojiName, renderer: &HtmlRenderer) -> String {
+    match emoji::render_emoji(emoji_name, renderer.slack_references, renderer.options) {
+        RenderedEmoji::Text(text) => encode_text(&text).to_string(),
+        RenderedEmoji::Shortcode(name) => format!(":{}:", encode_text(&name)),
+        RenderedEmoji::Raw(html) => html,
+        // Matrix only allows images from `mxc://` URLs
+        RenderedEmoji::Image { url, name } if !is_image_allowed(&url, renderer.options) => {
+            format!(":{}:", encode_text(&name))
+        }
+        RenderedEmoji::Image { url, name } => {
+            let size = renderer
+                .options
+                .html_emoji_size
+                .map(|size| format!(" width=\"{size}\" height=\"{size}\""))
+                .unwrap_or_default();
+            let title = if renderer.options.html_emoji_title {
+                format!(" title=\":{}:\"", encode_double_quoted_attribute(&name))
+            } else {
+                String::new()
+            };
+            let class = if renderer.options.matrix.is_some() {
+                "data-mx-emoticon"
+            } else {
+                "class=\"slack-emoji\""
+            };
+            format!(
+                "<img {class} src=\"{}\" alt=\":{}:\"{size}{title} />",
+                encode_double_quoted_attribute(&url),
+                encode_double_quoted_attribute(&name)
+            )
+        }
+    }
 }
 
 fn render_rich_text_preformatted_elements(
@@ -526,6 +970,9 @@ fn render_rich_text_preformatted_elements(
     renderer: &HtmlRenderer,
 ) -> String {
     let content = render_rich_text_section_elements(elements, renderer, false);
+    if renderer.options.matrix.is_some() {
+        return format!("<pre><code>{content}\n</code></pre>\n");
+    }
     format!("<pre style=\"white-space: pre-wrap; word-break: break-word;\"><code>{content}\n</code></pre>\n")
 }
 
@@ -622,6 +1069,21 @@ fn render_slack_mrkdwn_as_html(text: &str, renderer: &HtmlRenderer) -> String {
             continue;
         }
 
+        // Entities Slack escapes mrkdwn text with are already escaped, also in code
+        if ch == '&' {
+            if let Some(entity) = ["&amp;", "&lt;", "&gt;"].into_iter().find(|entity| {
+                chars[i..]
+                    .iter()
+                    .copied()
+                    .take(entity.len())
+                    .eq(entity.chars())
+            }) {
+                output.push_str(entity);
+                i += entity.len();
+                continue;
+            }
+        }
+
         // Inside code: escape everything, no formatting
         if in_code {
             output.push_str(&encode_char(ch));
@@ -634,22 +1096,41 @@ fn render_slack_mrkdwn_as_html(text: &str, renderer: &HtmlRenderer) -> String {
             if let Some(end) = chars[i..].iter().position(|&c| c == '>') {
                 let inner: String = chars[i + 1..i + end].iter().collect();
                 // Check for special Slack references: <@U123>, <!subteam^S123>
-                if inner.starts_with('@') || inner.starts_with('!') || inner.starts_with('#') {
-                    // User/channel/subteam mention in mrkdwn — render as escaped text
+                if let Some((date, link)) = format_slack_date_token(&inner, &renderer.options.time)
+                {
+                    let timestamp = inner
+                        .trim_start_matches("!date^")
+                        .split('^')
+                        .next()
+                        .and_then(|timestamp| timestamp.parse().ok());
+                    output.push_str(&render_date_as_html(
+                        &date,
+                        link.as_deref(),
+                        timestamp,
+                        renderer,
+                    ));
+                } else if let Some(element) = mrkdwn_mention_element(&inner) {
+                    // User/channel/usergroup mention in mrkdwn — render as in rich text
+                    output.push_str(&render_rich_text_section_element(&element, renderer).0);
+                } else if inner.starts_with('@') || inner.starts_with('!') || inner.starts_with('#')
+                {
+                    // Other special token in mrkdwn — render as escaped text
                     output.push_str(&encode_text(&inner));
                 } else if let Some(pipe_pos) = inner.find('|') {
-                    let url = &inner[..pipe_pos];
-                    let label = &inner[pipe_pos + 1..];
-                    output.push_str(&format!(
-                        "<a target=\"_blank\" rel=\"noopener noreferrer\" href=\"{}\">{}</a>",
-                        url,
-                        encode_text(label)
+                    let url = decode_mrkdwn_entities(&inner[..pipe_pos]);
+                    let label = decode_mrkdwn_entities(&inner[pipe_pos + 1..]);
+                    output.push_str(&render_link_html(
+                        &url,
+                        &encode_text(&label),
+                        renderer.options,
                     ));
                 } else {
                     // URL without label
-                    output.push_str(&format!(
-                        "<a target=\"_blank\" rel=\"noopener noreferrer\" href=\"{inner}\">{}</a>",
-                        encode_text(&inner)
+                    let url = decode_mrkdwn_entities(&inner);
+                    output.push_str(&render_link_html(
+                        &url,
+                        &encode_text(&url),
+                        renderer.options,
                     ));
                 }
                 i += end + 1;
@@ -663,16 +1144,28 @@ fn render_slack_mrkdwn_as_html(text: &str, renderer: &HtmlRenderer) -> String {
 
         // Emoji shortcode: :name: (checked before _ to avoid italic inside emoji names)
         if ch == ':' {
+            // `::` and colons after a letter or digit do not start an emoji, e.g. in
+            // `Foo::new::bar` or `10:30:00`
+            if chars.get(i + 1) == Some(&':') {
+                output.push_str("::");
+                i += 2;
+                continue;
+            }
+            let after_alphanumeric = i > 0 && chars[i - 1].is_ascii_alphanumeric();
             if let Some(end) = chars[i + 1..].iter().position(|&c| c == ':') {
-                let name: String = chars[i + 1..i + 1 + end].iter().collect();
-                // Valid emoji names: non-empty, no spaces, may contain letters/digits/underscores/hyphens
-                if !name.is_empty() && !name.contains(' ') {
-                    let emoji_html =
-                        render_emoji(&SlackEmojiName(name.clone()), &renderer.slack_references);
+                let mut name: String = chars[i + 1..i + 1 + end].iter().collect();
+                if !after_alphanumeric && is_emoji_name(&name) {
+                    i += end + 2; // skip past closing :
+                                  // The skin tone is part of the name, like in rich text
+                    let rest: String = chars[i..].iter().take(16).collect();
+                    if let Some(skin_tone_len) = mrkdwn_skin_tone_len(&rest) {
+                        name.push(':');
+                        name.push_str(&rest[..skin_tone_len - 1]);
+                        i += skin_tone_len;
+                    }
                     // If render_emoji returned :name: unchanged, it wasn't resolved
                     // but it's still a valid emoji shortcode — preserve it as-is
-                    output.push_str(&emoji_html);
-                    i += end + 2; // skip past closing :
+                    output.push_str(&render_emoji(&SlackEmojiName(name), renderer));
                     continue;
                 }
             }
@@ -771,12 +1264,185 @@ mod tests {
     use url::Url;
 
     use super::*;
-    use crate::test_utils::rich_text_block;
+    use crate::{
+        options::{
+            EmojiCallback, EmojiPolicy, HighlightStyleClasses, MentionUrlTemplates,
+            UnresolvedMentionPlaceholder, UnresolvedMentionPlaceholders,
+        },
+        references::SlackUserReference,
+        test_utils::{
+            history_message, message_json_with_unfurl, message_with_attachment, raw_attachments,
+            rich_text_block,
+        },
+    };
 
     fn render(blocks: Vec<SlackBlock>, refs: SlackReferences) -> String {
         render_blocks_as_html(blocks, refs, "text-primary", "text-accent")
     }
 
+    #[test]
+    fn test_attachments_with_pretext_image_and_footer() {
+        assert_eq!(
+            render_attachments_as_html(
+                &raw_attachments(),
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "<p>Build <strong>failed</strong></p>\n\
+            <div class=\"slack-attachment\" style=\"border-left: 4px solid #a30200; padding-left: 8px;\">\n\
+            <p><strong><a target=\"_blank\" rel=\"noopener noreferrer\" href=\"https://ci.example.com/builds/2\">api v1.2.1</a></strong></p>\n\
+            <p>1 check failed</p>\n\
+            <p><img src=\"https://ci.example.com/builds/2.png\" alt=\"api v1.2.1\" /></p>\n\
+            <p class=\"slack-attachment-footer\">CI</p>\n\
+            </div>\n"
+        );
+    }
+
+    #[test]
+    fn test_image_alt_texts_with_quotes() {
+        let message = serde_json::json!({
+            "ts": "1720000000.000100",
+            "blocks": [{
+                "type": "image",
+                "image_url": "https://example.com/image.png",
+                "alt_text": "b\" onload=\"alert(2)"
+            }],
+            "attachments": [{
+                "id": 1,
+                "title": "a\" onerror=\"alert(1)",
+                "image_url": "https://ci.example.com/builds/2.png"
+            }]
+        });
+        assert_eq!(
+            render_message_json_as_html(
+                &message,
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "<p><img src=\"https://example.com/image.png\" alt=\"b&quot; onload=&quot;alert(2)\" /></p>\n\
+            <div class=\"slack-attachment\">\n\
+            <p><strong>a\" onerror=\"alert(1)</strong></p>\n\
+            <p><img src=\"https://ci.example.com/builds/2.png\" alt=\"a&quot; onerror=&quot;alert(1)\" /></p>\n\
+            </div>\n"
+        );
+    }
+
+    #[test]
+    fn test_message_with_text_fallback_and_attachments() {
+        assert_eq!(
+            render_message_as_html(
+                &message_with_attachment(),
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "<p>Deploy <strong>finished</strong></p>\n\
+            <div class=\"slack-attachment\" style=\"border-left: 4px solid #2eb886; padding-left: 8px;\">\n\
+            <p><strong>api v1.2.0</strong></p>\n\
+            <p>All checks passed</p>\n\
+            <div class=\"slack-attachment-field\"><strong>Environment</strong><br />production</div>\n\
+            <div class=\"slack-attachment-field\"><strong>Duration</strong><br />3m</div>\n\
+            </div>\n\
+            <div class=\"slack-attachment\">\n<p>Build log</p>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn test_message_json_with_unfurl() {
+        assert_eq!(
+            render_message_json_as_html(
+                &message_json_with_unfurl(),
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "<p>See <a target=\"_blank\" rel=\"noopener noreferrer\" href=\"https://github.com/dax/slack-blocks-render\">https://github.com/dax/slack-blocks-render</a></p>\n\
+            <div class=\"slack-attachment\">\n<p><strong>api v1.2.0</strong></p>\n</div>\n\
+            <div class=\"slack-unfurl\">\n<p class=\"slack-unfurl-service\">GitHub</p>\n\
+            <p class=\"slack-unfurl-title\"><a target=\"_blank\" rel=\"noopener noreferrer\" href=\"https://github.com/dax/slack-blocks-render\">dax/slack-blocks-render</a></p>\n\
+            <p>Render Slack blocks</p>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn test_message_with_reactions() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "Ship it?",
+            "reactions": [
+                { "name": "+1", "count": 3, "users": ["U1", "U2"] },
+                { "name": "partyparrot", "count": 1, "users": ["U2"] }
+            ]
+        }));
+        let refs = SlackReferences {
+            users: HashMap::from([(SlackUserId("U1".to_string()), Some("alice".to_string()))]),
+            emojis: HashMap::from([(
+                SlackEmojiName("partyparrot".to_string()),
+                Some(SlackEmojiRef::Url(
+                    Url::parse("https://emoji.slack-edge.com/partyparrot.gif").unwrap(),
+                )),
+            )]),
+            ..SlackReferences::default()
+        };
+        assert_eq!(
+            render_message_as_html(&message, &refs, &RenderOptions::default()),
+            "<p>Ship it?</p>\n\
+            <p class=\"slack-reactions\"><span class=\"slack-reaction\" title=\"alice, U2, 1 other\">👍 3</span> \
+            <span class=\"slack-reaction\" title=\"U2\"><img class=\"slack-emoji\" src=\"https://emoji.slack-edge.com/partyparrot.gif\" alt=\":partyparrot:\" /> 1</span></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_message_without_blocks_with_mentions() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "<@U1> deployed, <@U2|bob> please check <#C1> <!here>"
+        }));
+        let refs = SlackReferences {
+            users: HashMap::from([(SlackUserId("U1".to_string()), Some("alice".to_string()))]),
+            channels: HashMap::from([(SlackChannelId("C1".to_string()), Some("ops".to_string()))]),
+            user_ids_to_highlight: Some(vec![SlackUserId("U1".to_string())]),
+            ..SlackReferences::default()
+        };
+        let options = RenderOptions {
+            default_style_class: "text-primary".to_string(),
+            highlight_style_class: "text-accent".to_string(),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render_message_as_html(&message, &refs, &options),
+            "<p><span class=\"text-accent\">@alice</span> deployed, \
+            <span class=\"text-primary\">@bob</span> please check #ops \
+            <span class=\"text-primary\">@here</span></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_message_without_blocks_with_links_and_entities() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "See <https://example.com?a=1&amp;b=2|Q&amp;A> &amp; `a &lt; b`"
+        }));
+        assert_eq!(
+            render_message_as_html(&message, &SlackReferences::default(), &RenderOptions::default()),
+            "<p>See <a target=\"_blank\" rel=\"noopener noreferrer\" href=\"https://example.com?a=1&amp;b=2\">Q&amp;A</a> \
+            &amp; <code>a &lt; b</code></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_message_with_blocks() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "fallback",
+            "blocks": [{ "type": "header", "text": { "type": "plain_text", "text": "Hello" } }]
+        }));
+        assert!(!render_message_as_html(
+            &message,
+            &SlackReferences::default(),
+            &RenderOptions::default()
+        )
+        .contains("fallback"));
+    }
+
     #[test]
     fn test_empty_input() {
         assert_eq!(render(vec![], SlackReferences::default()), "");
@@ -1169,6 +1835,25 @@ mod tests {
                 );
             }
 
+            #[test]
+            fn test_with_link_with_quote_and_ampersand() {
+                let blocks = vec![rich_text_block(serde_json::json!({
+                    "type": "rich_text",
+                    "elements": [
+                        {
+                            "type": "rich_text_section",
+                            "elements": [
+                                { "type": "link", "url": "https://example.com/?q=\"x\"&a=1", "text": "Example" }
+                            ]
+                        }
+                    ]
+                }))];
+                assert_eq!(
+                    render(blocks, SlackReferences::default()),
+                    "<p><a target=\"_blank\" rel=\"noopener noreferrer\" href=\"https://example.com/?q=%22x%22&amp;a=1\">Example</a></p>\n"
+                );
+            }
+
             #[test]
             fn test_with_user_mention() {
                 let refs = SlackReferences {
@@ -1195,6 +1880,126 @@ mod tests {
                 );
             }
 
+            #[test]
+            fn test_with_user_mention_avatar_and_title() {
+                let refs = SlackReferences {
+                    user_profiles: HashMap::from([(
+                        SlackUserId("U123".to_string()),
+                        SlackUserReference {
+                            display_name: Some("johnny".to_string()),
+                            real_name: Some("John \"JD\" Doe".to_string()),
+                            avatar_url: Some("https://avatars.example.com/U123.png".to_string()),
+                            ..SlackUserReference::default()
+                        },
+                    )]),
+                    ..SlackReferences::default()
+                };
+                let blocks = vec![rich_text_block(serde_json::json!({
+                    "type": "rich_text",
+                    "elements": [
+                        {
+                            "type": "rich_text_section",
+                            "elements": [
+                                { "type": "user", "user_id": "U123" }
+                            ]
+                        }
+                    ]
+                }))];
+                let options = RenderOptions {
+                    default_style_class: "text-primary".to_string(),
+                    html_user_avatar: true,
+                    html_user_title: true,
+                    ..RenderOptions::default()
+                };
+                assert_eq!(
+                    render_blocks_as_html_with_options(&blocks, &refs, &options),
+                    "<p><span class=\"text-primary\" title=\"John &quot;JD&quot; Doe\">\
+                    <img class=\"slack-avatar\" src=\"https://avatars.example.com/U123.png\" alt=\"\" />\
+                    @johnny</span></p>\n"
+                );
+                assert_eq!(
+                    render(blocks, refs),
+                    "<p><span class=\"text-primary\">@johnny</span></p>\n"
+                );
+            }
+
+            #[test]
+            fn test_with_unresolved_mention_placeholders() {
+                let blocks = vec![rich_text_block(serde_json::json!({
+                    "type": "rich_text",
+                    "elements": [
+                        {
+                            "type": "rich_text_section",
+                            "elements": [
+                                { "type": "text", "text": "Hi " },
+                                { "type": "user", "user_id": "U123" },
+                                { "type": "text", "text": " in " },
+                                { "type": "channel", "channel_id": "C123" },
+                                { "type": "usergroup", "usergroup_id": "S123" }
+                            ]
+                        }
+                    ]
+                }))];
+                let options = RenderOptions {
+                    unresolved_mention_placeholders: UnresolvedMentionPlaceholders {
+                        user: UnresolvedMentionPlaceholder::Label("unknown user".to_string()),
+                        channel: UnresolvedMentionPlaceholder::SlackToken,
+                        usergroup: UnresolvedMentionPlaceholder::Omit,
+                    },
+                    ..RenderOptions::default()
+                };
+                assert_eq!(
+                    render_blocks_as_html_with_options(
+                        &blocks,
+                        &SlackReferences::default(),
+                        &options
+                    ),
+                    "<p>Hi <span class=\"\">unknown user</span> in &lt;#C123&gt;</p>\n"
+                );
+            }
+
+            #[test]
+            fn test_with_mention_url_templates() {
+                let refs = SlackReferences {
+                    users: HashMap::from([(
+                        SlackUserId("user1".to_string()),
+                        Some("alice".to_string()),
+                    )]),
+                    ..SlackReferences::default()
+                };
+                let blocks = vec![rich_text_block(serde_json::json!({
+                    "type": "rich_text",
+                    "elements": [
+                        {
+                            "type": "rich_text_section",
+                            "elements": [
+                                { "type": "user", "user_id": "user1" },
+                                { "type": "text", "text": " " },
+                                { "type": "channel", "channel_id": "C123" },
+                                { "type": "text", "text": " " },
+                                { "type": "usergroup", "usergroup_id": "S123" }
+                            ]
+                        }
+                    ]
+                }))];
+                let options = RenderOptions {
+                    default_style_class: "text-primary".to_string(),
+                    mention_url_templates: MentionUrlTemplates {
+                        user: Some("https://acme.slack.com/team/{id}".to_string()),
+                        channel: Some("slack://channel?id={id}&team={team}".to_string()),
+                        usergroup: None,
+                        team_id: Some(SlackTeamId("T123".to_string())),
+                    },
+                    ..RenderOptions::default()
+                };
+                assert_eq!(
+                    render_blocks_as_html_with_options(&blocks, &refs, &options),
+                    "<p><a class=\"text-primary\" href=\"https://acme.slack.com/team/user1\">@alice</a> \
+                    <a class=\"text-primary\" href=\"slack://channel?id=C123&amp;team=T123\">#C123</a> \
+                    <span class=\"text-primary\">@S123</span></p>\n"
+                );
+            }
+
             #[test]
             fn test_with_highlighted_user_mention() {
                 let refs = SlackReferences {
@@ -1202,7 +2007,7 @@ mod tests {
                         SlackUserId("U123".to_string()),
                         Some("john.doe".to_string()),
                     )]),
-                    user_id_to_highlight: Some(SlackUserId("U123".to_string())),
+                    user_ids_to_highlight: Some(vec![SlackUserId("U123".to_string())]),
                     ..SlackReferences::default()
                 };
                 let blocks = vec![rich_text_block(serde_json::json!({
@@ -1222,6 +2027,56 @@ mod tests {
                 );
             }
 
+            #[test]
+            fn test_with_highlighted_mentions_and_category_classes() {
+                let refs = SlackReferences {
+                    user_ids_to_highlight: Some(vec![
+                        SlackUserId("U1".to_string()),
+                        SlackUserId("U2".to_string()),
+                    ]),
+                    channel_ids_to_highlight: Some(vec![SlackChannelId("C1".to_string())]),
+                    broadcasts_to_highlight: Some(vec!["here".to_string()]),
+                    ..SlackReferences::default()
+                };
+                let blocks = vec![rich_text_block(serde_json::json!({
+                    "type": "rich_text",
+                    "elements": [
+                        {
+                            "type": "rich_text_section",
+                            "elements": [
+                                { "type": "user", "user_id": "U1" },
+                                { "type": "user", "user_id": "U2" },
+                                { "type": "user", "user_id": "U3" },
+                                { "type": "channel", "channel_id": "C1" },
+                                { "type": "channel", "channel_id": "C2" },
+                                { "type": "broadcast", "range": "here" },
+                                { "type": "broadcast", "range": "channel" }
+                            ]
+                        }
+                    ]
+                }))];
+                let options = RenderOptions {
+                    default_style_class: "text-primary".to_string(),
+                    highlight_style_class: "text-accent".to_string(),
+                    highlight_style_classes: HighlightStyleClasses {
+                        channel: Some("channel-highlight".to_string()),
+                        broadcast: Some("broadcast-highlight".to_string()),
+                        ..HighlightStyleClasses::default()
+                    },
+                    ..RenderOptions::default()
+                };
+                assert_eq!(
+                    render_blocks_as_html_with_options(&blocks, &refs, &options),
+                    "<p><span class=\"text-accent\">@U1</span>\
+                    <span class=\"text-accent\">@U2</span>\
+                    <span class=\"text-primary\">@U3</span>\
+                    <span class=\"channel-highlight\">#C1</span>\
+                    #C2\
+                    <span class=\"broadcast-highlight\">@here</span>\
+                    <span class=\"text-primary\">@channel</span></p>\n"
+                );
+            }
+
             #[test]
             fn test_with_usergroup_mention() {
                 let refs = SlackReferences {
@@ -1344,6 +2199,121 @@ mod tests {
                     "<p><img class=\"slack-emoji\" src=\"https://emoji.slack-edge.com/custom.png\" alt=\":custom:\" /></p>\n"
                 );
             }
+
+            #[test]
+            fn test_with_emoji_alias_loop() {
+                let refs = SlackReferences {
+                    emojis: HashMap::from([(
+                        SlackEmojiName("loop".to_string()),
+                        Some(SlackEmojiRef::Alias(SlackEmojiName("loop".to_string()))),
+                    )]),
+                    ..SlackReferences::default()
+                };
+                let blocks = vec![rich_text_block(serde_json::json!({
+                    "type": "rich_text",
+                    "elements": [
+                        {
+                            "type": "rich_text_section",
+                            "elements": [
+                                { "type": "emoji", "name": "loop" }
+                            ]
+                        }
+                    ]
+                }))];
+                assert_eq!(render(blocks, refs), "<p>:loop:</p>\n");
+            }
+
+            #[test]
+            fn test_with_emoji_image_size_and_title() {
+                let refs = SlackReferences {
+                    emojis: HashMap::from([(
+                        SlackEmojiName("custom".to_string()),
+                        Some(SlackEmojiRef::Url(
+                            Url::parse("https://emoji.slack-edge.com/custom.png").unwrap(),
+                        )),
+                    )]),
+                    ..SlackReferences::default()
+                };
+                let blocks = vec![rich_text_block(serde_json::json!({
+                    "type": "rich_text",
+                    "elements": [
+                        {
+                            "type": "rich_text_section",
+                            "elements": [
+                                { "type": "emoji", "name": "custom" }
+                            ]
+                        }
+                    ]
+                }))];
+                let options = RenderOptions {
+                    html_emoji_size: Some(20),
+                    html_emoji_title: true,
+                    ..RenderOptions::default()
+                };
+                assert_eq!(
+                    render_blocks_as_html_with_options(&blocks, &refs, &options),
+                    "<p><img class=\"slack-emoji\" src=\"https://emoji.slack-edge.com/custom.png\" alt=\":custom:\" width=\"20\" height=\"20\" title=\":custom:\" /></p>\n"
+                );
+            }
+
+            #[test]
+            fn test_with_emoji_callback() {
+                let blocks = vec![SlackBlock::Section(SlackSectionBlock::new().with_text(
+                    SlackBlockText::MarkDown(SlackBlockMarkDownText::new(
+                        "hello :wave: :unknown:".to_string(),
+                    )),
+                ))];
+                let options = RenderOptions {
+                    emoji_policy: EmojiPolicy::Custom(EmojiCallback::new(|name, unicode| {
+                        unicode
+                            .map(|unicode| format!("<i class=\"emoji-{}\">{unicode}</i>", name.0))
+                    })),
+                    ..RenderOptions::default()
+                };
+                assert_eq!(
+                    render_blocks_as_html_with_options(
+                        &blocks,
+                        &SlackReferences::default(),
+                        &options
+                    ),
+                    "<p>hello <i class=\"emoji-wave\">👋</i> :unknown:</p>\n"
+                );
+            }
+
+            #[test]
+            fn test_with_date() {
+                let blocks = vec![rich_text_block(serde_json::json!({
+                    "type": "rich_text",
+                    "elements": [
+                        {
+                            "type": "rich_text_section",
+                            "elements": [
+                                { "type": "text", "text": "Due " },
+                                {
+                                    "type": "date",
+                                    "timestamp": 1392734382,
+                                    "format": "{date_short} at {time}",
+                                    "style": { "bold": true }
+                                },
+                                { "type": "text", "text": " (" },
+                                { "type": "date", "timestamp": 1392734382, "format": "{date_num}" },
+                                { "type": "text", "text": ")" }
+                            ]
+                        }
+                    ]
+                }))];
+                let options = RenderOptions {
+                    time: crate::time::TimeOptions {
+                        timezone: chrono_tz::Europe::Paris,
+                        ..crate::time::TimeOptions::default()
+                    },
+                    ..RenderOptions::default()
+                };
+                assert_eq!(
+                    render_blocks_as_html_with_options(&blocks, &SlackReferences::default(), &options),
+                    "<p>Due <strong><time datetime=\"2014-02-18T15:39:42+01:00\">Feb 18, 2014 at 15:39</time></strong> (<time datetime=\"2014-02-18T15:39:42+01:00\">2014-02-18</time>)</p>\n".to_string()
+                );
+            }
         }
 
         mod rich_text_section_with_inline_indentation {
@@ -1758,6 +2728,22 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_link_with_quote_and_ampersand_in_mrkdwn() {
+            let blocks = vec![SlackBlock::Section(
+                SlackSectionBlock::new()
+                    .with_text(SlackBlockText::MarkDown(SlackBlockMarkDownText::new(
+                    "<https://example.com/?q=\"x\"&a=1|docs> <https://example.com/\"onclick=\"x>"
+                        .to_string(),
+                ))),
+            )];
+            assert_eq!(
+                render(blocks, SlackReferences::default()),
+                "<p><a target=\"_blank\" rel=\"noopener noreferrer\" href=\"https://example.com/?q=&quot;x&quot;&amp;a=1\">docs</a> \
+                <a target=\"_blank\" rel=\"noopener noreferrer\" href=\"https://example.com/&quot;onclick=&quot;x\">https://example.com/\"onclick=\"x</a></p>\n"
+            );
+        }
+
         #[test]
         fn test_costory_real_world() {
             let blocks = vec![SlackBlock::Section(
@@ -1773,6 +2759,41 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_colons_without_emoji() {
+            let blocks = vec![SlackBlock::Section(SlackSectionBlock::new().with_text(
+                SlackBlockText::MarkDown(SlackBlockMarkDownText::new(
+                    "Foo::new::bar at 10:30:00, :a\nb: :tada:".to_string(),
+                )),
+            ))];
+            assert_eq!(
+                render_blocks_as_html_with_report(
+                    &blocks,
+                    &SlackReferences::default(),
+                    &RenderOptions::default()
+                ),
+                (
+                    "<p>Foo::new::bar at 10:30:00, :a<br />\nb: \u{1F389}</p>\n".to_string(),
+                    RenderReport::default()
+                )
+            );
+        }
+
+        #[test]
+        fn test_emoji_with_skin_tones() {
+            let blocks = vec![SlackBlock::Section(
+                SlackSectionBlock::new()
+                    .with_text(SlackBlockText::MarkDown(SlackBlockMarkDownText::new(
+                    ":+1::skin-tone-3: :man-facepalming::skin-tone-2: :partyparrot::skin-tone-4:"
+                        .to_string(),
+                ))),
+            )];
+            assert_eq!(
+                render(blocks, SlackReferences::default()),
+                "<p>👍🏼 🤦🏻‍♂️ :partyparrot::skin-tone-4:</p>\n"
+            );
+        }
+
         #[test]
         fn test_unresolved_emoji_kept_as_literal() {
             let blocks = vec![SlackBlock::Section(SlackSectionBlock::new().with_text(
@@ -1785,6 +2806,21 @@ mod tests {
                 "<p>:unknown_emoji:</p>\n"
             );
         }
+
+        #[test]
+        fn test_with_date_tokens() {
+            let blocks = vec![SlackBlock::Section(SlackSectionBlock::new().with_text(
+                SlackBlockText::MarkDown(SlackBlockMarkDownText::new(
+                    "Posted <!date^1392734382^{date_short} at {time}^https://example.com/|Feb 18>, \
+                    <!date^1392734382^{unknown}|fallback> <!date^oops>"
+                        .to_string(),
+                )),
+            ))];
+            assert_eq!(
+                render_blocks_as_html_with_options(&blocks, &SlackReferences::default(), &RenderOptions::default()),
+                "<p>Posted <a target=\"_blank\" rel=\"noopener noreferrer\" href=\"https://example.com/\"><time datetime=\"2014-02-18T14:39:42+00:00\">Feb 18, 2014 at 14:39</time></a>, <time datetime=\"2014-02-18T14:39:42+00:00\">{unknown}</time> !date^oops</p>\n".to_string()
+            );
+        }
     }
 
     mod render_slack_mrkdwn_text {
@@ -82,15 +82,311 @@
 //! // Finally, render the blocks as Markdown
 //! let markdown_text = render_blocks_as_markdown(blocks, slack_references, None);
 //! ```
+//!
+//! ## Resolving Slack references with a resolver
+//!
+//! Instead of filling `SlackReferences` by hand, implement the `SlackReferenceResolver` trait
+//! (usually on top of a Slack API client) and let `resolve_slack_references` send the
+//! unresolved IDs in batches.
+//! With the `slack-api` feature enabled, `SlackApiReferenceResolver` implements it on top of a
+//! slack-morphism `SlackClientSession`, also resolving the user profiles rendering avatars and
+//! real names in HTML mentions.
+//!
+//! To avoid resolving the same IDs on every run, wrap a resolver in a `CachedSlackReferenceResolver`
+//! backed by a `SlackReferencesCache`, which can be loaded from and saved to a JSON file. Entries
+//! expire after a configurable time to live, and IDs confirmed as not found are cached as well.
+//! ```
+//! use std::collections::HashMap;
+//! use slack_morphism::prelude::*;
+//! use slack_blocks_render::{
+//!   find_slack_references_in_blocks, resolve_slack_references, InMemorySlackReferenceResolver,
+//!   SlackReferences, DEFAULT_RESOLVER_BATCH_SIZE,
+//! };
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let blocks: Vec<SlackBlock> = vec![
+//!     SlackBlock::RichText(serde_json::from_value(serde_json::json!({
+//!         "elements": [
+//!             {
+//!                 "type": "rich_text_section",
+//!                 "elements": [
+//!                     {
+//!                         "type": "user",
+//!                         "user_id": "U123456"
+//!                     }
+//!                 ]
+//!             },
+//!         ]
+//!     })).unwrap()),
+//! ];
+//! // An in-memory resolver, replace it with your own implementation
+//! let resolver = InMemorySlackReferenceResolver::new(SlackReferences {
+//!     users: HashMap::from([(SlackUserId("U123456".to_string()), Some("john".to_string()))]),
+//!     ..SlackReferences::default()
+//! });
+//! let slack_references = find_slack_references_in_blocks(&blocks);
+//! let slack_references =
+//!     resolve_slack_references(&slack_references, &resolver, DEFAULT_RESOLVER_BATCH_SIZE)
+//!         .await
+//!         .unwrap();
+//! assert_eq!(
+//!     slack_references.users.get(&SlackUserId("U123456".to_string())),
+//!     Some(&Some("john".to_string()))
+//! );
+//! # });
+//! ```
+//!
+//! ## Rendering the same blocks in several formats
+//!
+//! The `*_with_options` functions borrow the blocks and the Slack references, and `render_blocks`
+//! renders Markdown, HTML and text together in a single traversal. The returned report lists the
+//! unresolved references, unsupported blocks and unsupported rich text elements, collected in the
+//! same traversal but apart from the renderers, so it is built once for all formats. The
+//! `*_with_report` functions return it along with a single format, and `build_render_report`
+//! builds it on its own, without rendering:
+//! ```
+//! use slack_morphism::prelude::*;
+//! use slack_blocks_render::{render_blocks, RenderOptions, SlackReferences};
+//!
+//! let blocks: Vec<SlackBlock> = vec![
+//!     SlackBlock::Header(SlackHeaderBlock::new("Hello World".into())),
+//! ];
+//! let options = RenderOptions {
+//!     default_style_class: "mention".to_string(),
+//!     highlight_style_class: "mention-highlight".to_string(),
+//!     ..RenderOptions::default()
+//! };
+//! let rendered = render_blocks(&blocks, &SlackReferences::default(), &options);
+//! assert_eq!(rendered.markdown, "## Hello World");
+//! assert_eq!(rendered.html, "<h2>Hello World</h2>\n");
+//! assert_eq!(rendered.text, "Hello World");
+//! assert!(rendered.report.is_empty());
+//! ```
+//!
+//! ## Rendering messages
+//!
+//! Messages are rendered with `render_message_as_markdown`, `render_message_as_html` and
+//! `render_message_as_text`: their blocks, or their mrkdwn `text` when they have no blocks,
+//! followed by their files, legacy attachments (title, text, fields, blocks and color bar) and
+//! reactions.
+//! Attachment pretexts, title links, images and footers are not part of the slack-morphism model:
+//! `find_attachments_in_message` reads attachments from the raw JSON of a message, and
+//! `render_attachments_as_*` renders them with these fields. Likewise, files lack their size,
+//! thumbnails and snippet previews in the slack-morphism model: `find_files_in_message` reads them
+//! from the raw JSON as `SlackFileShare`s, rendered with `render_files_as_*`. Private file URLs can
+//! be rewritten with `RenderOptions::file_url_rewriter`, e.g. to go through a proxy. Link previews
+//! are not part of the slack-morphism model either: `find_unfurls_in_message` reads them from the
+//! raw JSON of a message, and `render_unfurls_as_*` renders them as cards. `render_message_json_as_*`
+//! renders a message from its raw JSON with all of these, each unfurl once as a card and not as an
+//! attachment. Blocks which cannot be read are skipped, and reported by
+//! `render_message_json_as_*_with_report`.
+//!
+//! Whole conversations are rendered with `render_transcript_as_markdown`, `render_transcript_as_html`
+//! and `render_transcript_as_text`: messages are grouped by day with their author, time and edited
+//! marker, and thread replies are nested under their parent message. The references of all the
+//! messages can be resolved at once with `resolve_slack_references_in_messages`.
+//! The labels of the headers, like the edited marker, are in English unless set with
+//! `RenderOptions::transcript_labels`.
+//! Message times are linked to the messages in Slack with `RenderOptions::permalinks`, a
+//! `SlackPermalinkBuilder` which can also build links on its own, e.g. for search results.
+//! ```
+//! use slack_morphism::prelude::*;
+//! use slack_blocks_render::{render_message_as_text, RenderOptions, SlackReferences};
+//!
+//! let message: SlackHistoryMessage = serde_json::from_value(serde_json::json!({
+//!     "ts": "1720000000.000100",
+//!     "text": "Deploy finished",
+//!     "attachments": [{ "color": "good", "title": "api v1.2.0" }]
+//! })).unwrap();
+//! let text = render_message_as_text(&message, &SlackReferences::default(), &RenderOptions::default());
+//! assert_eq!(text, "Deploy finished\n\napi v1.2.0");
+//! ```
+//!
+//! ## Rendering times
+//!
+//! Message times, rich text `date` elements and mrkdwn `<!date^timestamp^format|fallback>` tokens
+//! are formatted with `RenderOptions::time`: a timezone, a locale, relative dates like
+//! `yesterday at 14:02`, and the clock giving the current time, which can be fixed for deterministic
+//! renderings. `format_slack_date` formats a timestamp with the Slack date tokens (`{date_short}`,
+//! `{time}`, `{ago}`...).
+//! ```
+//! use chrono::DateTime;
+//! use slack_blocks_render::{format_slack_date, Clock, TimeLocale, TimeOptions};
+//!
+//! let options = TimeOptions {
+//!     timezone: chrono_tz::Europe::Paris,
+//!     locale: TimeLocale::AmericanEnglish,
+//!     relative: true,
+//!     clock: Clock::fixed(DateTime::from_timestamp(1392821382, 0).unwrap()),
+//! };
+//! let date = format_slack_date(1392734382, "{date_pretty} at {time}", &options);
+//! assert_eq!(date.as_deref(), Some("yesterday at 3:39 PM"));
+//! ```
+//!
+//! ## Rendering as Slack mrkdwn
+//!
+//! `render_blocks_as_slack_mrkdwn` renders blocks back to Slack mrkdwn, e.g. to post rich text in the
+//! `text` of a message in another workspace. Mentions, emoji and dates are kept as Slack references,
+//! and text is escaped with `escape_slack_mrkdwn`. The standard Markdown of `markdown` blocks is
+//! converted to mrkdwn.
+//! ```
+//! use slack_morphism::prelude::*;
+//! use slack_blocks_render::render_blocks_as_slack_mrkdwn;
+//!
+//! let blocks: Vec<SlackBlock> = serde_json::from_value(serde_json::json!([{
+//!     "type": "rich_text",
+//!     "elements": [{
+//!         "type": "rich_text_section",
+//!         "elements": [
+//!             { "type": "user", "user_id": "U123" },
+//!             { "type": "text", "text": " ships R&D", "style": { "bold": true } }
+//!         ]
+//!     }]
+//! }])).unwrap();
+//! assert_eq!(render_blocks_as_slack_mrkdwn(&blocks), "<@U123> *ships R&amp;D*");
+//! ```
+//!
+//! ## Rendering for a terminal
+//!
+//! `render_blocks_as_ansi_with_options` renders blocks for a terminal: styles and mentions with ANSI
+//! SGR codes, links as OSC 8 hyperlinks, quotes and code blocks drawn with box characters, and lines
+//! wrapped to `AnsiOptions::width`. `AnsiOptions::no_color()` renders plain text for terminals
+//! without colors.
+//! ```
+//! use slack_morphism::prelude::*;
+//! use slack_blocks_render::{
+//!     render_blocks_as_ansi_with_options, AnsiOptions, RenderOptions, SlackReferences,
+//! };
+//!
+//! let blocks: Vec<SlackBlock> = serde_json::from_value(serde_json::json!([{
+//!     "type": "rich_text",
+//!     "elements": [{
+//!         "type": "rich_text_quote",
+//!         "elements": [{ "type": "text", "text": "The quick brown fox" }]
+//!     }]
+//! }])).unwrap();
+//! let options = RenderOptions {
+//!     ansi: AnsiOptions { width: Some(12), ..AnsiOptions::no_color() },
+//!     ..RenderOptions::default()
+//! };
+//! let rendered = render_blocks_as_ansi_with_options(&blocks, &SlackReferences::default(), &options);
+//! assert_eq!(rendered, "│ The quick\n│ brown fox");
+//! ```
+//!
+//! ## Rendering for Matrix
+//!
+//! `render_blocks_as_matrix` and `render_message_as_matrix` render the content of a Matrix message: a
+//! plain `body` and a `formatted_body` restricted to Matrix's HTML subset. Mentions of Slack users
+//! mapped to a Matrix ID by `MatrixOptions` are rendered as pills, and highlighted mentions are
+//! colored with `data-mx-color`. A `MatrixReply` is quoted in an `<mx-reply>` fallback.
+//! ```
+//! use slack_morphism::prelude::*;
+//! use slack_blocks_render::{
+//!     render_blocks_as_matrix, MatrixOptions, RenderOptions, SlackReferences,
+//! };
+//!
+//! let blocks: Vec<SlackBlock> = serde_json::from_value(serde_json::json!([{
+//!     "type": "rich_text",
+//!     "elements": [{
+//!         "type": "rich_text_section",
+//!         "elements": [
+//!             { "type": "user", "user_id": "U123" },
+//!             { "type": "text", "text": " deployed" }
+//!         ]
+//!     }]
+//! }])).unwrap();
+//! let options = RenderOptions {
+//!     matrix: Some(MatrixOptions::new(|user_id| {
+//!         (user_id.0 == "U123").then(|| "@alice:example.org".to_string())
+//!     })),
+//!     ..RenderOptions::default()
+//! };
+//! let message = render_blocks_as_matrix(&blocks, &SlackReferences::default(), &options);
+//! assert_eq!(message.body, "@U123 deployed");
+//! assert_eq!(
+//!     message.formatted_body,
+//!     "<p><a href=\"https://matrix.to/#/%40alice%3Aexample.org\">@U123</a> deployed</p>\n"
+//! );
+//! ```
+pub mod ansi;
+pub mod attachment;
+pub mod cache;
+mod emoji;
+pub mod files;
 pub mod html;
 pub mod markdown;
+pub mod matrix;
+mod message;
+pub mod mrkdwn;
+pub mod multi;
+pub mod options;
+pub mod permalink;
 pub mod references;
+pub mod report;
+pub mod resolver;
+#[cfg(feature = "slack-api")]
+pub mod slack_api;
 pub mod text;
+pub mod time;
+pub mod transcript;
+pub mod unfurl;
 pub mod visitor;
 
 #[cfg(test)]
 pub(crate) mod test_utils;
 
-pub use html::{render_blocks_as_html, render_slack_mrkdwn_text_as_html};
-pub use markdown::render_blocks_as_markdown;
-pub use references::{find_slack_references_in_blocks, SlackReferences};
+pub use ansi::{render_blocks_as_ansi, render_blocks_as_ansi_with_options, AnsiOptions};
+pub use attachment::{find_attachments_in_message, SlackAttachment};
+pub use cache::{CachedSlackReferenceResolver, SlackReferencesCache, SlackReferencesCacheTtl};
+pub use files::{
+    find_files_in_message, render_files_as_html, render_files_as_markdown, render_files_as_text,
+    SlackFileShare,
+};
+pub use html::{
+    render_attachments_as_html, render_blocks_as_html, render_blocks_as_html_with_options,
+    render_blocks_as_html_with_report, render_message_as_html, render_message_json_as_html,
+    render_message_json_as_html_with_report, render_slack_mrkdwn_text_as_html,
+};
+pub use markdown::{
+    render_attachments_as_markdown, render_blocks_as_markdown,
+    render_blocks_as_markdown_with_options, render_blocks_as_markdown_with_report,
+    render_message_as_markdown, render_message_json_as_markdown,
+    render_message_json_as_markdown_with_report,
+};
+pub use matrix::{
+    matrix_to_url, render_blocks_as_matrix, render_message_as_matrix, MatrixMessage, MatrixOptions,
+    MatrixReply, MatrixUserMapper,
+};
+pub use mrkdwn::{
+    escape_slack_mrkdwn, render_blocks_as_slack_mrkdwn, render_blocks_as_slack_mrkdwn_with_options,
+};
+pub use multi::{render_blocks, RenderedBlocks};
+pub use options::{
+    EmojiCallback, EmojiImageCdn, EmojiPolicy, FileUrlRewriter, HighlightStyleClasses,
+    MentionUrlTemplates, RenderOptions, UnresolvedMentionPlaceholder,
+    UnresolvedMentionPlaceholders, UserDisplayPolicy, VariationSelectors,
+};
+pub use permalink::SlackPermalinkBuilder;
+pub use references::{
+    find_slack_references_in_blocks, find_slack_references_in_messages, SlackReferenceKind,
+    SlackReferences, SlackReferencesMergeStrategy, SlackUserReference,
+};
+pub use report::{build_render_report, RenderReport};
+pub use resolver::{
+    resolve_slack_references, resolve_slack_references_in_messages, InMemorySlackReferenceResolver,
+    SlackReferenceResolver, DEFAULT_RESOLVER_BATCH_SIZE,
+};
+pub use text::{
+    render_attachments_as_text, render_blocks_as_text, render_blocks_as_text_with_options,
+    render_blocks_as_text_with_report, render_message_as_text, render_message_json_as_text,
+    render_message_json_as_text_with_report,
+};
+pub use time::{format_slack_date, Clock, TimeLocale, TimeOptions};
+pub use transcript::{
+    render_transcript_as_html, render_transcript_as_markdown, render_transcript_as_text,
+    TranscriptLabels,
+};
+pub use unfurl::{
+    find_unfurls_in_message, render_unfurls_as_html, render_unfurls_as_markdown,
+    render_unfurls_as_text, SlackUnfurl,
+};
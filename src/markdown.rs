@@ -2,7 +2,15 @@ use serde_json::Value;
 use slack_morphism::prelude::*;
 
 use crate::{
-    references::SlackReferences,
+    attachment::SlackAttachment,
+    emoji::{self, split_mrkdwn_emojis, MrkdwnPart, RenderedEmoji},
+    files::render_files_as_markdown,
+    message::{mrkdwn_section, MessageParts},
+    options::{RenderOptions, UnresolvedMentionPlaceholder},
+    references::{mrkdwn_mention_label, replace_slack_mrkdwn_tokens, SlackReferences},
+    report::{build_message_render_report, build_render_report, RenderReport},
+    time::format_rich_text_date,
+    unfurl::render_unfurls_as_markdown,
     visitor::{
         visit_slack_block_image_element, visit_slack_block_mark_down_text,
         visit_slack_block_plain_text, visit_slack_context_block, visit_slack_divider_block,
@@ -18,30 +26,226 @@ pub fn render_blocks_as_markdown(
     slack_references: SlackReferences,
     handle_delimiter: Option<String>,
 ) -> String {
-    let mut block_renderer = MarkdownRenderer::new(slack_references, handle_delimiter);
+    let options = RenderOptions {
+        handle_delimiter,
+        ..RenderOptions::default()
+    };
+    render_blocks_as_markdown_with_options(&blocks, &slack_references, &options)
+}
+
+/// Render borrowed Slack blocks as Markdown.
+/// Neither the blocks nor the references are cloned, so the same message can be rendered
+/// into several formats without copying it.
+pub fn render_blocks_as_markdown_with_options(
+    blocks: &[SlackBlock],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let mut block_renderer = MarkdownRenderer::new(slack_references, options);
     for block in blocks {
-        block_renderer.visit_slack_block(&block);
+        block_renderer.visit_slack_block(block);
     }
-    block_renderer.sub_texts.join("\n")
+    block_renderer.finish()
+}
+
+/// Render borrowed Slack blocks as Markdown, along with the report of what could not be rendered
+/// faithfully.
+pub fn render_blocks_as_markdown_with_report(
+    blocks: &[SlackBlock],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> (String, RenderReport) {
+    (
+        render_blocks_as_markdown_with_options(blocks, slack_references, options),
+        build_render_report(blocks, slack_references, options),
+    )
+}
+
+/// Render a Slack message as Markdown: its blocks, or its mrkdwn `text` when it has no blocks,
+/// then its files, its legacy attachments as quotes and its reactions.
+pub fn render_message_as_markdown(
+    message: &SlackHistoryMessage,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    render_message_parts_as_markdown(
+        &MessageParts::new(&message.content),
+        slack_references,
+        options,
+    )
+}
+
+/// Render a Slack message in the JSON format of the Slack API as Markdown, like
+/// [`render_message_as_markdown`] but with the attachments and files read from the raw JSON, and
+/// the unfurls rendered as cards after the attachments.
+pub fn render_message_json_as_markdown(
+    message: &serde_json::Value,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    render_message_parts_as_markdown(&MessageParts::from_json(message), slack_references, options)
+}
+
+/// Render a Slack message in the JSON format of the Slack API as Markdown, with the report of what
+/// could not be rendered, including the blocks which could not be read.
+pub fn render_message_json_as_markdown_with_report(
+    message: &serde_json::Value,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> (String, RenderReport) {
+    let message = MessageParts::from_json(message);
+    (
+        render_message_parts_as_markdown(&message, slack_references, options),
+        build_message_render_report(&message, slack_references, options),
+    )
+}
+
+/// Render the parts of a message.
+fn render_message_parts_as_markdown(
+    message: &MessageParts,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let blocks = render_blocks_as_markdown_with_options(&message.blocks, slack_references, options);
+    let parts = [
+        blocks,
+        render_files_as_markdown(&message.files, options),
+        render_attachments_as_markdown(&message.attachments, slack_references, options),
+        render_unfurls_as_markdown(&message.unfurls, slack_references, options),
+        render_reactions_as_markdown(
+            &message.reactions,
+            &MarkdownRenderer::new(slack_references, options),
+        ),
+    ];
+    parts
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Render reactions as their emoji followed by their count, e.g. `👍 3  🎉 1`.
+fn render_reactions_as_markdown(
+    reactions: &[SlackReaction],
+    renderer: &MarkdownRenderer,
+) -> String {
+    reactions
+        .iter()
+        .map(|reaction| {
+            let emoji = render_emoji(&SlackEmojiName(reaction.name.0.clone()), renderer, None);
+            format!("{emoji} {}", reaction.count)
+        })
+        .collect::<Vec<_>>()
+        .join("  ")
 }
 
-struct MarkdownRenderer {
+/// Render legacy attachments as Markdown: their pretext, then their title, text, fields,
+/// blocks, image and footer as a quote. Unfurls are left to [`render_unfurls_as_markdown`].
+pub fn render_attachments_as_markdown(
+    attachments: &[SlackAttachment],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    attachments
+        .iter()
+        .filter(|attachment| !attachment.is_unfurl())
+        .map(|attachment| render_attachment_as_markdown(attachment, slack_references, options))
+        .filter(|attachment| !attachment.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn render_attachment_as_markdown(
+    attachment: &SlackAttachment,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let render_mrkdwn = |text: &str| {
+        render_blocks_as_markdown_with_options(&[mrkdwn_section(text)], slack_references, options)
+    };
+    let mut parts = vec![];
+    if attachment.is_fallback_only() {
+        parts.extend(attachment.fallback.clone());
+    }
+    let title = attachment.title.as_deref().map(escape_markdown);
+    match (&title, &attachment.title_link) {
+        (Some(title), Some(title_link)) => {
+            parts.push(format!("*{}*", render_url_as_markdown(title_link, title)))
+        }
+        (Some(title), None) => parts.push(format!("*{title}*")),
+        (None, _) => {}
+    }
+    if let Some(text) = &attachment.text {
+        parts.push(render_mrkdwn(text));
+    }
+    for field in attachment.fields.iter().flatten() {
+        let value = field
+            .value
+            .as_deref()
+            .map(render_mrkdwn)
+            .unwrap_or_default();
+        match &field.title {
+            Some(title) => parts.push(format!("*{}*\n{value}", escape_markdown(title))),
+            None => parts.push(value),
+        }
+    }
+    if let Some(blocks) = &attachment.blocks {
+        parts.push(render_blocks_as_markdown_with_options(
+            blocks,
+            slack_references,
+            options,
+        ));
+    }
+    if let Some(image) = attachment.image() {
+        let alt = title.as_deref().unwrap_or_default();
+        parts.push(format!("![{alt}]({image})"));
+    }
+    if let Some(footer) = &attachment.footer {
+        parts.push(render_mrkdwn(footer));
+    }
+    let quote = parts
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .lines()
+        .map(|line| format!("> {line}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    // The pretext is shown above the attachment, out of its quote
+    match attachment.pretext.as_deref().map(render_mrkdwn) {
+        Some(pretext) if !quote.is_empty() => format!("{pretext}\n{quote}"),
+        Some(pretext) => pretext,
+        None => quote,
+    }
+}
+
+pub(crate) struct MarkdownRenderer<'a> {
     pub sub_texts: Vec<String>,
-    pub slack_references: SlackReferences,
-    pub handle_delimiter: Option<String>,
+    pub slack_references: &'a SlackReferences,
+    pub options: &'a RenderOptions,
 }
 
-impl MarkdownRenderer {
-    pub fn new(slack_references: SlackReferences, handle_delimiter: Option<String>) -> Self {
+impl<'a> MarkdownRenderer<'a> {
+    pub fn new(slack_references: &'a SlackReferences, options: &'a RenderOptions) -> Self {
         MarkdownRenderer {
             sub_texts: vec![],
             slack_references,
-            handle_delimiter,
+            options,
         }
     }
+
+    /// A renderer for nested content, with the same references and options.
+    pub fn nested(&self) -> Self {
+        MarkdownRenderer::new(self.slack_references, self.options)
+    }
+
+    pub fn finish(self) -> String {
+        self.sub_texts.join("\n")
+    }
 }
 
-fn join(mut texts: Vec<String>, join_str: &str) -> String {
+pub(crate) fn join(mut texts: Vec<String>, join_str: &str) -> String {
     for i in 0..texts.len() {
         if i < texts.len() - 1 {
             if texts[i].ends_with('`') && texts[i + 1].starts_with('`') {
@@ -68,10 +272,9 @@ fn join(mut texts: Vec<String>, join_str: &str) -> String {
     texts.join(join_str)
 }
 
-impl Visitor for MarkdownRenderer {
+impl Visitor for MarkdownRenderer<'_> {
     fn visit_slack_section_block(&mut self, slack_section_block: &SlackSectionBlock) {
-        let mut section_renderer =
-            MarkdownRenderer::new(self.slack_references.clone(), self.handle_delimiter.clone());
+        let mut section_renderer = self.nested();
         visit_slack_section_block(&mut section_renderer, slack_section_block);
         self.sub_texts.push(join(section_renderer.sub_texts, ""));
     }
@@ -82,8 +285,7 @@ impl Visitor for MarkdownRenderer {
     }
 
     fn visit_slack_header_block(&mut self, slack_header_block: &SlackHeaderBlock) {
-        let mut header_renderer =
-            MarkdownRenderer::new(self.slack_references.clone(), self.handle_delimiter.clone());
+        let mut header_renderer = self.nested();
         visit_slack_header_block(&mut header_renderer, slack_header_block);
         self.sub_texts
             .push(format!("## {}", join(header_renderer.sub_texts, "")));
@@ -119,20 +321,36 @@ impl Visitor for MarkdownRenderer {
         &mut self,
         slack_block_mark_down_text: &SlackBlockMarkDownText,
     ) {
-        self.sub_texts.push(slack_block_mark_down_text.text.clone());
+        let text = split_mrkdwn_emojis(&slack_block_mark_down_text.text)
+            .into_iter()
+            .map(|part| match part {
+                MrkdwnPart::Text(text) => replace_slack_mrkdwn_tokens(
+                    text,
+                    &self.options.time,
+                    |date, link| match link {
+                        Some(link) => render_url_as_markdown(&link, &date),
+                        None => date,
+                    },
+                    |element| render_rich_text_section_element(element, self),
+                ),
+                MrkdwnPart::Emoji(name) => {
+                    render_emoji(&SlackEmojiName(name.to_string()), self, None)
+                }
+            })
+            .collect::<String>();
+        self.sub_texts.push(text);
         visit_slack_block_mark_down_text(self, slack_block_mark_down_text);
     }
 
     fn visit_slack_context_block(&mut self, slack_context_block: &SlackContextBlock) {
-        let mut section_renderer =
-            MarkdownRenderer::new(self.slack_references.clone(), self.handle_delimiter.clone());
+        let mut section_renderer = self.nested();
         visit_slack_context_block(&mut section_renderer, slack_context_block);
         self.sub_texts.push(section_renderer.sub_texts.join(""));
     }
 
     fn visit_slack_rich_text_block(&mut self, slack_rich_text_block: &SlackRichTextBlock) {
         self.sub_texts.push(render_rich_text_block_as_markdown(
-            slack_rich_text_block.json_value.clone(),
+            &slack_rich_text_block.json_value,
             self,
         ));
     }
@@ -174,7 +392,7 @@ impl Visitor for MarkdownRenderer {
 }
 
 fn render_rich_text_block_as_markdown(
-    json_value: serde_json::Value,
+    json_value: &serde_json::Value,
     renderer: &MarkdownRenderer,
 ) -> String {
     match json_value.get("elements") {
@@ -301,11 +519,27 @@ fn render_rich_text_quote_elements(
     )
 }
 
+/// Render the placeholder of an unresolved mention. Slack tokens are rendered as code, as
+/// Markdown would take them for autolinks or raw HTML.
+fn render_mention_placeholder(
+    placeholder: &UnresolvedMentionPlaceholder,
+    rendered: Option<String>,
+) -> Option<String> {
+    match placeholder {
+        UnresolvedMentionPlaceholder::SlackToken => rendered.map(|token| format!("`{token}`")),
+        _ => rendered,
+    }
+}
+
 fn render_rich_text_section_element(
     element: &serde_json::Value,
     renderer: &MarkdownRenderer,
 ) -> String {
-    let handle_delimiter = renderer.handle_delimiter.clone().unwrap_or_default();
+    let handle_delimiter = renderer
+        .options
+        .handle_delimiter
+        .as_deref()
+        .unwrap_or_default();
     match element.get("type").map(|t| t.as_str()) {
         Some(Some("text")) => {
             let Some(serde_json::Value::String(text)) = element.get("text") else {
@@ -318,34 +552,84 @@ fn render_rich_text_section_element(
             let Some(serde_json::Value::String(channel_id)) = element.get("channel_id") else {
                 return "".to_string();
             };
-            let channel_rendered = if let Some(Some(channel_name)) = renderer
-                .slack_references
-                .channels
-                .get(&SlackChannelId(channel_id.clone()))
-            {
-                channel_name
-            } else {
-                channel_id
+            let slack_channel_id = SlackChannelId(channel_id.clone());
+            let channel_rendered = match renderer.slack_references.channels.get(&slack_channel_id) {
+                Some(Some(channel_name)) => Some(format!("#{channel_name}")),
+                _ => mrkdwn_mention_label(element, '#').or_else(|| {
+                    render_mention_placeholder(
+                        &renderer.options.unresolved_mention_placeholders.channel,
+                        renderer
+                            .options
+                            .unresolved_mention_placeholders
+                            .channel(&slack_channel_id),
+                    )
+                }),
+            };
+            let Some(channel_rendered) = channel_rendered else {
+                return "".to_string();
             };
             let style = element.get("style");
-            apply_all_styles(format!("#{channel_rendered}"), style)
+            let channel_rendered = match renderer
+                .options
+                .mention_url_templates
+                .channel_url(&slack_channel_id)
+            {
+                Some(url) => render_url_as_markdown(&url, &channel_rendered),
+                None => channel_rendered,
+            };
+            let highlighted = renderer
+                .slack_references
+                .is_channel_highlighted(&slack_channel_id);
+            apply_all_styles(
+                apply_highlight_style(channel_rendered, highlighted, style, renderer),
+                style,
+            )
         }
         Some(Some("user")) => {
             let Some(serde_json::Value::String(user_id)) = element.get("user_id") else {
                 return "".to_string();
             };
-            let user_rendered = if let Some(Some(user_name)) = renderer
+            let slack_user_id = SlackUserId(user_id.clone());
+            let user_rendered = match renderer
                 .slack_references
-                .users
-                .get(&SlackUserId(user_id.clone()))
+                .user_name(&slack_user_id, renderer.options.user_display_policy)
             {
-                user_name
-            } else {
-                user_id
+                Some(user_name) => Some(format!("@{user_name}")),
+                None => mrkdwn_mention_label(element, '@').or_else(|| {
+                    render_mention_placeholder(
+                        &renderer.options.unresolved_mention_placeholders.user,
+                        renderer
+                            .options
+                            .unresolved_mention_placeholders
+                            .user(&slack_user_id),
+                    )
+                }),
             };
+            let Some(user_rendered) = user_rendered else {
+                return "".to_string();
+            };
+            let user_rendered = format!("{handle_delimiter}{user_rendered}{handle_delimiter}");
+            let user_url = renderer
+                .options
+                .mention_url_templates
+                .user_url(&slack_user_id)
+                .or_else(|| {
+                    renderer
+                        .slack_references
+                        .user_profiles
+                        .get(&slack_user_id)
+                        .and_then(|profile| profile.profile_url.clone())
+                });
             let style = element.get("style");
+            let user_rendered = match user_url {
+                Some(url) => render_url_as_markdown(&url, &user_rendered),
+                None => user_rendered,
+            };
+            let highlighted = renderer
+                .slack_references
+                .is_user_highlighted(&slack_user_id);
             apply_all_styles(
-                format!("{handle_delimiter}@{user_rendered}{handle_delimiter}"),
+                apply_highlight_style(user_rendered, highlighted, style, renderer),
                 style,
             )
         }
@@ -353,32 +637,63 @@ fn render_rich_text_section_element(
             let Some(serde_json::Value::String(usergroup_id)) = element.get("usergroup_id") else {
                 return "".to_string();
             };
-            let usergroup_rendered = if let Some(Some(usergroup_name)) = renderer
+            let slack_usergroup_id = SlackUserGroupId(usergroup_id.clone());
+            let usergroup_rendered = match renderer
                 .slack_references
                 .usergroups
-                .get(&SlackUserGroupId(usergroup_id.clone()))
+                .get(&slack_usergroup_id)
             {
-                usergroup_name
-            } else {
-                usergroup_id
+                Some(Some(usergroup_name)) => Some(format!("@{usergroup_name}")),
+                _ => mrkdwn_mention_label(element, '@').or_else(|| {
+                    render_mention_placeholder(
+                        &renderer.options.unresolved_mention_placeholders.usergroup,
+                        renderer
+                            .options
+                            .unresolved_mention_placeholders
+                            .usergroup(&slack_usergroup_id),
+                    )
+                }),
+            };
+            let Some(usergroup_rendered) = usergroup_rendered else {
+                return "".to_string();
             };
+            let usergroup_rendered =
+                format!("{handle_delimiter}{usergroup_rendered}{handle_delimiter}");
             let style = element.get("style");
+            let usergroup_rendered = match renderer
+                .options
+                .mention_url_templates
+                .usergroup_url(&slack_usergroup_id)
+            {
+                Some(url) => render_url_as_markdown(&url, &usergroup_rendered),
+                None => usergroup_rendered,
+            };
+            let highlighted = renderer
+                .slack_references
+                .is_usergroup_highlighted(&slack_usergroup_id);
             apply_all_styles(
-                format!("{handle_delimiter}@{usergroup_rendered}{handle_delimiter}"),
+                apply_highlight_style(usergroup_rendered, highlighted, style, renderer),
                 style,
             )
         }
-        Some(Some("emoji")) => {
-            let Some(serde_json::Value::String(name)) = element.get("name") else {
+        Some(Some("broadcast")) => {
+            let Some(serde_json::Value::String(range)) = element.get("range") else {
                 return "".to_string();
             };
+            let highlighted = renderer.slack_references.is_broadcast_highlighted(range);
             let style = element.get("style");
-            render_emoji(
-                &SlackEmojiName(name.to_string()),
-                &renderer.slack_references,
+            apply_all_styles(
+                apply_highlight_style(format!("@{range}"), highlighted, style, renderer),
                 style,
             )
         }
+        Some(Some("emoji")) => {
+            let Some(serde_json::Value::String(name)) = element.get("name") else {
+                return "".to_string();
+            };
+            let style = element.get("style");
+            render_emoji(&SlackEmojiName(name.to_string()), renderer, style)
+        }
         Some(Some("link")) => {
             let Some(serde_json::Value::String(url)) = element.get("url") else {
                 return "".to_string();
@@ -389,48 +704,44 @@ fn render_rich_text_section_element(
             let style = element.get("style");
             apply_all_styles(render_url_as_markdown(url, text), style)
         }
+        Some(Some("date")) => {
+            let Some(date) = format_rich_text_date(element, &renderer.options.time) else {
+                return "".to_string();
+            };
+            apply_all_styles(date, element.get("style"))
+        }
         _ => "".to_string(),
     }
 }
 
+/// Escape the characters of `text` which Markdown would read as emphasis, code or links.
+pub(crate) fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '~' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 fn render_url_as_markdown(url: &str, text: &str) -> String {
     format!("[{}]({})", text, url)
 }
 
 fn render_emoji(
     emoji_name: &SlackEmojiName,
-    slack_references: &SlackReferences,
+    renderer: &MarkdownRenderer,
     style: Option<&Value>,
 ) -> String {
-    if let Some(Some(emoji)) = slack_references.emojis.get(emoji_name) {
-        match emoji {
-            SlackEmojiRef::Alias(alias) => {
-                return render_emoji(alias, slack_references, style);
-            }
-            SlackEmojiRef::Url(url) => {
-                return apply_all_styles(format!("![:{}:]({})", emoji_name.0, url), style);
-            }
+    match emoji::render_emoji(emoji_name, renderer.slack_references, renderer.options) {
+        RenderedEmoji::Text(text) | RenderedEmoji::Raw(text) => apply_all_styles(text, style),
+        RenderedEmoji::Shortcode(name) => apply_all_styles(format!(":{name}:"), style),
+        RenderedEmoji::Image { url, name } => {
+            apply_all_styles(format!("![:{}:]({})", name, url), style)
         }
     }
-    let name = &emoji_name.0;
-
-    let splitted = name.split("::skin-tone-").collect::<Vec<&str>>();
-    let Some(first) = splitted.first() else {
-        return apply_all_styles(format!(":{}:", name), style);
-    };
-    let Some(emoji) = emojis::get_by_shortcode(first) else {
-        return apply_all_styles(format!(":{}:", name), style);
-    };
-    let Some(skin_tone) = splitted.get(1).and_then(|s| s.parse::<usize>().ok()) else {
-        return apply_all_styles(emoji.to_string(), style);
-    };
-    let Some(mut skin_tones) = emoji.skin_tones() else {
-        return apply_all_styles(emoji.to_string(), style);
-    };
-    let Some(skinned_emoji) = skin_tones.nth(skin_tone - 1) else {
-        return apply_all_styles(emoji.to_string(), style);
-    };
-    apply_all_styles(skinned_emoji.to_string(), style)
 }
 
 fn apply_all_styles(text: String, style: Option<&serde_json::Value>) -> String {
@@ -440,6 +751,20 @@ fn apply_all_styles(text: String, style: Option<&serde_json::Value>) -> String {
     apply_code_style(text, style)
 }
 
+/// Bold a highlighted mention when requested, unless its style already makes it bold.
+fn apply_highlight_style(
+    text: String,
+    highlighted: bool,
+    style: Option<&serde_json::Value>,
+    renderer: &MarkdownRenderer,
+) -> String {
+    if highlighted && renderer.options.markdown_bold_highlights && !is_styled(style, "bold") {
+        format!("*{}*", text)
+    } else {
+        text
+    }
+}
+
 fn apply_bold_style(text: String, style: Option<&serde_json::Value>) -> String {
     if is_styled(style, "bold") {
         format!("*{}*", text)
@@ -492,7 +817,233 @@ mod tests {
     use url::Url;
 
     use super::*;
-    use crate::test_utils::rich_text_block;
+    use crate::{
+        attachment::find_attachments_in_message,
+        options::{
+            EmojiImageCdn, EmojiPolicy, MentionUrlTemplates, UnresolvedMentionPlaceholder,
+            UnresolvedMentionPlaceholders,
+        },
+        references::SlackUserReference,
+        test_utils::{
+            history_message, message_json_with_unfurl, message_with_attachment, raw_attachments,
+            rich_text_block,
+        },
+    };
+
+    #[test]
+    fn test_attachments_with_pretext_image_and_footer() {
+        assert_eq!(
+            render_attachments_as_markdown(
+                &raw_attachments(),
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "Build *failed*\n\
+            > *[api v1.2.1](https://ci.example.com/builds/2)*\n\
+            > 1 check failed\n\
+            > ![api v1.2.1](https://ci.example.com/builds/2.png)\n\
+            > CI"
+        );
+    }
+
+    #[test]
+    fn test_attachments_escape_titles() {
+        let attachments = find_attachments_in_message(&serde_json::json!({
+            "attachments": [
+                {
+                    "title": "fix [ci] *now*",
+                    "title_link": "https://ci.example.com/3",
+                    "fields": [{ "title": "snake_case", "value": "ok" }]
+                }
+            ]
+        }));
+        assert_eq!(
+            render_attachments_as_markdown(
+                &attachments,
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "> *[fix \\[ci\\] \\*now\\*](https://ci.example.com/3)*\n> *snake\\_case*\n> ok"
+        );
+    }
+
+    #[test]
+    fn test_message_with_text_fallback_and_attachments() {
+        assert_eq!(
+            render_message_as_markdown(
+                &message_with_attachment(),
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "Deploy *finished*\n\n> *api v1.2.0*\n> All checks passed\n> *Environment*\n> production\n> *Duration*\n> 3m\n\n> Build log"
+        );
+    }
+
+    #[test]
+    fn test_message_without_blocks_with_mentions_and_links() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "<@U1> see <https://example.com|the docs> &amp; <#C1> `a &lt; b`"
+        }));
+        let refs = SlackReferences {
+            users: HashMap::from([(SlackUserId("U1".to_string()), Some("alice".to_string()))]),
+            channels: HashMap::from([(SlackChannelId("C1".to_string()), Some("ops".to_string()))]),
+            ..SlackReferences::default()
+        };
+        assert_eq!(
+            render_message_as_markdown(&message, &refs, &RenderOptions::default()),
+            "@alice see [the docs](https://example.com) & #ops `a < b`"
+        );
+    }
+
+    #[test]
+    fn test_message_without_blocks_with_unresolved_mention_labels() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "<@U2|bob> <#C2|general> <!subteam^S1|@oncall> <@U3> <#C3|>"
+        }));
+        assert_eq!(
+            render_message_as_markdown(
+                &message,
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "@bob #general @oncall @U3 #C3"
+        );
+    }
+
+    #[test]
+    fn test_message_json_with_unfurl() {
+        assert_eq!(
+            render_message_json_as_markdown(
+                &message_json_with_unfurl(),
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "See [https://github.com/dax/slack-blocks-render](https://github.com/dax/slack-blocks-render)\n\n\
+            > *api v1.2.0*\n\n\
+            > *GitHub*\n\
+            > [dax/slack-blocks-render](https://github.com/dax/slack-blocks-render)\n\
+            > Render Slack blocks"
+        );
+    }
+
+    #[test]
+    fn test_message_json_keeps_parsed_blocks() {
+        let message = serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "fallback",
+            "blocks": [
+                { "type": "call", "call_id": "R1" },
+                { "type": "section", "text": { "type": "mrkdwn", "text": "Deploy *done*" } }
+            ]
+        });
+        let (markdown, report) = render_message_json_as_markdown_with_report(
+            &message,
+            &SlackReferences::default(),
+            &RenderOptions::default(),
+        );
+        assert_eq!(markdown, "Deploy *done*");
+        assert_eq!(report.unsupported_blocks, vec!["call".to_string()]);
+    }
+
+    #[test]
+    fn test_message_with_files() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "See the report",
+            "files": [
+                {
+                    "id": "F1",
+                    "name": "report.pdf",
+                    "mimetype": "application/pdf",
+                    "url_private": "https://files.slack.com/files-pri/T1-F1/report.pdf"
+                }
+            ]
+        }));
+        assert_eq!(
+            render_message_as_markdown(
+                &message,
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "See the report\n\n[report.pdf](https://files.slack.com/files-pri/T1-F1/report.pdf)"
+        );
+    }
+
+    #[test]
+    fn test_message_with_reactions() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "Ship it?",
+            "reactions": [
+                { "name": "+1", "count": 3, "users": ["U1", "U2"] },
+                { "name": "partyparrot", "count": 1, "users": ["U2"] }
+            ]
+        }));
+        assert_eq!(
+            render_message_as_markdown(
+                &message,
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "Ship it?\n\n👍 3  :partyparrot: 1"
+        );
+    }
+
+    #[test]
+    fn test_message_with_blocks() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "fallback",
+            "blocks": [{ "type": "header", "text": { "type": "plain_text", "text": "Hello" } }]
+        }));
+        assert!(!render_message_as_markdown(
+            &message,
+            &SlackReferences::default(),
+            &RenderOptions::default()
+        )
+        .contains("fallback"));
+    }
+
+    #[test]
+    fn test_render_with_report() {
+        let blocks = vec![
+            SlackBlock::Actions(SlackActionsBlock::new(vec![])),
+            rich_text_block(serde_json::json!({
+                "type": "rich_text",
+                "elements": [
+                    {
+                        "type": "rich_text_section",
+                        "elements": [
+                            { "type": "text", "text": "Hello " },
+                            { "type": "color", "value": "#FF0000" },
+                            { "type": "user", "user_id": "U123" }
+                        ]
+                    }
+                ]
+            })),
+        ];
+
+        let (markdown, report) = render_blocks_as_markdown_with_report(
+            &blocks,
+            &SlackReferences::default(),
+            &RenderOptions::default(),
+        );
+        assert_eq!(markdown, "Hello @U123");
+        assert_eq!(
+            report,
+            RenderReport {
+                unresolved_references: SlackReferences {
+                    users: HashMap::from([(SlackUserId("U123".to_string()), None)]),
+                    ..SlackReferences::default()
+                },
+                unsupported_blocks: vec!["actions".to_string()],
+                unsupported_elements: vec!["color".to_string()],
+                ..RenderReport::default()
+            }
+        );
+    }
 
     #[test]
     fn test_empty_input() {
@@ -713,6 +1264,45 @@ Video description
                 "Text1Text11Text12\nText2Text21Text22".to_string()
             );
         }
+
+        #[test]
+        fn test_with_date_tokens() {
+            let blocks = vec![SlackBlock::Section(SlackSectionBlock::new().with_text(
+                SlackBlockText::MarkDown(SlackBlockMarkDownText::new(
+                    "Posted <!date^1392734382^{date_short} at {time}^https://example.com/|Feb 18>, \
+                    <!date^1392734382^{unknown}|fallback> <!date^oops>"
+                        .to_string(),
+                )),
+            ))];
+            assert_eq!(
+                render_blocks_as_markdown_with_options(
+                    &blocks,
+                    &SlackReferences::default(),
+                    &RenderOptions::default()
+                ),
+                "Posted [Feb 18, 2014 at 14:39](https://example.com/), {unknown} <!date^oops>"
+                    .to_string()
+            );
+        }
+
+        #[test]
+        fn test_with_emoji_with_skin_tones() {
+            let blocks = vec![SlackBlock::Section(
+                SlackSectionBlock::new()
+                    .with_text(SlackBlockText::MarkDown(SlackBlockMarkDownText::new(
+                    ":+1::skin-tone-3: :man-facepalming::skin-tone-2: :partyparrot::skin-tone-4:"
+                        .to_string(),
+                ))),
+            )];
+            assert_eq!(
+                render_blocks_as_markdown_with_options(
+                    &blocks,
+                    &SlackReferences::default(),
+                    &RenderOptions::default()
+                ),
+                "👍🏼 🤦🏻‍♂️ :partyparrot::skin-tone-4:".to_string()
+            );
+        }
     }
 
     mod context {
@@ -1435,6 +2025,211 @@ Video description
                         "@@John Doe@".to_string()
                     );
                 }
+
+                #[test]
+                fn test_with_unresolved_mention_placeholders() {
+                    let blocks = vec![rich_text_block(serde_json::json!({
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    { "type": "text", "text": "Hi " },
+                                    { "type": "user", "user_id": "U123" },
+                                    { "type": "text", "text": " in " },
+                                    { "type": "channel", "channel_id": "C123" },
+                                    { "type": "usergroup", "usergroup_id": "S123" }
+                                ]
+                            }
+                        ]
+                    }))];
+                    let options = RenderOptions {
+                        unresolved_mention_placeholders: UnresolvedMentionPlaceholders {
+                            user: UnresolvedMentionPlaceholder::Label("unknown user".to_string()),
+                            channel: UnresolvedMentionPlaceholder::SlackToken,
+                            usergroup: UnresolvedMentionPlaceholder::Omit,
+                        },
+                        ..RenderOptions::default()
+                    };
+                    assert_eq!(
+                        render_blocks_as_markdown_with_options(
+                            &blocks,
+                            &SlackReferences::default(),
+                            &options
+                        ),
+                        "Hi unknown user in `<#C123>`".to_string()
+                    );
+                }
+
+                #[test]
+                fn test_with_slack_token_placeholders_as_code() {
+                    let blocks = vec![rich_text_block(serde_json::json!({
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    { "type": "user", "user_id": "U123" },
+                                    { "type": "text", "text": " in " },
+                                    { "type": "usergroup", "usergroup_id": "S123" }
+                                ]
+                            }
+                        ]
+                    }))];
+                    let options = RenderOptions {
+                        unresolved_mention_placeholders: UnresolvedMentionPlaceholders {
+                            user: UnresolvedMentionPlaceholder::SlackToken,
+                            channel: UnresolvedMentionPlaceholder::SlackToken,
+                            usergroup: UnresolvedMentionPlaceholder::SlackToken,
+                        },
+                        ..RenderOptions::default()
+                    };
+                    assert_eq!(
+                        render_blocks_as_markdown_with_options(
+                            &blocks,
+                            &SlackReferences::default(),
+                            &options
+                        ),
+                        "`<@U123>` in `<!subteam^S123>`".to_string()
+                    );
+                }
+
+                #[test]
+                fn test_with_highlighted_mentions_in_bold() {
+                    let blocks = vec![rich_text_block(serde_json::json!({
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    { "type": "user", "user_id": "U1" },
+                                    { "type": "text", "text": " " },
+                                    { "type": "user", "user_id": "U2" },
+                                    { "type": "text", "text": " " },
+                                    { "type": "channel", "channel_id": "C1" },
+                                    { "type": "text", "text": " " },
+                                    { "type": "broadcast", "range": "here" },
+                                    { "type": "text", "text": " " },
+                                    { "type": "usergroup", "usergroup_id": "S1", "style": { "bold": true } }
+                                ]
+                            }
+                        ]
+                    }))];
+                    let slack_references = SlackReferences {
+                        user_ids_to_highlight: Some(vec![
+                            SlackUserId("U1".to_string()),
+                            SlackUserId("U2".to_string()),
+                        ]),
+                        channel_ids_to_highlight: Some(vec![SlackChannelId("C1".to_string())]),
+                        broadcasts_to_highlight: Some(vec!["here".to_string()]),
+                        usergroup_ids_to_highlight: Some(vec![SlackUserGroupId("S1".to_string())]),
+                        ..SlackReferences::default()
+                    };
+                    let options = RenderOptions {
+                        markdown_bold_highlights: true,
+                        ..RenderOptions::default()
+                    };
+                    assert_eq!(
+                        render_blocks_as_markdown_with_options(
+                            &blocks,
+                            &slack_references,
+                            &options
+                        ),
+                        "*@U1* *@U2* *#C1* *@here* *@S1*".to_string()
+                    );
+                    assert_eq!(
+                        render_blocks_as_markdown_with_options(
+                            &blocks,
+                            &slack_references,
+                            &RenderOptions::default()
+                        ),
+                        "@U1 @U2 #C1 @here *@S1*".to_string()
+                    );
+                }
+
+                #[test]
+                fn test_with_user_id_and_user_profile() {
+                    let blocks = vec![rich_text_block(serde_json::json!({
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    {
+                                        "type": "user",
+                                        "user_id": "user1"
+                                    }
+                                ]
+                            }
+                        ]
+                    }))];
+                    assert_eq!(
+                        render_blocks_as_markdown(
+                            blocks,
+                            SlackReferences {
+                                users: HashMap::from([(
+                                    SlackUserId("user1".to_string()),
+                                    Some("jdoe".to_string())
+                                )]),
+                                user_profiles: HashMap::from([(
+                                    SlackUserId("user1".to_string()),
+                                    SlackUserReference {
+                                        display_name: Some("johnny".to_string()),
+                                        real_name: Some("John Doe".to_string()),
+                                        ..SlackUserReference::default()
+                                    }
+                                )]),
+                                ..SlackReferences::default()
+                            },
+                            None
+                        ),
+                        "@johnny".to_string()
+                    );
+                }
+
+                #[test]
+                fn test_with_mention_url_templates() {
+                    let blocks = vec![rich_text_block(serde_json::json!({
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    { "type": "user", "user_id": "user1" },
+                                    { "type": "text", "text": " " },
+                                    { "type": "channel", "channel_id": "C123" },
+                                    { "type": "text", "text": " " },
+                                    { "type": "usergroup", "usergroup_id": "S123" }
+                                ]
+                            }
+                        ]
+                    }))];
+                    assert_eq!(
+                        render_blocks_as_markdown_with_options(
+                            &blocks,
+                            &SlackReferences {
+                                users: HashMap::from([(
+                                    SlackUserId("user1".to_string()),
+                                    Some("alice".to_string())
+                                )]),
+                                ..SlackReferences::default()
+                            },
+                            &RenderOptions {
+                                mention_url_templates: MentionUrlTemplates {
+                                    user: Some("https://acme.slack.com/team/{id}".to_string()),
+                                    channel: Some(
+                                        "slack://channel?id={id}&team={team}".to_string()
+                                    ),
+                                    usergroup: None,
+                                    team_id: Some(SlackTeamId("T123".to_string())),
+                                },
+                                ..RenderOptions::default()
+                            }
+                        ),
+                        "[@alice](https://acme.slack.com/team/user1) [#C123](slack://channel?id=C123&team=T123) @S123"
+                            .to_string()
+                    );
+                }
             }
 
             mod usergroup_element {
@@ -1649,6 +2444,32 @@ Video description
                     );
                 }
 
+                #[test]
+                fn test_with_slack_shortcode_emoji() {
+                    let blocks = vec![rich_text_block(serde_json::json!({
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    {
+                                        "type": "emoji",
+                                        "name": "simple_smile"
+                                    },
+                                    {
+                                        "type": "emoji",
+                                        "name": "+1::skin-tone-3"
+                                    }
+                                ]
+                            }
+                        ]
+                    }))];
+                    assert_eq!(
+                        render_blocks_as_markdown(blocks, SlackReferences::default(), None),
+                        "🙂👍🏼".to_string()
+                    );
+                }
+
                 #[test]
                 fn test_with_emoji_with_unknown_skin_tone() {
                     let blocks = vec![rich_text_block(serde_json::json!({
@@ -1725,6 +2546,48 @@ Video description
                     );
                 }
 
+                #[test]
+                fn test_with_emoji_alias_loop() {
+                    let blocks = vec![rich_text_block(serde_json::json!({
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    {
+                                        "type": "emoji",
+                                        "name": "ping"
+                                    }
+                                ]
+                            }
+                        ]
+                    }))];
+                    assert_eq!(
+                        render_blocks_as_markdown(
+                            blocks,
+                            SlackReferences {
+                                emojis: HashMap::from([
+                                    (
+                                        SlackEmojiName("ping".to_string()),
+                                        Some(SlackEmojiRef::Alias(SlackEmojiName(
+                                            "pong".to_string()
+                                        )))
+                                    ),
+                                    (
+                                        SlackEmojiName("pong".to_string()),
+                                        Some(SlackEmojiRef::Alias(SlackEmojiName(
+                                            "ping".to_string()
+                                        )))
+                                    )
+                                ]),
+                                ..SlackReferences::default()
+                            },
+                            None
+                        ),
+                        ":ping:".to_string()
+                    );
+                }
+
                 #[test]
                 fn test_with_unknown_emoji_with_slack_reference_alias_to_custom_emoji() {
                     let blocks = vec![rich_text_block(serde_json::json!({
@@ -1800,6 +2663,80 @@ Video description
                         "![:unknown1:](https://emoji.com/unknown1.png)".to_string()
                     );
                 }
+
+                #[test]
+                fn test_with_emoji_policies() {
+                    let blocks = vec![rich_text_block(serde_json::json!({
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    { "type": "emoji", "name": "wave" },
+                                    { "type": "emoji", "name": "unknown1" }
+                                ]
+                            }
+                        ]
+                    }))];
+                    let render = |emoji_policy| {
+                        render_blocks_as_markdown_with_options(
+                            &blocks,
+                            &SlackReferences::default(),
+                            &RenderOptions {
+                                emoji_policy,
+                                ..RenderOptions::default()
+                            },
+                        )
+                    };
+                    assert_eq!(
+                        render(EmojiPolicy::Shortcode),
+                        ":wave::unknown1:".to_string()
+                    );
+                    assert_eq!(
+                        render(EmojiPolicy::Image(EmojiImageCdn::twemoji())),
+                        "![:wave:](https://cdn.jsdelivr.net/gh/jdecked/twemoji@15.1.0/assets/72x72/1f44b.png):unknown1:"
+                            .to_string()
+                    );
+                }
+            }
+
+            #[test]
+            fn test_with_date() {
+                let blocks = vec![rich_text_block(serde_json::json!({
+                    "type": "rich_text",
+                    "elements": [
+                        {
+                            "type": "rich_text_section",
+                            "elements": [
+                                { "type": "text", "text": "Due " },
+                                {
+                                    "type": "date",
+                                    "timestamp": 1392734382,
+                                    "format": "{date_short} at {time}",
+                                    "style": { "bold": true }
+                                },
+                                { "type": "text", "text": " (" },
+                                { "type": "date", "timestamp": 1392734382, "format": "{date_num}" },
+                                { "type": "text", "text": ")" }
+                            ]
+                        }
+                    ]
+                }))];
+                let options = RenderOptions {
+                    time: crate::time::TimeOptions {
+                        timezone: chrono_tz::Europe::Paris,
+                        ..crate::time::TimeOptions::default()
+                    },
+                    ..RenderOptions::default()
+                };
+                assert_eq!(
+                    render_blocks_as_markdown_with_options(
+                        &blocks,
+                        &SlackReferences::default(),
+                        &options
+                    ),
+                    "Due *Feb 18, 2014 at 15:39* (2014-02-18)".to_string()
+                );
             }
         }
 
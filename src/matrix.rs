@@ -0,0 +1,414 @@
+use std::{fmt, sync::Arc};
+
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use serde::Serialize;
+use slack_morphism::prelude::*;
+
+use crate::{
+    html::{render_blocks_as_html_with_options, render_message_as_html},
+    options::{encode_url_component, RenderOptions},
+    references::SlackReferences,
+    text::{render_blocks_as_text_with_options, render_message_as_text},
+};
+
+/// Options of the Matrix rendering, which restricts the HTML output to the subset allowed in
+/// Matrix messages.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct MatrixOptions {
+    /// Matrix IDs of Slack users, turning their mentions into pills.
+    pub user_ids: Option<MatrixUserMapper>,
+    /// Color of highlighted mentions which are not pills, as a `data-mx-color`.
+    pub highlight_color: Option<String>,
+}
+
+impl MatrixOptions {
+    pub fn new(
+        user_ids: impl Fn(&SlackUserId) -> Option<String> + Send + Sync + 'static,
+    ) -> MatrixOptions {
+        MatrixOptions {
+            user_ids: Some(MatrixUserMapper::new(user_ids)),
+            highlight_color: None,
+        }
+    }
+
+    pub fn with_highlight_color(mut self, highlight_color: impl Into<String>) -> MatrixOptions {
+        self.highlight_color = Some(highlight_color.into());
+        self
+    }
+
+    /// Matrix ID of `user_id`, like `@alice:example.org`.
+    pub(crate) fn user_id(&self, user_id: &SlackUserId) -> Option<String> {
+        self.user_ids
+            .as_ref()
+            .and_then(|user_ids| (user_ids.0)(user_id))
+    }
+}
+
+/// Callback mapping a Slack user to its Matrix ID, like `@alice:example.org`, or `None` for
+/// users without a Matrix account.
+#[derive(Clone)]
+pub struct MatrixUserMapper(pub Arc<MatrixUserMapperFn>);
+
+type MatrixUserMapperFn = dyn Fn(&SlackUserId) -> Option<String> + Send + Sync;
+
+impl MatrixUserMapper {
+    pub fn new(
+        mapper: impl Fn(&SlackUserId) -> Option<String> + Send + Sync + 'static,
+    ) -> MatrixUserMapper {
+        MatrixUserMapper(Arc::new(mapper))
+    }
+}
+
+impl fmt::Debug for MatrixUserMapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MatrixUserMapper")
+    }
+}
+
+impl PartialEq for MatrixUserMapper {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(Arc::as_ptr(&self.0), Arc::as_ptr(&other.0))
+    }
+}
+
+/// Content of an `m.room.message` event: a plain `body` and its `formatted_body` in Matrix's
+/// HTML subset.
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct MatrixMessage {
+    pub msgtype: String,
+    pub body: String,
+    pub format: String,
+    pub formatted_body: String,
+    #[serde(rename = "m.relates_to", skip_serializing_if = "Option::is_none")]
+    pub relates_to: Option<MatrixRelatesTo>,
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct MatrixRelatesTo {
+    #[serde(rename = "m.in_reply_to")]
+    pub in_reply_to: MatrixInReplyTo,
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct MatrixInReplyTo {
+    pub event_id: String,
+}
+
+/// Matrix event a message replies to, quoted in a `<mx-reply>` fallback.
+#[derive(PartialEq, Debug, Clone)]
+pub struct MatrixReply {
+    pub room_id: String,
+    pub event_id: String,
+    /// Matrix ID of the sender of the event.
+    pub sender: String,
+    /// Plain body of the event.
+    pub body: String,
+}
+
+impl MatrixMessage {
+    fn new(body: String, formatted_body: String) -> MatrixMessage {
+        MatrixMessage {
+            msgtype: "m.text".to_string(),
+            body,
+            format: "org.matrix.custom.html".to_string(),
+            formatted_body,
+            relates_to: None,
+        }
+    }
+
+    /// Quote `reply` before the bodies and relate the message to it.
+    fn in_reply_to(mut self, reply: &MatrixReply) -> MatrixMessage {
+        let mut quote = reply.body.lines();
+        let first_line = quote.next().unwrap_or_default();
+        let mut body = format!("> <{}> {first_line}\n", reply.sender);
+        for line in quote {
+            body.push_str(&format!("> {line}\n"));
+        }
+        self.body = format!("{body}\n{}", self.body);
+        self.formatted_body = format!(
+            "<mx-reply><blockquote><a href=\"{}\">In reply to</a> <a href=\"{}\">{}</a><br />{}</blockquote></mx-reply>{}",
+            encode_double_quoted_attribute(&matrix_to_url(&[&reply.room_id, &reply.event_id])),
+            encode_double_quoted_attribute(&matrix_to_url(&[&reply.sender])),
+            encode_text(&reply.sender),
+            encode_text(&reply.body).replace('\n', "<br />"),
+            self.formatted_body
+        );
+        self.relates_to = Some(MatrixRelatesTo {
+            in_reply_to: MatrixInReplyTo {
+                event_id: reply.event_id.clone(),
+            },
+        });
+        self
+    }
+}
+
+/// Link to a Matrix user, room or event, like `https://matrix.to/#/%40alice%3Aexample.org`.
+pub fn matrix_to_url(ids: &[&str]) -> String {
+    let path = ids
+        .iter()
+        .map(|id| encode_url_component(id))
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("https://matrix.to/#/{path}")
+}
+
+/// Render Slack blocks as the content of a Matrix message.
+pub fn render_blocks_as_matrix(
+    blocks: &[SlackBlock],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> MatrixMessage {
+    let options = matrix_render_options(options);
+    MatrixMessage::new(
+        render_blocks_as_text_with_options(blocks, slack_references, &options),
+        render_blocks_as_html_with_options(blocks, slack_references, &options),
+    )
+}
+
+/// Render a Slack message as the content of a Matrix message: its blocks, or its mrkdwn `text`
+/// when it has no blocks, then its files and its legacy attachments, quoting `reply` if any.
+/// Reactions are left out, as Matrix has its own.
+pub fn render_message_as_matrix(
+    message: &SlackHistoryMessage,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+    reply: Option<&MatrixReply>,
+) -> MatrixMessage {
+    let options = matrix_render_options(options);
+    let message = MatrixMessage::new(
+        render_message_as_text(message, slack_references, &options),
+        render_message_as_html(message, slack_references, &options),
+    );
+    match reply {
+        Some(reply) => message.in_reply_to(reply),
+        None => message,
+    }
+}
+
+/// `options` with the Matrix rendering enabled.
+fn matrix_render_options(options: &RenderOptions) -> RenderOptions {
+    RenderOptions {
+        matrix: Some(options.matrix.clone().unwrap_or_default()),
+        ..options.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use url::Url;
+
+    use super::*;
+    use crate::{
+        attachment::SlackAttachment,
+        html::render_attachments_as_html,
+        test_utils::{history_message, message_with_attachment, rich_text_block},
+    };
+
+    fn matrix_options() -> RenderOptions {
+        RenderOptions {
+            matrix: Some(
+                MatrixOptions::new(|user_id| {
+                    (user_id.0 == "U1").then(|| "@alice:example.org".to_string())
+                })
+                .with_highlight_color("#ff5555"),
+            ),
+            ..RenderOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_matrix_to_url() {
+        assert_eq!(
+            matrix_to_url(&["@alice:example.org"]),
+            "https://matrix.to/#/%40alice%3Aexample.org"
+        );
+        assert_eq!(
+            matrix_to_url(&["!room:example.org", "$event"]),
+            "https://matrix.to/#/%21room%3Aexample.org/%24event"
+        );
+    }
+
+    #[test]
+    fn test_render_blocks_as_matrix() {
+        let blocks = vec![rich_text_block(serde_json::json!({
+            "type": "rich_text",
+            "elements": [
+                {
+                    "type": "rich_text_section",
+                    "elements": [
+                        { "type": "user", "user_id": "U1" },
+                        { "type": "text", "text": " and " },
+                        { "type": "user", "user_id": "U2" },
+                        { "type": "text", "text": " see " },
+                        { "type": "link", "url": "https://example.com", "text": "docs" },
+                        { "type": "text", "text": " " },
+                        { "type": "emoji", "name": "partyparrot" }
+                    ]
+                }
+            ]
+        }))];
+        let refs = SlackReferences {
+            users: HashMap::from([
+                (SlackUserId("U1".to_string()), Some("alice".to_string())),
+                (SlackUserId("U2".to_string()), Some("bob".to_string())),
+            ]),
+            emojis: HashMap::from([(
+                SlackEmojiName("partyparrot".to_string()),
+                Some(SlackEmojiRef::Url(
+                    Url::parse("https://emoji.slack-edge.com/partyparrot.gif").unwrap(),
+                )),
+            )]),
+            user_ids_to_highlight: Some(vec![SlackUserId("U2".to_string())]),
+            ..SlackReferences::default()
+        };
+        assert_eq!(
+            render_blocks_as_matrix(&blocks, &refs, &matrix_options()),
+            MatrixMessage {
+                msgtype: "m.text".to_string(),
+                body: "@alice and @bob see docs :partyparrot:".to_string(),
+                format: "org.matrix.custom.html".to_string(),
+                formatted_body:
+                    "<p><a href=\"https://matrix.to/#/%40alice%3Aexample.org\">@alice</a> and \
+                    <font data-mx-color=\"#ff5555\">@bob</font> see \
+                    <a href=\"https://example.com/\">docs</a> :partyparrot:</p>\n"
+                        .to_string(),
+                relates_to: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_blocks_as_matrix_without_styles() {
+        let blocks = vec![rich_text_block(serde_json::json!({
+            "type": "rich_text",
+            "elements": [
+                {
+                    "type": "rich_text_section",
+                    "elements": [{ "type": "emoji", "name": "partyparrot" }]
+                },
+                {
+                    "type": "rich_text_preformatted",
+                    "elements": [{ "type": "text", "text": "cargo test" }]
+                }
+            ]
+        }))];
+        let refs = SlackReferences {
+            emojis: HashMap::from([(
+                SlackEmojiName("partyparrot".to_string()),
+                Some(SlackEmojiRef::Url(
+                    Url::parse("mxc://example.org/partyparrot").unwrap(),
+                )),
+            )]),
+            ..SlackReferences::default()
+        };
+        assert_eq!(
+            render_blocks_as_matrix(&blocks, &refs, &RenderOptions::default()).formatted_body,
+            "<p><img data-mx-emoticon src=\"mxc://example.org/partyparrot\" alt=\":partyparrot:\" /></p>\n\
+            <pre><code>cargo test\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_attachment_image_as_matrix_link() {
+        let attachments = vec![SlackAttachment {
+            title: Some("Build".to_string()),
+            image_url: Some("https://ci.example.com/build.png?a=1&b=2".to_string()),
+            ..SlackAttachment::default()
+        }];
+        assert_eq!(
+            render_attachments_as_html(
+                &attachments,
+                &SlackReferences::default(),
+                &matrix_options()
+            ),
+            "<blockquote>\n<p><strong>Build</strong></p>\n\
+            <p><a href=\"https://ci.example.com/build.png?a=1&amp;b=2\">Build</a></p>\n\
+            </blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_message_as_matrix_without_reactions() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "Ship it?",
+            "reactions": [{ "name": "+1", "count": 3, "users": ["U1", "U2"] }]
+        }));
+        let message = render_message_as_matrix(
+            &message,
+            &SlackReferences::default(),
+            &matrix_options(),
+            None,
+        );
+        assert_eq!(message.body, "Ship it?");
+        assert_eq!(message.formatted_body, "<p>Ship it?</p>\n");
+    }
+
+    #[test]
+    fn test_render_message_without_blocks_as_matrix_with_pills() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "<@U1> deployed"
+        }));
+        let refs = SlackReferences {
+            users: HashMap::from([(SlackUserId("U1".to_string()), Some("alice".to_string()))]),
+            ..SlackReferences::default()
+        };
+        let message = render_message_as_matrix(&message, &refs, &matrix_options(), None);
+        assert_eq!(
+            message.formatted_body,
+            "<p><a href=\"https://matrix.to/#/%40alice%3Aexample.org\">@alice</a> deployed</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_message_as_matrix_with_reply() {
+        let reply = MatrixReply {
+            room_id: "!ops:example.org".to_string(),
+            event_id: "$deploy".to_string(),
+            sender: "@alice:example.org".to_string(),
+            body: "Deploying api\nv1.2.0".to_string(),
+        };
+        let message = render_message_as_matrix(
+            &message_with_attachment(),
+            &SlackReferences::default(),
+            &matrix_options(),
+            Some(&reply),
+        );
+        assert_eq!(
+            message.body,
+            "> <@alice:example.org> Deploying api\n\
+            > v1.2.0\n\
+            \n\
+            Deploy *finished*\n\
+            \n\
+            api v1.2.0\n\
+            All checks passed\n\
+            Environment: production\n\
+            Duration: 3m\n\
+            \n\
+            Build log"
+        );
+        assert_eq!(
+            message.formatted_body,
+            "<mx-reply><blockquote>\
+            <a href=\"https://matrix.to/#/%21ops%3Aexample.org/%24deploy\">In reply to</a> \
+            <a href=\"https://matrix.to/#/%40alice%3Aexample.org\">@alice:example.org</a>\
+            <br />Deploying api<br />v1.2.0</blockquote></mx-reply>\
+            <p>Deploy <strong>finished</strong></p>\n\
+            <blockquote>\n\
+            <p><strong>api v1.2.0</strong></p>\n\
+            <p>All checks passed</p>\n\
+            <div><strong>Environment</strong><br />production</div>\n\
+            <div><strong>Duration</strong><br />3m</div>\n\
+            </blockquote>\n\
+            <blockquote>\n<p>Build log</p>\n</blockquote>\n"
+        );
+        assert_eq!(
+            serde_json::to_value(&message).unwrap()["m.relates_to"],
+            serde_json::json!({ "m.in_reply_to": { "event_id": "$deploy" } })
+        );
+    }
+}
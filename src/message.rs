@@ -0,0 +1,150 @@
+use std::borrow::Cow;
+
+use slack_morphism::prelude::*;
+
+use crate::{
+    attachment::{find_attachments_in_message, message_attachments, SlackAttachment},
+    files::{find_files_in_message, message_files, SlackFileShare},
+    unfurl::{find_unfurls_in_message, SlackUnfurl},
+};
+
+/// What is rendered of a message, from the slack-morphism model or from raw JSON.
+pub(crate) struct MessageParts<'a> {
+    pub(crate) blocks: Cow<'a, [SlackBlock]>,
+    /// Types of the blocks of the raw JSON which do not match the slack-morphism model.
+    pub(crate) unparsed_blocks: Vec<String>,
+    pub(crate) files: Vec<SlackFileShare>,
+    /// Attachments, whose unfurls are not rendered as attachments.
+    pub(crate) attachments: Vec<SlackAttachment>,
+    pub(crate) unfurls: Vec<SlackUnfurl>,
+    pub(crate) reactions: Cow<'a, [SlackReaction]>,
+}
+
+impl<'a> MessageParts<'a> {
+    /// Parts of a message from the slack-morphism model, which has no unfurls.
+    pub(crate) fn new(content: &'a SlackMessageContent) -> Self {
+        MessageParts {
+            blocks: message_blocks(content),
+            unparsed_blocks: vec![],
+            files: message_files(content),
+            attachments: message_attachments(content),
+            unfurls: vec![],
+            reactions: Cow::Borrowed(content.reactions.as_deref().unwrap_or_default()),
+        }
+    }
+}
+
+impl MessageParts<'static> {
+    /// Parts of a message in the JSON format of the Slack API. Each block and reaction is read
+    /// on its own, so that one not matching the slack-morphism model does not drop the others.
+    /// The mrkdwn `text` is rendered when no block can be read.
+    pub(crate) fn from_json(message: &serde_json::Value) -> Self {
+        let mut blocks = vec![];
+        let mut unparsed_blocks = vec![];
+        for block in json_array(message, "blocks") {
+            match serde_json::from_value(block.clone()) {
+                Ok(block) => blocks.push(block),
+                Err(_) => unparsed_blocks.push(
+                    block
+                        .get("type")
+                        .and_then(|t| t.as_str())
+                        .unwrap_or("unknown")
+                        .to_string(),
+                ),
+            }
+        }
+        let text = message.get("text").and_then(|text| text.as_str());
+        if let (true, Some(text)) = (blocks.is_empty(), text.filter(|text| !text.is_empty())) {
+            blocks.push(mrkdwn_section(text));
+        }
+        let reactions = json_array(message, "reactions")
+            .filter_map(|reaction| {
+                let mut reaction = reaction.clone();
+                // Reactions in some payloads only have a count
+                if let Some(reaction) = reaction.as_object_mut() {
+                    reaction.entry("users").or_insert(serde_json::json!([]));
+                }
+                serde_json::from_value(reaction).ok()
+            })
+            .collect::<Vec<_>>();
+        MessageParts {
+            blocks: Cow::Owned(blocks),
+            unparsed_blocks,
+            files: find_files_in_message(message),
+            attachments: find_attachments_in_message(message),
+            unfurls: find_unfurls_in_message(message),
+            reactions: Cow::Owned(reactions),
+        }
+    }
+}
+
+fn json_array<'a>(
+    value: &'a serde_json::Value,
+    name: &str,
+) -> impl Iterator<Item = &'a serde_json::Value> {
+    value
+        .get(name)
+        .and_then(|array| array.as_array())
+        .into_iter()
+        .flatten()
+}
+
+/// Blocks of a message, or a section with its mrkdwn `text` fallback when it has no blocks.
+pub(crate) fn message_blocks(content: &SlackMessageContent) -> Cow<'_, [SlackBlock]> {
+    match (&content.blocks, &content.text) {
+        (Some(blocks), _) if !blocks.is_empty() => Cow::Borrowed(blocks),
+        (_, Some(text)) if !text.is_empty() => Cow::Owned(vec![mrkdwn_section(text)]),
+        _ => Cow::Borrowed(&[]),
+    }
+}
+
+/// Section block with mrkdwn `text`, to render attachment texts like block texts.
+pub(crate) fn mrkdwn_section(text: &str) -> SlackBlock {
+    SlackBlock::Section(SlackSectionBlock::new().with_text(SlackBlockText::MarkDown(
+        SlackBlockMarkDownText::new(text.to_string()),
+    )))
+}
+
+/// CSS color of an attachment color bar: a hex code with or without `#`, or one of the
+/// `good`, `warning` and `danger` named colors.
+pub(crate) fn attachment_color(color: &str) -> Option<String> {
+    match color {
+        "good" => Some("#2eb886".to_string()),
+        "warning" => Some("#daa038".to_string()),
+        "danger" => Some("#a30200".to_string()),
+        _ => {
+            let hex = color.strip_prefix('#').unwrap_or(color);
+            (matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+                .then(|| format!("#{hex}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_blocks_fall_back_to_text() {
+        let content = SlackMessageContent::new().with_text("Hello *World*".to_string());
+        assert_eq!(
+            message_blocks(&content).into_owned(),
+            vec![mrkdwn_section("Hello *World*")]
+        );
+
+        let content = content.with_blocks(vec![SlackBlock::Divider(SlackDividerBlock::new())]);
+        assert_eq!(
+            message_blocks(&content).into_owned(),
+            vec![SlackBlock::Divider(SlackDividerBlock::new())]
+        );
+        assert!(message_blocks(&SlackMessageContent::new()).is_empty());
+    }
+
+    #[test]
+    fn test_attachment_color() {
+        assert_eq!(attachment_color("good"), Some("#2eb886".to_string()));
+        assert_eq!(attachment_color("36a64f"), Some("#36a64f".to_string()));
+        assert_eq!(attachment_color("#FFF"), Some("#FFF".to_string()));
+        assert_eq!(attachment_color("red\" onclick=\""), None);
+    }
+}
@@ -0,0 +1,684 @@
+use slack_morphism::prelude::*;
+
+use crate::{
+    markdown::join,
+    options::RenderOptions,
+    time::format_rich_text_date,
+    visitor::{
+        visit_slack_block_image_element, visit_slack_block_mark_down_text,
+        visit_slack_block_plain_text, visit_slack_context_block, visit_slack_divider_block,
+        visit_slack_header_block, visit_slack_image_block, visit_slack_markdown_block,
+        visit_slack_section_block, visit_slack_video_block, SlackRichTextBlock, Visitor,
+    },
+};
+
+/// Render Slack blocks as Slack mrkdwn, e.g. to post them again in the `text` of a message.
+///
+/// Mentions, emoji and dates are rendered as Slack references (`<@U123>`, `:wave:`,
+/// `<!date^...>`), so Slack resolves them when the text is posted.
+///
+/// mrkdwn cannot escape `|` in link labels and date fallbacks: links whose label contains one are
+/// rendered as bare `<url>` links, and such dates as their formatted text.
+pub fn render_blocks_as_slack_mrkdwn(blocks: &[SlackBlock]) -> String {
+    render_blocks_as_slack_mrkdwn_with_options(blocks, &RenderOptions::default())
+}
+
+/// Render borrowed Slack blocks as Slack mrkdwn.
+/// `options.time` formats the fallback text of dates without one.
+pub fn render_blocks_as_slack_mrkdwn_with_options(
+    blocks: &[SlackBlock],
+    options: &RenderOptions,
+) -> String {
+    let mut block_renderer = MrkdwnRenderer::new(options);
+    for block in blocks {
+        block_renderer.visit_slack_block(block);
+    }
+    block_renderer.finish()
+}
+
+/// Escape the control characters of mrkdwn: `&`, `<` and `>`.
+pub fn escape_slack_mrkdwn(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Escape a URL of a link: the control characters of mrkdwn, and `|` percent-encoded.
+fn escape_slack_mrkdwn_url(url: &str) -> String {
+    escape_slack_mrkdwn(url).replace('|', "%7C")
+}
+
+struct MrkdwnRenderer<'a> {
+    sub_texts: Vec<String>,
+    options: &'a RenderOptions,
+}
+
+impl<'a> MrkdwnRenderer<'a> {
+    fn new(options: &'a RenderOptions) -> Self {
+        MrkdwnRenderer {
+            sub_texts: vec![],
+            options,
+        }
+    }
+
+    fn finish(self) -> String {
+        self.sub_texts.join("\n")
+    }
+}
+
+impl Visitor for MrkdwnRenderer<'_> {
+    fn visit_slack_section_block(&mut self, slack_section_block: &SlackSectionBlock) {
+        let mut section_renderer = MrkdwnRenderer::new(self.options);
+        visit_slack_section_block(&mut section_renderer, slack_section_block);
+        self.sub_texts.push(section_renderer.sub_texts.join("\n"));
+    }
+
+    fn visit_slack_block_plain_text(&mut self, slack_block_plain_text: &SlackBlockPlainText) {
+        self.sub_texts
+            .push(escape_slack_mrkdwn(&slack_block_plain_text.text));
+        visit_slack_block_plain_text(self, slack_block_plain_text);
+    }
+
+    fn visit_slack_header_block(&mut self, slack_header_block: &SlackHeaderBlock) {
+        let mut header_renderer = MrkdwnRenderer::new(self.options);
+        visit_slack_header_block(&mut header_renderer, slack_header_block);
+        self.sub_texts
+            .push(apply_style(header_renderer.sub_texts.join(""), "*"));
+    }
+
+    fn visit_slack_divider_block(&mut self, slack_divider_block: &SlackDividerBlock) {
+        self.sub_texts.push("---".to_string());
+        visit_slack_divider_block(self, slack_divider_block);
+    }
+
+    fn visit_slack_image_block(&mut self, slack_image_block: &SlackImageBlock) {
+        if let Some(image_url) = slack_image_block.image_url_or_file.image_url() {
+            self.sub_texts.push(render_link(
+                image_url.as_str(),
+                Some(&escape_slack_mrkdwn(&slack_image_block.alt_text)),
+            ));
+        }
+        visit_slack_image_block(self, slack_image_block);
+    }
+
+    fn visit_slack_block_image_element(
+        &mut self,
+        slack_block_image_element: &SlackBlockImageElement,
+    ) {
+        if let Some(image_url) = slack_block_image_element.image_url_or_file.image_url() {
+            self.sub_texts.push(render_link(
+                image_url.as_str(),
+                Some(&escape_slack_mrkdwn(&slack_block_image_element.alt_text)),
+            ));
+        }
+        visit_slack_block_image_element(self, slack_block_image_element);
+    }
+
+    fn visit_slack_block_mark_down_text(
+        &mut self,
+        slack_block_mark_down_text: &SlackBlockMarkDownText,
+    ) {
+        self.sub_texts.push(slack_block_mark_down_text.text.clone());
+        visit_slack_block_mark_down_text(self, slack_block_mark_down_text);
+    }
+
+    fn visit_slack_context_block(&mut self, slack_context_block: &SlackContextBlock) {
+        let mut context_renderer = MrkdwnRenderer::new(self.options);
+        visit_slack_context_block(&mut context_renderer, slack_context_block);
+        self.sub_texts.push(context_renderer.sub_texts.join(" "));
+    }
+
+    fn visit_slack_rich_text_block(&mut self, slack_rich_text_block: &SlackRichTextBlock) {
+        self.sub_texts.push(render_rich_text_block_as_mrkdwn(
+            &slack_rich_text_block.json_value,
+            self,
+        ));
+    }
+
+    fn visit_slack_video_block(&mut self, slack_video_block: &SlackVideoBlock) {
+        let title: SlackBlockText = slack_video_block.title.clone().into();
+        let title = match title {
+            SlackBlockText::Plain(plain_text) => escape_slack_mrkdwn(&plain_text.text),
+            SlackBlockText::MarkDown(md_text) => md_text.text,
+        };
+        match &slack_video_block.title_url {
+            Some(title_url) => self.sub_texts.push(apply_style(
+                render_link(title_url.as_str(), Some(&title)),
+                "*",
+            )),
+            None => self.sub_texts.push(apply_style(title, "*")),
+        }
+        if let Some(description) = slack_video_block.description.clone() {
+            let description: SlackBlockText = description.into();
+            self.sub_texts.push(match description {
+                SlackBlockText::Plain(plain_text) => escape_slack_mrkdwn(&plain_text.text),
+                SlackBlockText::MarkDown(md_text) => md_text.text,
+            });
+        }
+        visit_slack_video_block(self, slack_video_block);
+    }
+
+    fn visit_slack_markdown_block(&mut self, slack_markdown_block: &SlackMarkdownBlock) {
+        self.sub_texts
+            .push(render_markdown_as_mrkdwn(&slack_markdown_block.text));
+        visit_slack_markdown_block(self, slack_markdown_block);
+    }
+}
+
+/// Convert the standard Markdown of a `markdown` block to mrkdwn: headings are bolded, bullets
+/// become `•`, emphasis and links are rewritten and the rest is escaped.
+fn render_markdown_as_mrkdwn(text: &str) -> String {
+    let mut in_code_block = false;
+    text.lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") {
+                in_code_block = !in_code_block;
+                return "```".to_string();
+            }
+            if in_code_block {
+                return escape_slack_mrkdwn(line);
+            }
+            let indent = &line[..line.len() - trimmed.len()];
+            let heading = trimmed.trim_start_matches('#');
+            if heading.len() < trimmed.len()
+                && trimmed.len() - heading.len() <= 6
+                && heading.starts_with(' ')
+            {
+                // Headings are bold already
+                let heading = heading.trim().replace("**", "");
+                return apply_style(render_markdown_inline_as_mrkdwn(&heading), "*");
+            }
+            for bullet in ["- ", "* ", "+ "] {
+                if let Some(item) = trimmed.strip_prefix(bullet) {
+                    return format!("{indent}• {}", render_markdown_inline_as_mrkdwn(item));
+                }
+            }
+            render_markdown_inline_as_mrkdwn(line)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Convert the inline Markdown of a line to mrkdwn: code spans, emphasis, strikethrough, links,
+/// images, autolinks and backslash escapes.
+fn render_markdown_inline_as_mrkdwn(text: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(ch) = rest.chars().next() {
+        let after = &rest[ch.len_utf8()..];
+        match ch {
+            '\\' if after.starts_with(|c: char| c.is_ascii_punctuation()) => {
+                let escaped = &after[..1];
+                output.push_str(&escape_slack_mrkdwn(escaped));
+                rest = &after[1..];
+                continue;
+            }
+            '`' => {
+                if let Some(end) = after.find('`') {
+                    output.push_str(&format!("`{}`", escape_slack_mrkdwn(&after[..end])));
+                    rest = &after[end + 1..];
+                    continue;
+                }
+            }
+            '!' | '[' => {
+                let label_start = if ch == '!' {
+                    after.strip_prefix('[')
+                } else {
+                    Some(after)
+                };
+                if let Some((label, url, remaining)) = label_start.and_then(split_markdown_link) {
+                    output.push_str(&render_link(url, Some(&escape_slack_mrkdwn(label))));
+                    rest = remaining;
+                    continue;
+                }
+            }
+            '<' => {
+                if let Some(end) = after.find('>') {
+                    let url = &after[..end];
+                    if url.contains("://") && !url.contains(char::is_whitespace) {
+                        output.push_str(&render_link(url, None));
+                        rest = &after[end + 1..];
+                        continue;
+                    }
+                }
+            }
+            '*' | '_' | '~' => {
+                let double = after.starts_with(ch);
+                let marker = if double { &rest[..2] } else { &rest[..1] };
+                let inner_start = &rest[marker.len()..];
+                if let Some(end) = inner_start.find(marker) {
+                    let inner = &inner_start[..end];
+                    if !inner.is_empty() && !inner.starts_with(' ') && !inner.ends_with(' ') {
+                        let mrkdwn_marker = match (ch, double) {
+                            ('~', _) => "~",
+                            (_, true) => "*",
+                            (_, false) => "_",
+                        };
+                        output.push_str(&format!(
+                            "{mrkdwn_marker}{}{mrkdwn_marker}",
+                            render_markdown_inline_as_mrkdwn(inner)
+                        ));
+                        rest = &inner_start[end + marker.len()..];
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        output.push_str(&escape_slack_mrkdwn(&rest[..ch.len_utf8()]));
+        rest = after;
+    }
+    output
+}
+
+/// Label, URL and remaining text of a Markdown `label](url)` link, its opening `[` excluded.
+fn split_markdown_link(text: &str) -> Option<(&str, &str, &str)> {
+    let (label, rest) = text.split_once("](")?;
+    let (url, rest) = rest.split_once(')')?;
+    (!label.contains(']')).then_some((label, url, rest))
+}
+
+fn render_rich_text_block_as_mrkdwn(
+    json_value: &serde_json::Value,
+    renderer: &MrkdwnRenderer,
+) -> String {
+    let Some(serde_json::Value::Array(elements)) = json_value.get("elements") else {
+        return "".to_string();
+    };
+    elements
+        .iter()
+        .map(|element| {
+            let Some(serde_json::Value::Array(elements)) = element.get("elements") else {
+                return "".to_string();
+            };
+            match element.get("type").and_then(|t| t.as_str()) {
+                Some("rich_text_section") => render_rich_text_section_elements(elements, renderer),
+                Some("rich_text_list") => {
+                    render_rich_text_list_elements(element, elements, renderer)
+                }
+                Some("rich_text_preformatted") => format!(
+                    "```\n{}\n```",
+                    render_rich_text_section_elements(elements, renderer)
+                ),
+                Some("rich_text_quote") => render_rich_text_section_elements(elements, renderer)
+                    .lines()
+                    .map(|line| format!("> {line}"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                _ => "".to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_rich_text_section_elements(
+    elements: &[serde_json::Value],
+    renderer: &MrkdwnRenderer,
+) -> String {
+    join(
+        elements
+            .iter()
+            .map(|element| render_rich_text_section_element(element, renderer))
+            .collect(),
+        "",
+    )
+    .trim_end_matches('\n')
+    .to_string()
+}
+
+/// Render list items as `•` bullets or numbers, as mrkdwn has no list syntax.
+fn render_rich_text_list_elements(
+    list: &serde_json::Value,
+    elements: &[serde_json::Value],
+    renderer: &MrkdwnRenderer,
+) -> String {
+    let ordered = list.get("style").and_then(|s| s.as_str()) == Some("ordered");
+    let indent = list.get("indent").and_then(|i| i.as_u64()).unwrap_or(0);
+    let offset = list.get("offset").and_then(|o| o.as_u64()).unwrap_or(0);
+    let indent_prefix = "    ".repeat(indent.try_into().unwrap_or_default());
+    elements
+        .iter()
+        .filter_map(|element| match element.get("elements") {
+            Some(serde_json::Value::Array(elements)) => {
+                Some(render_rich_text_section_elements(elements, renderer))
+            }
+            _ => None,
+        })
+        .enumerate()
+        .map(|(index, item)| {
+            if ordered {
+                format!("{indent_prefix}{}. {item}", offset + index as u64 + 1)
+            } else {
+                format!("{indent_prefix}• {item}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_rich_text_section_element(
+    element: &serde_json::Value,
+    renderer: &MrkdwnRenderer,
+) -> String {
+    let field = |name: &str| element.get(name).and_then(|value| value.as_str());
+    let style = element.get("style");
+    match field("type") {
+        Some("text") => {
+            let Some(text) = field("text") else {
+                return "".to_string();
+            };
+            apply_all_styles(escape_slack_mrkdwn(text), style)
+        }
+        Some("channel") => field("channel_id")
+            .map(|channel_id| apply_all_styles(format!("<#{channel_id}>"), style))
+            .unwrap_or_default(),
+        Some("user") => field("user_id")
+            .map(|user_id| apply_all_styles(format!("<@{user_id}>"), style))
+            .unwrap_or_default(),
+        Some("usergroup") => field("usergroup_id")
+            .map(|usergroup_id| apply_all_styles(format!("<!subteam^{usergroup_id}>"), style))
+            .unwrap_or_default(),
+        Some("broadcast") => field("range")
+            .map(|range| apply_all_styles(format!("<!{range}>"), style))
+            .unwrap_or_default(),
+        Some("emoji") => field("name")
+            .map(|name| apply_all_styles(format!(":{name}:"), style))
+            .unwrap_or_default(),
+        Some("link") => field("url")
+            .map(|url| {
+                let label = field("text").map(escape_slack_mrkdwn);
+                apply_all_styles(render_link(url, label.as_deref()), style)
+            })
+            .unwrap_or_default(),
+        Some("date") => {
+            let Some(timestamp) = element.get("timestamp").and_then(|t| t.as_i64()) else {
+                return "".to_string();
+            };
+            let format = field("format").unwrap_or("{date_short}");
+            let fallback = field("fallback")
+                .map(|fallback| fallback.to_string())
+                .or_else(|| format_rich_text_date(element, &renderer.options.time))
+                .unwrap_or_else(|| timestamp.to_string());
+            // `^` and `|` would end the format or the fallback, and cannot be escaped
+            if format.contains(['^', '|']) || fallback.contains('|') {
+                let formatted =
+                    format_rich_text_date(element, &renderer.options.time).unwrap_or(fallback);
+                return apply_all_styles(escape_slack_mrkdwn(&formatted), style);
+            }
+            apply_all_styles(
+                format!(
+                    "<!date^{timestamp}^{}|{}>",
+                    escape_slack_mrkdwn(format),
+                    escape_slack_mrkdwn(&fallback)
+                ),
+                style,
+            )
+        }
+        _ => "".to_string(),
+    }
+}
+
+/// Render a link with an already escaped label. mrkdwn cannot escape `|`, which would end the
+/// URL of the link, so a label containing one is dropped rather than altered.
+fn render_link(url: &str, label: Option<&str>) -> String {
+    match label.filter(|label| {
+        !label.is_empty() && *label != escape_slack_mrkdwn(url) && !label.contains('|')
+    }) {
+        Some(label) => format!("<{}|{}>", escape_slack_mrkdwn_url(url), label),
+        None => format!("<{}>", escape_slack_mrkdwn_url(url)),
+    }
+}
+
+fn apply_all_styles(text: String, style: Option<&serde_json::Value>) -> String {
+    let is_styled = |name: &str| {
+        style
+            .and_then(|style| style.get(name))
+            .and_then(|styled| styled.as_bool())
+            .unwrap_or_default()
+    };
+    let mut text = text;
+    for (name, marker) in [
+        ("code", "`"),
+        ("strike", "~"),
+        ("italic", "_"),
+        ("bold", "*"),
+    ] {
+        if is_styled(name) {
+            text = apply_style(text, marker);
+        }
+    }
+    text
+}
+
+/// Wrap `text` with a style marker. Slack ignores markers next to whitespace, so leading and
+/// trailing whitespace is kept outside of the markers.
+fn apply_style(text: String, marker: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text;
+    }
+    let start = text.len() - text.trim_start().len();
+    let end = start + trimmed.len();
+    format!(
+        "{}{marker}{trimmed}{marker}{}",
+        &text[..start],
+        &text[end..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::*;
+    use crate::test_utils::rich_text_block;
+
+    #[test]
+    fn test_escape_slack_mrkdwn() {
+        assert_eq!(
+            escape_slack_mrkdwn("a < b && c > d"),
+            "a &lt; b &amp;&amp; c &gt; d"
+        );
+    }
+
+    #[test]
+    fn test_with_blocks() {
+        let blocks = vec![
+            SlackBlock::Header(SlackHeaderBlock::new("Release <1.0> ".into())),
+            SlackBlock::Section(SlackSectionBlock::new().with_text(SlackBlockText::MarkDown(
+                SlackBlockMarkDownText::new("Already *mrkdwn* &amp; kept".to_string()),
+            ))),
+            SlackBlock::Divider(SlackDividerBlock::new()),
+            SlackBlock::Context(SlackContextBlock::new(vec![
+                SlackContextBlockElement::Image(SlackBlockImageElement::new(
+                    SlackImageUrlOrFile::ImageUrl {
+                        image_url: Url::parse("https://example.com/a.png?x=1&y=2").unwrap(),
+                    },
+                    "Logo".to_string(),
+                )),
+                SlackContextBlockElement::Plain(SlackBlockPlainText::new("Q&A".to_string())),
+            ])),
+        ];
+        assert_eq!(
+            render_blocks_as_slack_mrkdwn(&blocks),
+            "*Release &lt;1.0&gt;* \n\
+            Already *mrkdwn* &amp; kept\n\
+            ---\n\
+            <https://example.com/a.png?x=1&amp;y=2|Logo> Q&amp;A"
+        );
+    }
+
+    #[test]
+    fn test_with_markdown_block() {
+        let blocks = vec![SlackBlock::Markdown(SlackMarkdownBlock::new(
+            "## Release **1.0**\n\
+            Fixed _bugs_ & ~~regressions~~ in `a<b`, see [the docs](https://example.com/docs) \
+            and <https://example.com>\n\
+            - one \\[x\\]\n\
+            ```\nif a > b {}\n```"
+                .to_string(),
+        ))];
+        assert_eq!(
+            render_blocks_as_slack_mrkdwn(&blocks),
+            "*Release 1.0*\n\
+            Fixed _bugs_ &amp; ~regressions~ in `a&lt;b`, see <https://example.com/docs|the docs> \
+            and <https://example.com>\n\
+            • one [x]\n\
+            ```\nif a &gt; b {}\n```"
+        );
+    }
+
+    #[test]
+    fn test_escape_link_labels_and_date_formats() {
+        let blocks = vec![rich_text_block(serde_json::json!({
+            "type": "rich_text",
+            "elements": [
+                {
+                    "type": "rich_text_section",
+                    "elements": [
+                        { "type": "link", "url": "https://example.com/?q=a|b", "text": "a | b" },
+                        { "type": "text", "text": " " },
+                        { "type": "date", "timestamp": 1392734382, "format": "{date_num} ^ {time}" },
+                        { "type": "text", "text": " " },
+                        { "type": "date", "timestamp": 1392734382, "format": "{date_num}", "fallback": "18|02" }
+                    ]
+                }
+            ]
+        }))];
+        assert_eq!(
+            render_blocks_as_slack_mrkdwn(&blocks),
+            "<https://example.com/?q=a%7Cb> 2014-02-18 ^ 14:39 2014-02-18"
+        );
+
+        let blocks = vec![SlackBlock::Video(
+            SlackVideoBlock::new(
+                "alt text".into(),
+                "Q1 | Q2".into(),
+                "https://example.com/thumbnail.jpg".parse().unwrap(),
+                "https://example.com/video_embed.avi".parse().unwrap(),
+            )
+            .with_title_url("https://example.com/video".parse().unwrap()),
+        )];
+        assert_eq!(
+            render_blocks_as_slack_mrkdwn(&blocks),
+            "*<https://example.com/video>*"
+        );
+    }
+
+    #[test]
+    fn test_escape_image_alt_texts_and_markdown_link_labels() {
+        let blocks = vec![
+            SlackBlock::Image(SlackImageBlock::new(
+                SlackImageUrlOrFile::ImageUrl {
+                    image_url: "https://x.com/a.png".parse().unwrap(),
+                },
+                "a > <!channel> & b".to_string(),
+            )),
+            SlackBlock::Markdown(SlackMarkdownBlock::new(
+                "[a <!here> & b](https://x.com)".to_string(),
+            )),
+        ];
+        assert_eq!(
+            render_blocks_as_slack_mrkdwn(&blocks),
+            "<https://x.com/a.png|a &gt; &lt;!channel&gt; &amp; b>\n\
+            <https://x.com|a &lt;!here&gt; &amp; b>"
+        );
+    }
+
+    #[test]
+    fn test_with_rich_text_references() {
+        let blocks = vec![rich_text_block(serde_json::json!({
+            "type": "rich_text",
+            "elements": [
+                {
+                    "type": "rich_text_section",
+                    "elements": [
+                        { "type": "user", "user_id": "U123" },
+                        { "type": "text", "text": " please check " },
+                        { "type": "channel", "channel_id": "C123" },
+                        { "type": "text", "text": " with " },
+                        { "type": "usergroup", "usergroup_id": "S123" },
+                        { "type": "text", "text": " and " },
+                        { "type": "broadcast", "range": "here" },
+                        { "type": "text", "text": " " },
+                        { "type": "emoji", "name": "wave" },
+                        { "type": "text", "text": " " },
+                        { "type": "link", "url": "https://example.com/?a=1&b=2", "text": "docs <v2>" },
+                        { "type": "text", "text": " due " },
+                        { "type": "date", "timestamp": 1392734382, "format": "{date_short}" }
+                    ]
+                }
+            ]
+        }))];
+        assert_eq!(
+            render_blocks_as_slack_mrkdwn(&blocks),
+            "<@U123> please check <#C123> with <!subteam^S123> and <!here> :wave: \
+            <https://example.com/?a=1&amp;b=2|docs &lt;v2&gt;> due \
+            <!date^1392734382^{date_short}|Feb 18, 2014>"
+        );
+    }
+
+    #[test]
+    fn test_with_rich_text_styles() {
+        let blocks = vec![rich_text_block(serde_json::json!({
+            "type": "rich_text",
+            "elements": [
+                {
+                    "type": "rich_text_section",
+                    "elements": [
+                        { "type": "text", "text": "bold ", "style": { "bold": true } },
+                        { "type": "text", "text": "both", "style": { "bold": true, "italic": true } },
+                        { "type": "text", "text": " " },
+                        { "type": "text", "text": "a < b", "style": { "code": true } },
+                        { "type": "text", "text": " " },
+                        { "type": "text", "text": "gone", "style": { "strike": true } }
+                    ]
+                }
+            ]
+        }))];
+        assert_eq!(
+            render_blocks_as_slack_mrkdwn(&blocks),
+            "*bold* *_both_* `a &lt; b` ~gone~"
+        );
+    }
+
+    #[test]
+    fn test_with_rich_text_lists_quotes_and_preformatted() {
+        let blocks = vec![rich_text_block(serde_json::json!({
+            "type": "rich_text",
+            "elements": [
+                {
+                    "type": "rich_text_list",
+                    "style": "bullet",
+                    "elements": [
+                        { "type": "rich_text_section", "elements": [{ "type": "text", "text": "one" }] },
+                        { "type": "rich_text_section", "elements": [{ "type": "text", "text": "two" }] }
+                    ]
+                },
+                {
+                    "type": "rich_text_list",
+                    "style": "ordered",
+                    "indent": 1,
+                    "elements": [
+                        { "type": "rich_text_section", "elements": [{ "type": "text", "text": "first" }] },
+                        { "type": "rich_text_section", "elements": [{ "type": "text", "text": "second" }] }
+                    ]
+                },
+                {
+                    "type": "rich_text_quote",
+                    "elements": [{ "type": "text", "text": "quoted\ntwice" }]
+                },
+                {
+                    "type": "rich_text_preformatted",
+                    "elements": [{ "type": "text", "text": "if a > b {}" }]
+                }
+            ]
+        }))];
+        assert_eq!(
+            render_blocks_as_slack_mrkdwn(&blocks),
+            "• one\n• two\n    1. first\n    2. second\n> quoted\n> twice\n```\nif a &gt; b {}\n```"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use slack_morphism::prelude::*;
+
+use crate::{
+    html::HtmlRenderer,
+    markdown::MarkdownRenderer,
+    options::RenderOptions,
+    references::SlackReferences,
+    report::{RenderReport, RenderReportFinder},
+    text::TextRenderer,
+    visitor::{SlackEventBlock, SlackRichTextBlock, SlackUnsupportedBlock, Visitor},
+};
+
+/// Markdown, HTML and text renderings of the same Slack blocks.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct RenderedBlocks {
+    pub markdown: String,
+    pub html: String,
+    pub text: String,
+    /// What could not be rendered faithfully, in any of the formats.
+    pub report: RenderReport,
+}
+
+/// Render Slack blocks as Markdown, HTML and text, along with their report, in a single
+/// traversal. Each block is visited once and handed to the three renderers and to the report,
+/// so rich text blocks are serialized to JSON once, each renderer then walking that JSON on its
+/// own and the report walking it once for all formats.
+pub fn render_blocks(
+    blocks: &[SlackBlock],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> RenderedBlocks {
+    let mut block_renderer = MultiRenderer {
+        markdown: MarkdownRenderer::new(slack_references, options),
+        html: HtmlRenderer::new(slack_references, options),
+        text: TextRenderer::new(slack_references, options),
+        report: RenderReportFinder::new(options),
+    };
+    for block in blocks {
+        block_renderer.visit_slack_block(block);
+    }
+    RenderedBlocks {
+        markdown: block_renderer.markdown.finish(),
+        html: block_renderer.html.finish(),
+        text: block_renderer.text.finish(),
+        report: block_renderer.report.finish(slack_references),
+    }
+}
+
+struct MultiRenderer<'a> {
+    markdown: MarkdownRenderer<'a>,
+    html: HtmlRenderer<'a>,
+    text: TextRenderer<'a>,
+    report: RenderReportFinder<'a>,
+}
+
+impl Visitor for MultiRenderer<'_> {
+    fn visit_slack_section_block(&mut self, slack_section_block: &SlackSectionBlock) {
+        self.markdown.visit_slack_section_block(slack_section_block);
+        self.html.visit_slack_section_block(slack_section_block);
+        self.text.visit_slack_section_block(slack_section_block);
+        self.report.visit_slack_section_block(slack_section_block);
+    }
+
+    fn visit_slack_header_block(&mut self, slack_header_block: &SlackHeaderBlock) {
+        self.markdown.visit_slack_header_block(slack_header_block);
+        self.html.visit_slack_header_block(slack_header_block);
+        self.text.visit_slack_header_block(slack_header_block);
+        self.report.visit_slack_header_block(slack_header_block);
+    }
+
+    fn visit_slack_divider_block(&mut self, slack_divider_block: &SlackDividerBlock) {
+        self.markdown.visit_slack_divider_block(slack_divider_block);
+        self.html.visit_slack_divider_block(slack_divider_block);
+        self.text.visit_slack_divider_block(slack_divider_block);
+        self.report.visit_slack_divider_block(slack_divider_block);
+    }
+
+    fn visit_slack_image_block(&mut self, slack_image_block: &SlackImageBlock) {
+        self.markdown.visit_slack_image_block(slack_image_block);
+        self.html.visit_slack_image_block(slack_image_block);
+        self.text.visit_slack_image_block(slack_image_block);
+        self.report.visit_slack_image_block(slack_image_block);
+    }
+
+    fn visit_slack_actions_block(&mut self, slack_actions_block: &SlackActionsBlock) {
+        self.markdown.visit_slack_actions_block(slack_actions_block);
+        self.html.visit_slack_actions_block(slack_actions_block);
+        self.text.visit_slack_actions_block(slack_actions_block);
+        self.report.visit_slack_actions_block(slack_actions_block);
+    }
+
+    fn visit_slack_context_block(&mut self, slack_context_block: &SlackContextBlock) {
+        self.markdown.visit_slack_context_block(slack_context_block);
+        self.html.visit_slack_context_block(slack_context_block);
+        self.text.visit_slack_context_block(slack_context_block);
+        self.report.visit_slack_context_block(slack_context_block);
+    }
+
+    fn visit_slack_input_block(&mut self, slack_input_block: &SlackInputBlock) {
+        self.markdown.visit_slack_input_block(slack_input_block);
+        self.html.visit_slack_input_block(slack_input_block);
+        self.text.visit_slack_input_block(slack_input_block);
+        self.report.visit_slack_input_block(slack_input_block);
+    }
+
+    fn visit_slack_file_block(&mut self, slack_file_block: &SlackFileBlock) {
+        self.markdown.visit_slack_file_block(slack_file_block);
+        self.html.visit_slack_file_block(slack_file_block);
+        self.text.visit_slack_file_block(slack_file_block);
+        self.report.visit_slack_file_block(slack_file_block);
+    }
+
+    fn visit_slack_video_block(&mut self, slack_video_block: &SlackVideoBlock) {
+        self.markdown.visit_slack_video_block(slack_video_block);
+        self.html.visit_slack_video_block(slack_video_block);
+        self.text.visit_slack_video_block(slack_video_block);
+        self.report.visit_slack_video_block(slack_video_block);
+    }
+
+    fn visit_slack_rich_text_block(&mut self, slack_rich_text_block: &SlackRichTextBlock) {
+        self.markdown
+            .visit_slack_rich_text_block(slack_rich_text_block);
+        self.html.visit_slack_rich_text_block(slack_rich_text_block);
+        self.text.visit_slack_rich_text_block(slack_rich_text_block);
+        self.report
+            .visit_slack_rich_text_block(slack_rich_text_block);
+    }
+
+    fn visit_slack_event_block(&mut self, slack_event_block: &SlackEventBlock) {
+        self.markdown.visit_slack_event_block(slack_event_block);
+        self.html.visit_slack_event_block(slack_event_block);
+        self.text.visit_slack_event_block(slack_event_block);
+        self.report.visit_slack_event_block(slack_event_block);
+    }
+
+    fn visit_slack_markdown_block(&mut self, slack_markdown_block: &SlackMarkdownBlock) {
+        self.markdown
+            .visit_slack_markdown_block(slack_markdown_block);
+        self.html.visit_slack_markdown_block(slack_markdown_block);
+        self.text.visit_slack_markdown_block(slack_markdown_block);
+        self.report.visit_slack_markdown_block(slack_markdown_block);
+    }
+
+    fn visit_slack_unsupported_block(&mut self, slack_unsupported_block: &SlackUnsupportedBlock) {
+        self.markdown
+            .visit_slack_unsupported_block(slack_unsupported_block);
+        self.html
+            .visit_slack_unsupported_block(slack_unsupported_block);
+        self.text
+            .visit_slack_unsupported_block(slack_unsupported_block);
+        self.report
+            .visit_slack_unsupported_block(slack_unsupported_block);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        html::render_blocks_as_html_with_options, markdown::render_blocks_as_markdown_with_options,
+        report::build_render_report, test_utils::rich_text_block,
+        text::render_blocks_as_text_with_options,
+    };
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!(
+            render_blocks(&[], &SlackReferences::default(), &RenderOptions::default()),
+            RenderedBlocks::default()
+        );
+    }
+
+    #[test]
+    fn test_renders_same_output_as_each_renderer() {
+        let blocks = vec![
+            SlackBlock::Header(SlackHeaderBlock::new("Title".into())),
+            SlackBlock::Section(SlackSectionBlock::new().with_text(SlackBlockText::MarkDown(
+                SlackBlockMarkDownText::new("*Text*".to_string()),
+            ))),
+            SlackBlock::Divider(SlackDividerBlock::new()),
+            rich_text_block(serde_json::json!({
+                "type": "rich_text",
+                "elements": [
+                    {
+                        "type": "rich_text_section",
+                        "elements": [
+                            {
+                                "type": "text",
+                                "text": "Hello "
+                            },
+                            {
+                                "type": "user",
+                                "user_id": "U123456"
+                            }
+                        ]
+                    }
+                ]
+            })),
+        ];
+        let slack_references = SlackReferences {
+            users: HashMap::from([(
+                SlackUserId("U123456".to_string()),
+                Some("john.doe".to_string()),
+            )]),
+            user_ids_to_highlight: Some(vec![SlackUserId("U123456".to_string())]),
+            ..SlackReferences::default()
+        };
+        let options = RenderOptions {
+            handle_delimiter: Some("`".to_string()),
+            default_style_class: "text-primary".to_string(),
+            highlight_style_class: "text-accent".to_string(),
+            ..RenderOptions::default()
+        };
+
+        assert_eq!(
+            render_blocks(&blocks, &slack_references, &options),
+            RenderedBlocks {
+                markdown: render_blocks_as_markdown_with_options(
+                    &blocks,
+                    &slack_references,
+                    &options
+                ),
+                html: render_blocks_as_html_with_options(&blocks, &slack_references, &options),
+                text: render_blocks_as_text_with_options(&blocks, &slack_references, &options),
+                report: build_render_report(&blocks, &slack_references, &options),
+            }
+        );
+        assert_eq!(
+            render_blocks(&blocks, &slack_references, &options).markdown,
+            "## Title\n*Text*\n---\n\nHello `@john.doe`"
+        );
+    }
+
+    #[test]
+    fn test_reports_what_could_not_be_rendered() {
+        let blocks = vec![
+            SlackBlock::Actions(SlackActionsBlock::new(vec![])),
+            rich_text_block(serde_json::json!({
+                "type": "rich_text",
+                "elements": [
+                    {
+                        "type": "rich_text_section",
+                        "elements": [
+                            { "type": "channel", "channel_id": "C123" }
+                        ]
+                    }
+                ]
+            })),
+        ];
+
+        let rendered = render_blocks(
+            &blocks,
+            &SlackReferences::default(),
+            &RenderOptions::default(),
+        );
+        assert_eq!(rendered.text, "#C123");
+        assert_eq!(
+            rendered.report,
+            RenderReport {
+                unresolved_references: SlackReferences {
+                    channels: HashMap::from([(SlackChannelId("C123".to_string()), None)]),
+                    ..SlackReferences::default()
+                },
+                unsupported_blocks: vec!["actions".to_string()],
+                ..RenderReport::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,434 @@
+use std::{fmt, sync::Arc};
+
+use slack_morphism::prelude::*;
+
+use crate::{
+    ansi::AnsiOptions, matrix::MatrixOptions, permalink::SlackPermalinkBuilder, time::TimeOptions,
+    transcript::TranscriptLabels,
+};
+
+/// Options shared by the Markdown, HTML and text renderers.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Delimiter wrapped around user and usergroup mentions in Markdown (e.g. `@` handles as `` `@john` ``).
+    pub handle_delimiter: Option<String>,
+    /// CSS class of the `<span>` wrapping mentions in HTML.
+    pub default_style_class: String,
+    /// CSS class of the `<span>` wrapping highlighted mentions in HTML.
+    pub highlight_style_class: String,
+    /// Per-category CSS classes of highlighted mentions in HTML.
+    pub highlight_style_classes: HighlightStyleClasses,
+    /// Render highlighted mentions in bold in Markdown.
+    pub markdown_bold_highlights: bool,
+    /// Which name of a user is rendered in mentions.
+    pub user_display_policy: UserDisplayPolicy,
+    /// Add the avatar of mentioned users in HTML.
+    pub html_user_avatar: bool,
+    /// Add the real name of mentioned users as a `title` tooltip in HTML.
+    pub html_user_title: bool,
+    /// URL templates turning mentions into links in Markdown and HTML.
+    pub mention_url_templates: MentionUrlTemplates,
+    /// What is rendered for mentions missing from the Slack references.
+    pub unresolved_mention_placeholders: UnresolvedMentionPlaceholders,
+    /// How emoji are rendered in Markdown and HTML. Text has no images nor markup, so it
+    /// renders standard emoji as Unicode and other emoji as their `:shortcode:` whatever the policy.
+    pub emoji_policy: EmojiPolicy,
+    /// Width and height of emoji images in HTML, in pixels.
+    pub html_emoji_size: Option<u32>,
+    /// Add the `:shortcode:` of emoji images as a `title` tooltip in HTML.
+    pub html_emoji_title: bool,
+    /// Render emoji that are not rendered as Unicode in text as a description like `(party parrot)`
+    /// rather than their `:shortcode:`.
+    pub text_emoji_descriptions: bool,
+    /// Rewrite the private URLs of files shared in messages, e.g. to go through a proxy
+    /// holding the Slack token.
+    pub file_url_rewriter: Option<FileUrlRewriter>,
+    /// Formatting of message times, rich text dates and mrkdwn date tokens.
+    pub time: TimeOptions,
+    /// Link message times to the messages in Slack in Markdown and HTML transcripts.
+    pub permalinks: Option<SlackPermalinkBuilder>,
+    /// Labels of the headers of transcripts, like the edited marker.
+    pub transcript_labels: TranscriptLabels,
+    /// Width, colors and hyperlinks of the terminal rendering.
+    pub ansi: AnsiOptions,
+    /// Restrict the HTML rendering to the subset allowed in Matrix messages, with mentions of
+    /// users having a Matrix ID rendered as pills.
+    pub matrix: Option<MatrixOptions>,
+}
+
+/// How emoji are rendered.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub enum EmojiPolicy {
+    /// Standard emoji as Unicode characters, custom emoji as images.
+    #[default]
+    Unicode,
+    /// Every emoji as its `:shortcode:`.
+    Shortcode,
+    /// Standard emoji as images from a CDN, custom emoji as images.
+    Image(EmojiImageCdn),
+    /// Emoji rendered by a callback, falling back to [`EmojiPolicy::Unicode`] when it returns
+    /// `None`.
+    Custom(EmojiCallback),
+}
+
+/// CDN serving standard emoji as images named after their code points, like Twemoji or Noto.
+///
+/// `{codepoints}` is replaced by the lowercase hexadecimal code points of the emoji joined with
+/// `separator`, the U+FE0F variation selectors being kept as the CDN names its files.
+#[derive(PartialEq, Debug, Clone)]
+pub struct EmojiImageCdn {
+    pub url_template: String,
+    pub separator: String,
+    pub variation_selectors: VariationSelectors,
+}
+
+/// U+FE0F variation selectors kept in the file names of a CDN.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum VariationSelectors {
+    /// Kept only in sequences with a zero width joiner, as in Twemoji.
+    #[default]
+    KeptWithZeroWidthJoiner,
+    /// Always dropped, as in Noto.
+    Dropped,
+    /// Always kept.
+    Kept,
+}
+
+impl EmojiImageCdn {
+    pub fn twemoji() -> EmojiImageCdn {
+        EmojiImageCdn {
+            url_template:
+                "https://cdn.jsdelivr.net/gh/jdecked/twemoji@15.1.0/assets/72x72/{codepoints}.png"
+                    .to_string(),
+            separator: "-".to_string(),
+            variation_selectors: VariationSelectors::KeptWithZeroWidthJoiner,
+        }
+    }
+
+    pub fn noto() -> EmojiImageCdn {
+        EmojiImageCdn {
+            url_template:
+                "https://cdn.jsdelivr.net/gh/googlefonts/noto-emoji@main/png/72/emoji_u{codepoints}.png"
+                    .to_string(),
+            separator: "_".to_string(),
+            variation_selectors: VariationSelectors::Dropped,
+        }
+    }
+
+    /// URL of the image of a Unicode emoji.
+    pub fn image_url(&self, emoji: &str) -> String {
+        let keep_variation_selectors = match self.variation_selectors {
+            VariationSelectors::KeptWithZeroWidthJoiner => emoji.contains('\u{200D}'),
+            VariationSelectors::Dropped => false,
+            VariationSelectors::Kept => true,
+        };
+        let codepoints = emoji
+            .chars()
+            .filter(|c| keep_variation_selectors || *c != '\u{FE0F}')
+            .map(|c| format!("{:x}", c as u32))
+            .collect::<Vec<_>>()
+            .join(&self.separator);
+        self.url_template.replace("{codepoints}", &codepoints)
+    }
+}
+
+/// Callback rendering an emoji from its name and, for standard emoji, its Unicode characters.
+/// Its output is inserted as is, without escaping.
+#[derive(Clone)]
+pub struct EmojiCallback(pub Arc<EmojiCallbackFn>);
+
+type EmojiCallbackFn = dyn Fn(&SlackEmojiName, Option<&str>) -> Option<String> + Send + Sync;
+
+impl EmojiCallback {
+    pub fn new(
+        callback: impl Fn(&SlackEmojiName, Option<&str>) -> Option<String> + Send + Sync + 'static,
+    ) -> EmojiCallback {
+        EmojiCallback(Arc::new(callback))
+    }
+}
+
+impl fmt::Debug for EmojiCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EmojiCallback")
+    }
+}
+
+impl PartialEq for EmojiCallback {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(Arc::as_ptr(&self.0), Arc::as_ptr(&other.0))
+    }
+}
+
+/// Callback rewriting the private URL (`url_private`, `url_private_download` or a thumbnail)
+/// of a file from its ID and the URL.
+#[derive(Clone)]
+pub struct FileUrlRewriter(pub Arc<FileUrlRewriterFn>);
+
+type FileUrlRewriterFn = dyn Fn(&SlackFileId, &str) -> String + Send + Sync;
+
+impl FileUrlRewriter {
+    pub fn new(rewriter: impl Fn(&SlackFileId, &str) -> String + Send + Sync + 'static) -> Self {
+        FileUrlRewriter(Arc::new(rewriter))
+    }
+
+    pub(crate) fn rewrite(&self, file_id: &SlackFileId, url: &str) -> String {
+        (self.0)(file_id, url)
+    }
+}
+
+impl fmt::Debug for FileUrlRewriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FileUrlRewriter")
+    }
+}
+
+impl PartialEq for FileUrlRewriter {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(Arc::as_ptr(&self.0), Arc::as_ptr(&other.0))
+    }
+}
+
+/// CSS classes of highlighted mentions by category, `highlight_style_class` being used for
+/// categories without a class.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct HighlightStyleClasses {
+    pub user: Option<String>,
+    pub usergroup: Option<String>,
+    pub channel: Option<String>,
+    pub broadcast: Option<String>,
+}
+
+/// Name rendered for a user mention, falling back to the other names when empty.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum UserDisplayPolicy {
+    /// The display name of the user profile.
+    #[default]
+    DisplayName,
+    /// The real name of the user profile.
+    RealName,
+    /// The name stored in `SlackReferences::users`.
+    Name,
+}
+
+/// Text rendered in place of a mention missing from the Slack references.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub enum UnresolvedMentionPlaceholder {
+    /// The raw ID, e.g. `@U123456` or `#C123456`.
+    #[default]
+    RawId,
+    /// A label rendered as is, e.g. `unknown user`.
+    Label(String),
+    /// The Slack token of the mention, e.g. `<@U123456>`, which can be rendered again later.
+    /// Markdown renders it as code, as it would otherwise be taken for an autolink.
+    SlackToken,
+    /// Nothing at all.
+    Omit,
+}
+
+/// Placeholders of unresolved mentions, by kind.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct UnresolvedMentionPlaceholders {
+    pub user: UnresolvedMentionPlaceholder,
+    pub channel: UnresolvedMentionPlaceholder,
+    pub usergroup: UnresolvedMentionPlaceholder,
+}
+
+impl UnresolvedMentionPlaceholders {
+    pub fn new() -> UnresolvedMentionPlaceholders {
+        UnresolvedMentionPlaceholders::default()
+    }
+
+    /// Placeholder of an unresolved user mention, `None` if it is omitted.
+    pub fn user(&self, user_id: &SlackUserId) -> Option<String> {
+        render_placeholder(
+            &self.user,
+            format!("@{}", user_id.0),
+            format!("<@{}>", user_id.0),
+        )
+    }
+
+    /// Placeholder of an unresolved channel mention, `None` if it is omitted.
+    pub fn channel(&self, channel_id: &SlackChannelId) -> Option<String> {
+        render_placeholder(
+            &self.channel,
+            format!("#{}", channel_id.0),
+            format!("<#{}>", channel_id.0),
+        )
+    }
+
+    /// Placeholder of an unresolved usergroup mention, `None` if it is omitted.
+    pub fn usergroup(&self, usergroup_id: &SlackUserGroupId) -> Option<String> {
+        render_placeholder(
+            &self.usergroup,
+            format!("@{}", usergroup_id.0),
+            format!("<!subteam^{}>", usergroup_id.0),
+        )
+    }
+}
+
+fn render_placeholder(
+    placeholder: &UnresolvedMentionPlaceholder,
+    raw_id: String,
+    slack_token: String,
+) -> Option<String> {
+    match placeholder {
+        UnresolvedMentionPlaceholder::RawId => Some(raw_id),
+        UnresolvedMentionPlaceholder::Label(label) => Some(label.clone()),
+        UnresolvedMentionPlaceholder::SlackToken => Some(slack_token),
+        UnresolvedMentionPlaceholder::Omit => None,
+    }
+}
+
+/// URL templates of mentions, e.g. `https://acme.slack.com/team/{id}` or
+/// `slack://channel?id={id}&team={team}`.
+///
+/// `{id}` is replaced by the ID of the mentioned user, channel or usergroup and `{team}` by
+/// `team_id`. Mentions without a template are not linked, except users having a profile URL in
+/// `SlackReferences::user_profiles`.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct MentionUrlTemplates {
+    pub user: Option<String>,
+    pub channel: Option<String>,
+    pub usergroup: Option<String>,
+    /// Team substituted to `{team}`.
+    pub team_id: Option<SlackTeamId>,
+}
+
+impl MentionUrlTemplates {
+    pub fn new() -> MentionUrlTemplates {
+        MentionUrlTemplates::default()
+    }
+
+    pub fn user_url(&self, user_id: &SlackUserId) -> Option<String> {
+        self.url(self.user.as_deref(), &user_id.0)
+    }
+
+    pub fn channel_url(&self, channel_id: &SlackChannelId) -> Option<String> {
+        self.url(self.channel.as_deref(), &channel_id.0)
+    }
+
+    pub fn usergroup_url(&self, usergroup_id: &SlackUserGroupId) -> Option<String> {
+        self.url(self.usergroup.as_deref(), &usergroup_id.0)
+    }
+
+    fn url(&self, template: Option<&str>, id: &str) -> Option<String> {
+        let team_id = self
+            .team_id
+            .as_ref()
+            .map(|team_id| team_id.0.as_str())
+            .unwrap_or_default();
+        template.map(|template| {
+            template
+                .replace("{id}", &encode_url_component(id))
+                .replace("{team}", &encode_url_component(team_id))
+        })
+    }
+}
+
+pub(crate) fn encode_url_component(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+impl RenderOptions {
+    pub fn new() -> RenderOptions {
+        RenderOptions::default()
+    }
+
+    pub(crate) fn user_style_class(&self, highlighted: bool) -> &str {
+        self.style_class(highlighted, &self.highlight_style_classes.user)
+    }
+
+    pub(crate) fn usergroup_style_class(&self, highlighted: bool) -> &str {
+        self.style_class(highlighted, &self.highlight_style_classes.usergroup)
+    }
+
+    pub(crate) fn channel_style_class(&self, highlighted: bool) -> &str {
+        self.style_class(highlighted, &self.highlight_style_classes.channel)
+    }
+
+    pub(crate) fn broadcast_style_class(&self, highlighted: bool) -> &str {
+        self.style_class(highlighted, &self.highlight_style_classes.broadcast)
+    }
+
+    fn style_class<'a>(&'a self, highlighted: bool, category_class: &'a Option<String>) -> &'a str {
+        match (highlighted, category_class) {
+            (false, _) => &self.default_style_class,
+            (true, Some(category_class)) => category_class,
+            (true, None) => &self.highlight_style_class,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mention_url_templates() {
+        let templates = MentionUrlTemplates {
+            user: Some("https://acme.slack.com/team/{id}".to_string()),
+            channel: Some("slack://channel?id={id}&team={team}".to_string()),
+            team_id: Some(SlackTeamId("T123".to_string())),
+            ..MentionUrlTemplates::default()
+        };
+
+        assert_eq!(
+            templates.user_url(&SlackUserId("U123".to_string())),
+            Some("https://acme.slack.com/team/U123".to_string())
+        );
+        assert_eq!(
+            templates.channel_url(&SlackChannelId("C 1&2".to_string())),
+            Some("slack://channel?id=C+1%262&team=T123".to_string())
+        );
+        assert_eq!(
+            templates.usergroup_url(&SlackUserGroupId("S123".to_string())),
+            None
+        );
+    }
+
+    #[test]
+    fn test_emoji_image_cdn_urls() {
+        assert_eq!(
+            EmojiImageCdn::twemoji().image_url("👍🏽"),
+            "https://cdn.jsdelivr.net/gh/jdecked/twemoji@15.1.0/assets/72x72/1f44d-1f3fd.png"
+        );
+        assert_eq!(
+            EmojiImageCdn::noto().image_url("❤️"),
+            "https://cdn.jsdelivr.net/gh/googlefonts/noto-emoji@main/png/72/emoji_u2764.png"
+        );
+        assert_eq!(
+            EmojiImageCdn::twemoji().image_url("🏳️‍🌈"),
+            "https://cdn.jsdelivr.net/gh/jdecked/twemoji@15.1.0/assets/72x72/1f3f3-fe0f-200d-1f308.png"
+        );
+        assert_eq!(
+            EmojiImageCdn::noto().image_url("🏳️‍🌈"),
+            "https://cdn.jsdelivr.net/gh/googlefonts/noto-emoji@main/png/72/emoji_u1f3f3_200d_1f308.png"
+        );
+    }
+
+    #[test]
+    fn test_unresolved_mention_placeholders() {
+        let placeholders = UnresolvedMentionPlaceholders {
+            user: UnresolvedMentionPlaceholder::Label("unknown user".to_string()),
+            channel: UnresolvedMentionPlaceholder::SlackToken,
+            usergroup: UnresolvedMentionPlaceholder::Omit,
+        };
+
+        assert_eq!(
+            placeholders.user(&SlackUserId("U123".to_string())),
+            Some("unknown user".to_string())
+        );
+        assert_eq!(
+            placeholders.channel(&SlackChannelId("C123".to_string())),
+            Some("<#C123>".to_string())
+        );
+        assert_eq!(
+            placeholders.usergroup(&SlackUserGroupId("S123".to_string())),
+            None
+        );
+        assert_eq!(
+            UnresolvedMentionPlaceholders::new().usergroup(&SlackUserGroupId("S123".to_string())),
+            Some("@S123".to_string())
+        );
+    }
+}
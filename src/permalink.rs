@@ -0,0 +1,153 @@
+use slack_morphism::prelude::*;
+
+use crate::options::encode_url_component;
+
+/// Builder of links to messages and threads in Slack, like
+/// `https://acme.slack.com/archives/C123/p1720000200000100?thread_ts=1720000000.000100&cid=C123`.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct SlackPermalinkBuilder {
+    /// Workspace domain: a subdomain of `slack.com` like `acme`, or a full host like
+    /// `acme.enterprise.slack.com`.
+    pub domain: String,
+    /// Channel of the messages without a `channel`, e.g. returned by `conversations.history`.
+    pub channel_id: Option<SlackChannelId>,
+}
+
+impl SlackPermalinkBuilder {
+    pub fn new(domain: impl Into<String>) -> SlackPermalinkBuilder {
+        SlackPermalinkBuilder {
+            domain: domain.into(),
+            channel_id: None,
+        }
+    }
+
+    pub fn with_channel_id(mut self, channel_id: SlackChannelId) -> SlackPermalinkBuilder {
+        self.channel_id = Some(channel_id);
+        self
+    }
+
+    /// Link to the message `ts` of `channel_id`. Thread replies are linked with their
+    /// `thread_ts`, so that Slack opens them in their thread.
+    pub fn permalink(
+        &self,
+        channel_id: &SlackChannelId,
+        ts: &SlackTs,
+        thread_ts: Option<&SlackTs>,
+    ) -> String {
+        let host = if self.domain.contains('.') {
+            self.domain.clone()
+        } else {
+            format!("{}.slack.com", self.domain)
+        };
+        let mut url = format!(
+            "https://{host}/archives/{}/p{}",
+            encode_url_component(&channel_id.0),
+            ts.0.replace('.', "")
+        );
+        if let Some(thread_ts) = thread_ts.filter(|thread_ts| *thread_ts != ts) {
+            url.push_str(&format!(
+                "?thread_ts={}&cid={}",
+                encode_url_component(&thread_ts.0),
+                encode_url_component(&channel_id.0)
+            ));
+        }
+        url
+    }
+
+    /// Link to `message`, in its `channel` or else in `channel_id`.
+    pub fn message_permalink(&self, message: &SlackHistoryMessage) -> Option<String> {
+        let channel_id = message
+            .origin
+            .channel
+            .as_ref()
+            .or(self.channel_id.as_ref())?;
+        Some(self.permalink(
+            channel_id,
+            &message.origin.ts,
+            message.origin.thread_ts.as_ref(),
+        ))
+    }
+
+    /// Link to the thread of `message`, in its `channel` or else in `channel_id`.
+    pub fn message_thread_permalink(&self, message: &SlackHistoryMessage) -> Option<String> {
+        let channel_id = message
+            .origin
+            .channel
+            .as_ref()
+            .or(self.channel_id.as_ref())?;
+        let thread_ts = message.origin.thread_ts.as_ref()?;
+        Some(self.thread_permalink(channel_id, thread_ts))
+    }
+
+    /// Link to the thread started by the message `thread_ts` of `channel_id`.
+    pub fn thread_permalink(&self, channel_id: &SlackChannelId, thread_ts: &SlackTs) -> String {
+        self.permalink(channel_id, thread_ts, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::history_message;
+
+    #[test]
+    fn test_permalinks() {
+        let permalinks = SlackPermalinkBuilder::new("acme");
+        let channel_id = SlackChannelId("C123".to_string());
+        let ts = SlackTs("1720000200.000100".to_string());
+        let thread_ts = SlackTs("1720000000.000100".to_string());
+
+        assert_eq!(
+            permalinks.permalink(&channel_id, &ts, None),
+            "https://acme.slack.com/archives/C123/p1720000200000100"
+        );
+        assert_eq!(
+            permalinks.permalink(&channel_id, &ts, Some(&thread_ts)),
+            "https://acme.slack.com/archives/C123/p1720000200000100?thread_ts=1720000000.000100&cid=C123"
+        );
+        assert_eq!(
+            permalinks.permalink(&channel_id, &thread_ts, Some(&thread_ts)),
+            "https://acme.slack.com/archives/C123/p1720000000000100"
+        );
+        assert_eq!(
+            SlackPermalinkBuilder::new("acme.enterprise.slack.com")
+                .thread_permalink(&channel_id, &thread_ts),
+            "https://acme.enterprise.slack.com/archives/C123/p1720000000000100"
+        );
+    }
+
+    #[test]
+    fn test_message_permalinks() {
+        let reply = history_message(serde_json::json!({
+            "ts": "1720000200.000100",
+            "thread_ts": "1720000000.000100",
+            "text": "Rolling back"
+        }));
+        let permalinks = SlackPermalinkBuilder::new("acme");
+        assert_eq!(permalinks.message_permalink(&reply), None);
+        let permalinks = permalinks.with_channel_id(SlackChannelId("C123".to_string()));
+        assert_eq!(
+            permalinks.message_permalink(&reply),
+            Some(
+                "https://acme.slack.com/archives/C123/p1720000200000100?thread_ts=1720000000.000100&cid=C123"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            permalinks.message_thread_permalink(&reply),
+            Some("https://acme.slack.com/archives/C123/p1720000000000100".to_string())
+        );
+
+        let in_channel = history_message(serde_json::json!({
+            "ts": "1720000200.000100",
+            "channel": "C456",
+            "text": "Rolling back"
+        }));
+        assert_eq!(
+            SlackPermalinkBuilder::new("acme")
+                .with_channel_id(SlackChannelId("C123".to_string()))
+                .message_permalink(&in_channel),
+            Some("https://acme.slack.com/archives/C456/p1720000200000100".to_string())
+        );
+    }
+}
@@ -1,9 +1,22 @@
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::{hash_map::Entry, HashMap},
+    hash::Hash,
+};
 
 use serde::{Deserialize, Serialize};
 use slack_morphism::prelude::*;
 
-use crate::visitor::{visit_slack_rich_text_block, SlackRichTextBlock, Visitor};
+use crate::{
+    attachment::{message_attachments, SlackAttachment},
+    emoji::{find_standard_emoji, split_mrkdwn_emojis, MrkdwnPart},
+    message::message_blocks,
+    options::UserDisplayPolicy,
+    time::{format_slack_date_token, TimeOptions},
+    visitor::{
+        visit_slack_block_mark_down_text, visit_slack_rich_text_block, SlackRichTextBlock, Visitor,
+    },
+};
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct SlackReferences {
@@ -15,10 +28,26 @@ pub struct SlackReferences {
     pub usergroups: HashMap<SlackUserGroupId, Option<String>>,
     #[serde(default = "HashMap::new")]
     pub emojis: HashMap<SlackEmojiName, Option<SlackEmojiRef>>,
-    #[serde(default)]
-    pub user_id_to_highlight: Option<SlackUserId>,
+    #[serde(default = "HashMap::new")]
+    pub teams: HashMap<SlackTeamId, Option<String>>,
+    /// Profiles of users, preferred to [`SlackReferences::users`] when rendering user mentions.
+    #[serde(default = "HashMap::new")]
+    pub user_profiles: HashMap<SlackUserId, SlackUserReference>,
     #[serde(default)]
     pub usergroup_ids_to_highlight: Option<Vec<SlackUserGroupId>>,
+    /// Users to highlight, e.g. the user reading the messages. Also read from the single ID of
+    /// the former `user_id_to_highlight` field.
+    #[serde(
+        default,
+        alias = "user_id_to_highlight",
+        deserialize_with = "deserialize_user_ids_to_highlight"
+    )]
+    pub user_ids_to_highlight: Option<Vec<SlackUserId>>,
+    #[serde(default)]
+    pub channel_ids_to_highlight: Option<Vec<SlackChannelId>>,
+    /// Broadcast ranges to highlight: `here`, `channel` or `everyone`.
+    #[serde(default)]
+    pub broadcasts_to_highlight: Option<Vec<String>>,
 }
 
 impl SlackReferences {
@@ -28,21 +57,94 @@ impl SlackReferences {
             users: HashMap::new(),
             usergroups: HashMap::new(),
             emojis: HashMap::new(),
-            user_id_to_highlight: None,
+            teams: HashMap::new(),
+            user_profiles: HashMap::new(),
             usergroup_ids_to_highlight: None,
+            user_ids_to_highlight: None,
+            channel_ids_to_highlight: None,
+            broadcasts_to_highlight: None,
         }
     }
 
+    /// Merge `other` into these references, preferring resolved entries.
+    ///
+    /// Same as [`SlackReferences::merge`] with [`SlackReferencesMergeStrategy::PreferResolved`].
     pub fn extend(&mut self, other: SlackReferences) {
-        self.users.extend(other.users);
-        self.usergroups.extend(other.usergroups);
-        self.channels.extend(other.channels);
-        self.emojis.extend(other.emojis);
-        if let Some(other_ids) = other.usergroup_ids_to_highlight {
-            self.usergroup_ids_to_highlight
-                .get_or_insert_with(Vec::new)
-                .extend(other_ids);
+        self.merge(other, SlackReferencesMergeStrategy::PreferResolved);
+    }
+
+    /// Merge `other` into these references.
+    ///
+    /// Whatever the strategy, a resolved entry is never replaced by an unresolved one, and
+    /// highlights are merged: every user, usergroup, channel and broadcast highlighted in either
+    /// references stays highlighted.
+    pub fn merge(&mut self, other: SlackReferences, strategy: SlackReferencesMergeStrategy) {
+        merge_entries(&mut self.users, other.users, strategy);
+        merge_entries(&mut self.usergroups, other.usergroups, strategy);
+        merge_entries(&mut self.channels, other.channels, strategy);
+        merge_entries(&mut self.emojis, other.emojis, strategy);
+        merge_entries(&mut self.teams, other.teams, strategy);
+        for (user_id, other_profile) in other.user_profiles {
+            match self.user_profiles.entry(user_id) {
+                Entry::Vacant(entry) => {
+                    entry.insert(other_profile);
+                }
+                Entry::Occupied(mut entry) => match strategy {
+                    SlackReferencesMergeStrategy::PreferResolved => {
+                        entry.get_mut().merge(other_profile)
+                    }
+                    SlackReferencesMergeStrategy::PreferNewer => {
+                        entry.insert(other_profile);
+                    }
+                    SlackReferencesMergeStrategy::PreferExisting => {}
+                },
+            }
         }
+
+        merge_highlights(&mut self.user_ids_to_highlight, other.user_ids_to_highlight);
+        merge_highlights(
+            &mut self.usergroup_ids_to_highlight,
+            other.usergroup_ids_to_highlight,
+        );
+        merge_highlights(
+            &mut self.channel_ids_to_highlight,
+            other.channel_ids_to_highlight,
+        );
+        merge_highlights(
+            &mut self.broadcasts_to_highlight,
+            other.broadcasts_to_highlight,
+        );
+    }
+
+    /// Add a user to [`SlackReferences::user_ids_to_highlight`].
+    #[deprecated(note = "set `user_ids_to_highlight` instead")]
+    pub fn with_user_id_to_highlight(mut self, user_id: SlackUserId) -> SlackReferences {
+        merge_highlights(&mut self.user_ids_to_highlight, Some(vec![user_id]));
+        self
+    }
+
+    pub fn is_user_highlighted(&self, user_id: &SlackUserId) -> bool {
+        self.user_ids_to_highlight
+            .as_ref()
+            .is_some_and(|ids| ids.contains(user_id))
+    }
+
+    pub fn is_usergroup_highlighted(&self, usergroup_id: &SlackUserGroupId) -> bool {
+        self.usergroup_ids_to_highlight
+            .as_ref()
+            .is_some_and(|ids| ids.contains(usergroup_id))
+    }
+
+    pub fn is_channel_highlighted(&self, channel_id: &SlackChannelId) -> bool {
+        self.channel_ids_to_highlight
+            .as_ref()
+            .is_some_and(|ids| ids.contains(channel_id))
+    }
+
+    pub fn is_broadcast_highlighted(&self, range: &str) -> bool {
+        self.broadcasts_to_highlight
+            .as_ref()
+            .is_some_and(|ranges| ranges.iter().any(|highlighted| highlighted == range))
     }
 
     pub fn is_empty(&self) -> bool {
@@ -50,9 +152,176 @@ impl SlackReferences {
             && self.usergroups.is_empty()
             && self.channels.is_empty()
             && self.emojis.is_empty()
+            && self.teams.is_empty()
+            && self.user_profiles.is_empty()
+    }
+
+    /// Name to render for a user mention according to `policy`, if the user is known.
+    pub fn user_name(&self, user_id: &SlackUserId, policy: UserDisplayPolicy) -> Option<&str> {
+        let name = self.users.get(user_id).and_then(|name| name.as_deref());
+        let profile = self.user_profiles.get(user_id);
+        let display_name = profile.and_then(|profile| profile.display_name.as_deref());
+        let real_name = profile.and_then(|profile| profile.real_name.as_deref());
+        let candidates = match policy {
+            UserDisplayPolicy::DisplayName => [display_name, real_name, name],
+            UserDisplayPolicy::RealName => [real_name, display_name, name],
+            UserDisplayPolicy::Name => [name, display_name, real_name],
+        };
+        candidates
+            .into_iter()
+            .flatten()
+            .find(|name| !name.is_empty())
     }
 }
 
+/// Profile of a Slack user.
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlackUserReference {
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub real_name: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    #[serde(default)]
+    pub profile_url: Option<String>,
+    /// Whether the account is deactivated.
+    #[serde(default)]
+    pub deleted: bool,
+    /// Whether the user is a bot user.
+    #[serde(default)]
+    pub bot: bool,
+    /// IANA timezone of the user (e.g. `Europe/Paris`).
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+impl SlackUserReference {
+    /// Merge `other` into this profile, fields missing from `other` being kept. The `deleted`
+    /// and `bot` flags cannot be told missing, so they stay set once set in either profile.
+    pub(crate) fn merge(&mut self, other: SlackUserReference) {
+        self.display_name = other.display_name.or(self.display_name.take());
+        self.real_name = other.real_name.or(self.real_name.take());
+        self.avatar_url = other.avatar_url.or(self.avatar_url.take());
+        self.profile_url = other.profile_url.or(self.profile_url.take());
+        self.deleted |= other.deleted;
+        self.bot |= other.bot;
+        self.timezone = other.timezone.or(self.timezone.take());
+    }
+}
+
+impl From<&SlackUser> for SlackUserReference {
+    fn from(user: &SlackUser) -> Self {
+        let profile = user.profile.as_ref();
+        SlackUserReference {
+            display_name: profile.and_then(|profile| profile.display_name.clone()),
+            real_name: profile
+                .and_then(|profile| profile.real_name.clone())
+                .or_else(|| user.real_name.clone()),
+            // Pick the resolution closest to the usual 48px avatar size
+            avatar_url: profile
+                .and_then(|profile| profile.icon.as_ref())
+                .and_then(|icon| icon.images.as_ref())
+                .and_then(|images| {
+                    images
+                        .resolutions
+                        .iter()
+                        .min_by_key(|(size, _)| size.abs_diff(48))
+                })
+                .map(|(_, url)| url.clone()),
+            profile_url: None,
+            deleted: user.deleted.unwrap_or_default(),
+            bot: user.flags.is_bot.unwrap_or_default(),
+            timezone: user.tz.clone(),
+        }
+    }
+}
+
+/// How [`SlackReferences::merge`] settles entries resolved in both references.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum SlackReferencesMergeStrategy {
+    /// Keep the value of the merged references, and merge user profiles field by field so that
+    /// no resolved field is lost.
+    #[default]
+    PreferResolved,
+    /// Keep the value of the merged references, replacing user profiles as a whole: fields
+    /// missing from the newer profile are dropped.
+    PreferNewer,
+    /// Keep the existing value, user profiles included.
+    PreferExisting,
+}
+
+fn merge_entries<K, V>(
+    entries: &mut HashMap<K, Option<V>>,
+    other: HashMap<K, Option<V>>,
+    strategy: SlackReferencesMergeStrategy,
+) where
+    K: Eq + Hash,
+{
+    for (key, other_value) in other {
+        match entries.entry(key) {
+            Entry::Vacant(entry) => {
+                entry.insert(other_value);
+            }
+            Entry::Occupied(mut entry) => {
+                let keep_existing = other_value.is_none()
+                    || (entry.get().is_some()
+                        && strategy == SlackReferencesMergeStrategy::PreferExisting);
+                if !keep_existing {
+                    entry.insert(other_value);
+                }
+            }
+        }
+    }
+}
+
+fn merge_highlights<T: PartialEq>(highlights: &mut Option<Vec<T>>, other: Option<Vec<T>>) {
+    let Some(other) = other else {
+        return;
+    };
+    let highlights = highlights.get_or_insert_with(Vec::new);
+    for highlight in other {
+        if !highlights.contains(&highlight) {
+            highlights.push(highlight);
+        }
+    }
+}
+
+/// Read users to highlight from a list of IDs, or from the single ID of the former
+/// `user_id_to_highlight` field.
+fn deserialize_user_ids_to_highlight<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<SlackUserId>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum UserIds {
+        One(SlackUserId),
+        Many(Vec<SlackUserId>),
+    }
+
+    Ok(
+        Option::<UserIds>::deserialize(deserializer)?.map(|user_ids| match user_ids {
+            UserIds::One(user_id) => vec![user_id],
+            UserIds::Many(user_ids) => user_ids,
+        }),
+    )
+}
+
+/// Kind of reference stored in [`SlackReferences`].
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlackReferenceKind {
+    User,
+    Channel,
+    Usergroup,
+    Emoji,
+    Team,
+    UserProfile,
+}
+
 impl Default for SlackReferences {
     fn default() -> Self {
         Self::new()
@@ -79,18 +348,221 @@ pub fn find_slack_references_in_blocks(blocks: &[SlackBlock]) -> SlackReferences
     finder.slack_references
 }
 
+/// Find Slack references in messages: references used in their blocks, or in their mrkdwn text
+/// when they have no blocks, in the texts and blocks of their attachments, their authors, the
+/// teams of their authors and their reactions.
+pub fn find_slack_references_in_messages(messages: &[SlackHistoryMessage]) -> SlackReferences {
+    let mut finder = SlackReferencesFinder::new();
+    for message in messages {
+        for block in message_blocks(&message.content).iter() {
+            finder.visit_slack_block(block);
+        }
+        for attachment in message_attachments(&message.content) {
+            find_slack_references_in_attachment(&attachment, &mut finder);
+        }
+        if let Some(user_id) = &message.sender.user {
+            finder.slack_references.users.insert(user_id.clone(), None);
+        }
+        for reaction in message.content.reactions.iter().flatten() {
+            for user_id in &reaction.users {
+                finder.slack_references.users.insert(user_id.clone(), None);
+            }
+            if find_standard_emoji(&reaction.name.0).is_none() {
+                finder
+                    .slack_references
+                    .emojis
+                    .insert(SlackEmojiName(reaction.name.0.clone()), None);
+            }
+        }
+        if let Some(team_id) = message
+            .sender
+            .user_profile
+            .as_ref()
+            .and_then(|profile| profile.team.as_ref())
+        {
+            finder.slack_references.teams.insert(team_id.clone(), None);
+        }
+    }
+    finder.slack_references
+}
+
+/// Find the references of the mrkdwn texts and blocks of an attachment.
+fn find_slack_references_in_attachment(
+    attachment: &SlackAttachment,
+    finder: &mut SlackReferencesFinder,
+) {
+    let field_values = attachment
+        .fields
+        .iter()
+        .flatten()
+        .filter_map(|field| field.value.as_ref());
+    for text in [&attachment.pretext, &attachment.text, &attachment.footer]
+        .into_iter()
+        .flatten()
+        .chain(field_values)
+    {
+        find_slack_references_in_mrkdwn(text, &mut finder.slack_references);
+    }
+    for block in attachment.blocks.iter().flatten() {
+        finder.visit_slack_block(block);
+    }
+}
+
 impl Visitor for SlackReferencesFinder {
     fn visit_slack_rich_text_block(&mut self, slack_rich_text_block: &SlackRichTextBlock) {
         find_slack_references_in_rich_text_block(
-            slack_rich_text_block.json_value.clone(),
+            &slack_rich_text_block.json_value,
             &mut self.slack_references,
         );
         visit_slack_rich_text_block(self, slack_rich_text_block);
     }
+
+    fn visit_slack_block_mark_down_text(
+        &mut self,
+        slack_block_mark_down_text: &SlackBlockMarkDownText,
+    ) {
+        find_slack_references_in_mrkdwn(
+            &slack_block_mark_down_text.text,
+            &mut self.slack_references,
+        );
+        visit_slack_block_mark_down_text(self, slack_block_mark_down_text);
+    }
+}
+
+/// Find the custom emojis and the mentions of mrkdwn text.
+pub(crate) fn find_slack_references_in_mrkdwn(text: &str, slack_references: &mut SlackReferences) {
+    for element in text
+        .split('<')
+        .skip(1)
+        .filter_map(|token| token.split_once('>'))
+        .filter_map(|(token, _)| mrkdwn_mention_element(token))
+    {
+        find_slack_references_in_rich_text_section_element(&element, slack_references);
+    }
+    for part in split_mrkdwn_emojis(text) {
+        if let MrkdwnPart::Emoji(name) = part {
+            if find_standard_emoji(name).is_none() {
+                slack_references
+                    .emojis
+                    .insert(SlackEmojiName(name.to_string()), None);
+            }
+        }
+    }
+}
+
+/// Rich text element of a mention token of mrkdwn text without its angle brackets, like
+/// `@U123`, `#C123|general`, `!subteam^S123` or `!here`, to render it like in rich text. The
+/// `|label` of users, channels and usergroups is kept to render them when they are unresolved.
+pub(crate) fn mrkdwn_mention_element(token: &str) -> Option<serde_json::Value> {
+    let (reference, label) = token
+        .split_once('|')
+        .map_or((token, None), |(reference, label)| {
+            (reference, Some(label).filter(|label| !label.is_empty()))
+        });
+    let mut element = if let Some(user_id) = reference.strip_prefix('@') {
+        serde_json::json!({ "type": "user", "user_id": user_id })
+    } else if let Some(channel_id) = reference.strip_prefix('#') {
+        serde_json::json!({ "type": "channel", "channel_id": channel_id })
+    } else if let Some(usergroup_id) = reference.strip_prefix("!subteam^") {
+        serde_json::json!({ "type": "usergroup", "usergroup_id": usergroup_id })
+    } else if let Some(range @ ("here" | "channel" | "everyone")) = reference.strip_prefix('!') {
+        return Some(serde_json::json!({ "type": "broadcast", "range": range }));
+    } else {
+        return None;
+    };
+    if let Some(label) = label {
+        element["label"] = decode_mrkdwn_entities(label).into();
+    }
+    Some(element)
+}
+
+/// Name of an unresolved mention from the `|label` of its mrkdwn token, like `@bob` for
+/// `<@U123|bob>`, rendered rather than the unresolved mention placeholder.
+pub(crate) fn mrkdwn_mention_label(element: &serde_json::Value, prefix: char) -> Option<String> {
+    let label = element.get("label")?.as_str()?;
+    Some(match label.starts_with(prefix) {
+        true => label.to_string(),
+        false => format!("{prefix}{label}"),
+    })
+}
+
+/// Rich text element of a `<...>` token of mrkdwn text without its angle brackets: a mention
+/// like with [`mrkdwn_mention_element`], or a link like `https://example.com|the docs`.
+pub(crate) fn mrkdwn_token_element(token: &str) -> Option<serde_json::Value> {
+    if token.starts_with(['@', '#', '!']) {
+        return mrkdwn_mention_element(token);
+    }
+    let (url, text) = token.split_once('|').unwrap_or((token, token));
+    if url.is_empty() {
+        return None;
+    }
+    Some(serde_json::json!({
+        "type": "link",
+        "url": decode_mrkdwn_entities(url),
+        "text": decode_mrkdwn_entities(text),
+    }))
 }
 
-fn find_slack_references_in_rich_text_block(
-    json_value: serde_json::Value,
+/// Replace the `<...>` tokens of mrkdwn text outside of code by their rendering: date tokens
+/// with `render_date`, mentions and links as rich text elements with `render_element`. Other
+/// tokens are kept, and the `&amp;`, `&lt;` and `&gt;` entities of the text are decoded.
+pub(crate) fn replace_slack_mrkdwn_tokens(
+    text: &str,
+    options: &TimeOptions,
+    render_date: impl Fn(String, Option<String>) -> String,
+    render_element: impl Fn(&serde_json::Value) -> String,
+) -> String {
+    let mut output = String::new();
+    let mut in_code = false;
+    let mut rest = text;
+    while let Some(start) = rest.find(['`', '<']) {
+        output.push_str(&decode_mrkdwn_entities(&rest[..start]));
+        let after = &rest[start + 1..];
+        if rest[start..].starts_with('`') {
+            in_code = !in_code;
+            output.push('`');
+            rest = after;
+            continue;
+        }
+        match after.find('>').filter(|_| !in_code) {
+            Some(end) => {
+                let token = &after[..end];
+                let rendered = match format_slack_date_token(token, options) {
+                    Some((date, link)) => Some(render_date(date, link)),
+                    None => mrkdwn_token_element(token).map(|element| render_element(&element)),
+                };
+                match rendered {
+                    Some(rendered) => output.push_str(&rendered),
+                    None => {
+                        output.push_str(&decode_mrkdwn_entities(&rest[start..=start + end + 1]))
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                output.push('<');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(&decode_mrkdwn_entities(rest));
+    output
+}
+
+/// Decode the `&amp;`, `&lt;` and `&gt;` entities Slack escapes mrkdwn text with.
+pub(crate) fn decode_mrkdwn_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(
+        text.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+pub(crate) fn find_slack_references_in_rich_text_block(
+    json_value: &serde_json::Value,
     slack_references: &mut SlackReferences,
 ) {
     let Some(serde_json::Value::Array(elements)) = json_value.get("elements") else {
@@ -158,7 +630,7 @@ fn find_slack_references_in_rich_text_quote_elements(
     find_slack_references_in_rich_text_section_elements(elements, slack_references);
 }
 
-fn find_slack_references_in_rich_text_section_element(
+pub(crate) fn find_slack_references_in_rich_text_section_element(
     element: &serde_json::Value,
     slack_references: &mut SlackReferences,
 ) {
@@ -191,14 +663,7 @@ fn find_slack_references_in_rich_text_section_element(
             let Some(serde_json::Value::String(name)) = element.get("name") else {
                 return;
             };
-            let splitted = name.split("::skin-tone-").collect::<Vec<&str>>();
-            let Some(first) = splitted.first() else {
-                slack_references
-                    .emojis
-                    .insert(SlackEmojiName(name.to_string()), None);
-                return;
-            };
-            if emojis::get_by_shortcode(first).is_none() {
+            if find_standard_emoji(name).is_none() {
                 slack_references
                     .emojis
                     .insert(SlackEmojiName(name.to_string()), None);
@@ -405,6 +870,189 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_find_slack_references_in_mrkdwn_text() {
+        let blocks = vec![SlackBlock::Section(
+            SlackSectionBlock::new().with_text(SlackBlockText::MarkDown(
+                SlackBlockMarkDownText::new(
+                    ":partyparrot: :smile: at 10:30, see <https://example.com|link> and `:code:`, \
+                    cc <@U1> <#C1|general> <!subteam^S1> <!here>"
+                        .to_string(),
+                ),
+            )),
+        )];
+        assert_eq!(
+            find_slack_references_in_blocks(&blocks),
+            SlackReferences {
+                users: HashMap::from([(SlackUserId("U1".to_string()), None)]),
+                channels: HashMap::from([(SlackChannelId("C1".to_string()), None)]),
+                usergroups: HashMap::from([(SlackUserGroupId("S1".to_string()), None)]),
+                emojis: HashMap::from([(SlackEmojiName("partyparrot".to_string()), None)]),
+                ..SlackReferences::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_replace_slack_mrkdwn_tokens() {
+        let render_date = |formatted: String, link: Option<String>| match link {
+            Some(link) => format!("[{formatted}]({link})"),
+            None => formatted,
+        };
+        let render_element = |element: &serde_json::Value| format!("[{element}]");
+        assert_eq!(
+            replace_slack_mrkdwn_tokens(
+                "Posted <!date^1392734382^{date_num} {time}|Feb 18>, see <!date^1392734382^{date}^https://example.com|link> or <!date^oops^{date}|fallback>",
+                &TimeOptions::default(),
+                render_date,
+                render_element
+            ),
+            "Posted 2014-02-18 14:39, see [February 18th, 2014](https://example.com) or fallback"
+        );
+        assert_eq!(
+            replace_slack_mrkdwn_tokens(
+                "<@U1> see <https://example.com?a=1&amp;b=2|Q&amp;A> &amp; <!foo> `<#C1> &lt;` 1 < 2",
+                &TimeOptions::default(),
+                render_date,
+                render_element
+            ),
+            "[{\"type\":\"user\",\"user_id\":\"U1\"}] see \
+            [{\"text\":\"Q&A\",\"type\":\"link\",\"url\":\"https://example.com?a=1&b=2\"}] \
+            & <!foo> `<#C1> <` 1 < 2"
+        );
+    }
+
+    #[test]
+    fn test_find_slack_references_in_messages() {
+        let messages: Vec<SlackHistoryMessage> = serde_json::from_value(serde_json::json!([
+            {
+                "ts": "1700000000.000100",
+                "user": "U111111",
+                "user_profile": {
+                    "team": "T123456"
+                },
+                "blocks": [
+                    {
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    {
+                                        "type": "user",
+                                        "user_id": "U222222"
+                                    }
+                                ]
+                            }
+                        ]
+                    }
+                ]
+            },
+            {
+                "ts": "1700000000.000200",
+                "user": "U222222",
+                "text": "Hello"
+            }
+        ]))
+        .unwrap();
+        assert_eq!(
+            find_slack_references_in_messages(&messages),
+            SlackReferences {
+                users: HashMap::from([
+                    (SlackUserId("U111111".to_string()), None),
+                    (SlackUserId("U222222".to_string()), None)
+                ]),
+                teams: HashMap::from([(SlackTeamId("T123456".to_string()), None)]),
+                ..SlackReferences::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_find_slack_references_in_message_texts_and_attachments() {
+        let messages: Vec<SlackHistoryMessage> = serde_json::from_value(serde_json::json!([
+            {
+                "ts": "1700000000.000100",
+                "user": "U000000",
+                "text": "<@U111111> see <https://example.com|the docs> & <#C111111>",
+                "attachments": [
+                    {
+                        "text": "Paged <!subteam^S111111|oncall>",
+                        "fields": [
+                            { "title": "Owner", "value": "<@U222222>", "short": true }
+                        ]
+                    }
+                ]
+            }
+        ]))
+        .unwrap();
+        assert_eq!(
+            find_slack_references_in_messages(&messages),
+            SlackReferences {
+                users: HashMap::from([
+                    (SlackUserId("U000000".to_string()), None),
+                    (SlackUserId("U111111".to_string()), None),
+                    (SlackUserId("U222222".to_string()), None)
+                ]),
+                channels: HashMap::from([(SlackChannelId("C111111".to_string()), None)]),
+                usergroups: HashMap::from([(SlackUserGroupId("S111111".to_string()), None)]),
+                ..SlackReferences::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_find_slack_references_in_message_reactions() {
+        let messages: Vec<SlackHistoryMessage> = serde_json::from_value(serde_json::json!([
+            {
+                "ts": "1700000000.000100",
+                "text": "Ship it?",
+                "reactions": [
+                    { "name": "+1", "count": 2, "users": ["U111111", "U222222"] },
+                    { "name": "partyparrot", "count": 1, "users": ["U111111"] }
+                ]
+            }
+        ]))
+        .unwrap();
+        assert_eq!(
+            find_slack_references_in_messages(&messages),
+            SlackReferences {
+                users: HashMap::from([
+                    (SlackUserId("U111111".to_string()), None),
+                    (SlackUserId("U222222".to_string()), None)
+                ]),
+                emojis: HashMap::from([(SlackEmojiName("partyparrot".to_string()), None)]),
+                ..SlackReferences::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_find_slack_references_with_slack_shortcode_emoji() {
+        let blocks = vec![rich_text_block(serde_json::json!({
+            "type": "rich_text",
+            "elements": [
+                {
+                    "type": "rich_text_section",
+                    "elements": [
+                        {
+                            "type": "emoji",
+                            "name": "simple_smile"
+                        },
+                        {
+                            "type": "emoji",
+                            "name": "man-facepalming::skin-tone-3"
+                        }
+                    ]
+                }
+            ]
+        }))];
+        assert_eq!(
+            find_slack_references_in_blocks(&blocks),
+            SlackReferences::default()
+        );
+    }
+
     #[test]
     fn test_find_slack_references_with_unknown_emoji() {
         let blocks = vec![rich_text_block(serde_json::json!({
@@ -429,4 +1077,247 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn test_user_name_with_display_policy() {
+        let user_id = SlackUserId("U1".to_string());
+        let slack_references = SlackReferences {
+            users: HashMap::from([(user_id.clone(), Some("jdoe".to_string()))]),
+            user_profiles: HashMap::from([(
+                user_id.clone(),
+                SlackUserReference {
+                    display_name: Some("".to_string()),
+                    real_name: Some("John Doe".to_string()),
+                    ..SlackUserReference::default()
+                },
+            )]),
+            ..SlackReferences::default()
+        };
+
+        assert_eq!(
+            slack_references.user_name(&user_id, UserDisplayPolicy::DisplayName),
+            Some("John Doe")
+        );
+        assert_eq!(
+            slack_references.user_name(&user_id, UserDisplayPolicy::RealName),
+            Some("John Doe")
+        );
+        assert_eq!(
+            slack_references.user_name(&user_id, UserDisplayPolicy::Name),
+            Some("jdoe")
+        );
+        assert_eq!(
+            slack_references.user_name(
+                &SlackUserId("U2".to_string()),
+                UserDisplayPolicy::DisplayName
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_user_reference_from_slack_user() {
+        let user: SlackUser = serde_json::from_value(serde_json::json!({
+            "id": "U1",
+            "name": "jdoe",
+            "deleted": true,
+            "is_bot": false,
+            "tz": "Europe/Paris",
+            "profile": {
+                "display_name": "johnny",
+                "real_name": "John Doe",
+                "image_24": "https://avatars.example.com/24.png",
+                "image_48": "https://avatars.example.com/48.png",
+                "image_72": "https://avatars.example.com/72.png"
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            SlackUserReference::from(&user),
+            SlackUserReference {
+                display_name: Some("johnny".to_string()),
+                real_name: Some("John Doe".to_string()),
+                avatar_url: Some("https://avatars.example.com/48.png".to_string()),
+                profile_url: None,
+                deleted: true,
+                bot: false,
+                timezone: Some("Europe/Paris".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_extend_merges_highlights() {
+        let mut slack_references = SlackReferences {
+            user_ids_to_highlight: Some(vec![SlackUserId("U1".to_string())]),
+            ..SlackReferences::default()
+        };
+        slack_references.extend(SlackReferences {
+            user_ids_to_highlight: Some(vec![SlackUserId("U2".to_string())]),
+            channel_ids_to_highlight: Some(vec![SlackChannelId("C1".to_string())]),
+            broadcasts_to_highlight: Some(vec!["channel".to_string()]),
+            ..SlackReferences::default()
+        });
+
+        assert!(slack_references.is_user_highlighted(&SlackUserId("U1".to_string())));
+        assert!(slack_references.is_user_highlighted(&SlackUserId("U2".to_string())));
+        assert!(!slack_references.is_user_highlighted(&SlackUserId("U3".to_string())));
+        assert!(slack_references.is_channel_highlighted(&SlackChannelId("C1".to_string())));
+        assert!(slack_references.is_broadcast_highlighted("channel"));
+        assert!(!slack_references.is_broadcast_highlighted("here"));
+    }
+
+    fn user_references(users: &[(&str, Option<&str>)]) -> SlackReferences {
+        SlackReferences {
+            users: users
+                .iter()
+                .map(|(id, name)| (SlackUserId(id.to_string()), name.map(str::to_string)))
+                .collect(),
+            ..SlackReferences::default()
+        }
+    }
+
+    #[test]
+    fn test_extend_never_downgrades_resolved_entries() {
+        let found = user_references(&[("U1", None), ("U2", None)]);
+        let resolved = user_references(&[("U1", Some("alice"))]);
+
+        let mut slack_references = resolved.clone();
+        slack_references.extend(found.clone());
+        assert_eq!(
+            slack_references,
+            user_references(&[("U1", Some("alice")), ("U2", None)])
+        );
+
+        let mut slack_references = found;
+        slack_references.extend(resolved);
+        assert_eq!(
+            slack_references,
+            user_references(&[("U1", Some("alice")), ("U2", None)])
+        );
+    }
+
+    #[test]
+    fn test_merge_strategies() {
+        let existing = user_references(&[("U1", Some("alice")), ("U2", Some("bob"))]);
+        let other = user_references(&[("U1", Some("alice.smith")), ("U2", None)]);
+
+        for (strategy, expected) in [
+            (
+                SlackReferencesMergeStrategy::PreferResolved,
+                user_references(&[("U1", Some("alice.smith")), ("U2", Some("bob"))]),
+            ),
+            (
+                SlackReferencesMergeStrategy::PreferNewer,
+                user_references(&[("U1", Some("alice.smith")), ("U2", Some("bob"))]),
+            ),
+            (
+                SlackReferencesMergeStrategy::PreferExisting,
+                user_references(&[("U1", Some("alice")), ("U2", Some("bob"))]),
+            ),
+        ] {
+            let mut slack_references = existing.clone();
+            slack_references.merge(other.clone(), strategy);
+            assert_eq!(slack_references, expected, "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn test_merge_user_profiles() {
+        let user_id = SlackUserId("U1".to_string());
+        let existing = SlackReferences {
+            user_profiles: HashMap::from([(
+                user_id.clone(),
+                SlackUserReference {
+                    display_name: Some("alice".to_string()),
+                    deleted: true,
+                    bot: true,
+                    timezone: Some("Europe/Paris".to_string()),
+                    ..SlackUserReference::default()
+                },
+            )]),
+            ..SlackReferences::default()
+        };
+        let other = SlackReferences {
+            user_profiles: HashMap::from([(
+                user_id.clone(),
+                SlackUserReference {
+                    real_name: Some("Alice Smith".to_string()),
+                    ..SlackUserReference::default()
+                },
+            )]),
+            ..SlackReferences::default()
+        };
+
+        let mut slack_references = existing.clone();
+        slack_references.merge(other.clone(), SlackReferencesMergeStrategy::PreferResolved);
+        assert_eq!(
+            slack_references.user_profiles[&user_id],
+            SlackUserReference {
+                display_name: Some("alice".to_string()),
+                real_name: Some("Alice Smith".to_string()),
+                deleted: true,
+                bot: true,
+                timezone: Some("Europe/Paris".to_string()),
+                ..SlackUserReference::default()
+            }
+        );
+
+        let mut slack_references = existing.clone();
+        slack_references.merge(other.clone(), SlackReferencesMergeStrategy::PreferNewer);
+        assert_eq!(slack_references.user_profiles, other.user_profiles);
+        assert!(!slack_references.user_profiles[&user_id].deleted);
+        assert!(!slack_references.user_profiles[&user_id].bot);
+
+        let mut slack_references = existing.clone();
+        slack_references.merge(other, SlackReferencesMergeStrategy::PreferExisting);
+        assert_eq!(slack_references.user_profiles, existing.user_profiles);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_with_user_id_to_highlight() {
+        let slack_references = SlackReferences::default()
+            .with_user_id_to_highlight(SlackUserId("U1".to_string()))
+            .with_user_id_to_highlight(SlackUserId("U2".to_string()))
+            .with_user_id_to_highlight(SlackUserId("U1".to_string()));
+
+        assert_eq!(
+            slack_references.user_ids_to_highlight,
+            Some(vec![
+                SlackUserId("U1".to_string()),
+                SlackUserId("U2".to_string())
+            ])
+        );
+        assert!(slack_references.is_user_highlighted(&SlackUserId("U2".to_string())));
+    }
+
+    #[test]
+    fn test_deserialize_former_user_id_to_highlight() {
+        let slack_references: SlackReferences =
+            serde_json::from_value(serde_json::json!({ "user_id_to_highlight": "U1" })).unwrap();
+        assert_eq!(
+            slack_references.user_ids_to_highlight,
+            Some(vec![SlackUserId("U1".to_string())])
+        );
+
+        let slack_references: SlackReferences =
+            serde_json::from_value(serde_json::json!({ "user_ids_to_highlight": ["U1", "U2"] }))
+                .unwrap();
+        assert_eq!(
+            slack_references.user_ids_to_highlight,
+            Some(vec![
+                SlackUserId("U1".to_string()),
+                SlackUserId("U2".to_string())
+            ])
+        );
+
+        let slack_references: SlackReferences =
+            serde_json::from_value(serde_json::json!({ "user_id_to_highlight": null })).unwrap();
+        assert_eq!(slack_references.user_ids_to_highlight, None);
+        let slack_references: SlackReferences =
+            serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(slack_references.user_ids_to_highlight, None);
+    }
 }
@@ -0,0 +1,447 @@
+use std::{collections::HashMap, hash::Hash};
+
+use serde::{Deserialize, Serialize};
+use slack_morphism::prelude::*;
+
+use crate::{
+    emoji::is_broken_alias,
+    message::MessageParts,
+    options::RenderOptions,
+    references::{
+        find_slack_references_in_mrkdwn, find_slack_references_in_rich_text_section_element,
+        SlackReferences,
+    },
+    visitor::{SlackRichTextBlock, SlackUnsupportedBlock, Visitor},
+};
+
+/// What could not be rendered faithfully.
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RenderReport {
+    /// References rendered with a fallback because they are missing from the Slack references.
+    /// Entries are unresolved, so they can be given to a resolver again.
+    #[serde(default)]
+    pub unresolved_references: SlackReferences,
+    /// Types of the blocks that are not rendered (e.g. `actions`).
+    #[serde(default)]
+    pub unsupported_blocks: Vec<String>,
+    /// Types of the rich text elements that are not rendered (e.g. `color`).
+    #[serde(default)]
+    pub unsupported_elements: Vec<String>,
+    /// Emoji aliases that loop or point to an unknown emoji, rendered as their `:shortcode:`.
+    #[serde(default)]
+    pub broken_emoji_aliases: Vec<SlackEmojiName>,
+}
+
+impl RenderReport {
+    /// Record blocks skipped before rendering, e.g. because they could not be read.
+    pub(crate) fn skip_blocks(&mut self, block_types: &[String]) {
+        for block_type in block_types {
+            push_unique(&mut self.unsupported_blocks, block_type);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unresolved_references.is_empty()
+            && self.unsupported_blocks.is_empty()
+            && self.unsupported_elements.is_empty()
+            && self.broken_emoji_aliases.is_empty()
+    }
+}
+
+/// Build the report of rendering `blocks` with `slack_references` and `options`, in any format.
+/// The blocks are visited once, without being rendered: the report tells what the renderers
+/// cannot render, so it is the same whatever the format.
+pub fn build_render_report(
+    blocks: &[SlackBlock],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> RenderReport {
+    let mut finder = RenderReportFinder::new(options);
+    for block in blocks {
+        finder.visit_slack_block(block);
+    }
+    finder.finish(slack_references)
+}
+
+/// Build the report of rendering the blocks of a message, including the blocks which could not
+/// be read.
+pub(crate) fn build_message_render_report(
+    message: &MessageParts,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> RenderReport {
+    let mut report = build_render_report(&message.blocks, slack_references, options);
+    report.skip_blocks(&message.unparsed_blocks);
+    report
+}
+
+/// Finds the references, unsupported blocks and unsupported rich text elements of blocks in a
+/// single traversal. References are resolved like the renderers resolve them with `options`.
+pub(crate) struct RenderReportFinder<'a> {
+    options: &'a RenderOptions,
+    found_references: SlackReferences,
+    unsupported_blocks: Vec<String>,
+    unsupported_elements: Vec<String>,
+}
+
+impl<'a> RenderReportFinder<'a> {
+    pub fn new(options: &'a RenderOptions) -> Self {
+        RenderReportFinder {
+            options,
+            found_references: SlackReferences::default(),
+            unsupported_blocks: vec![],
+            unsupported_elements: vec![],
+        }
+    }
+
+    /// Walk rich text elements, recording the references and the unsupported elements.
+    fn find_in_rich_text_elements(&mut self, elements: &[serde_json::Value]) {
+        for element in elements {
+            match element.get("type").and_then(|t| t.as_str()) {
+                Some(
+                    "rich_text_section"
+                    | "rich_text_list"
+                    | "rich_text_preformatted"
+                    | "rich_text_quote",
+                ) => {
+                    if let Some(serde_json::Value::Array(elements)) = element.get("elements") {
+                        self.find_in_rich_text_elements(elements);
+                    }
+                }
+                Some("channel" | "user" | "usergroup" | "emoji") => {
+                    find_slack_references_in_rich_text_section_element(
+                        element,
+                        &mut self.found_references,
+                    );
+                }
+                Some("text" | "link" | "date" | "broadcast") => {}
+                element_type => {
+                    push_unique(
+                        &mut self.unsupported_elements,
+                        element_type.unwrap_or("unknown"),
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn finish(self, slack_references: &SlackReferences) -> RenderReport {
+        let found_references = &self.found_references;
+        let mut broken_emoji_aliases: Vec<SlackEmojiName> = found_references
+            .emojis
+            .keys()
+            .filter(|name| is_broken_alias(name, slack_references))
+            .cloned()
+            .collect();
+        broken_emoji_aliases.sort_by(|a, b| a.0.cmp(&b.0));
+        let unresolved_references = SlackReferences {
+            users: unresolved(&found_references.users, |id| {
+                slack_references
+                    .user_name(id, self.options.user_display_policy)
+                    .is_none()
+            }),
+            channels: unresolved(&found_references.channels, |id| {
+                !matches!(slack_references.channels.get(id), Some(Some(_)))
+            }),
+            usergroups: unresolved(&found_references.usergroups, |id| {
+                !matches!(slack_references.usergroups.get(id), Some(Some(_)))
+            }),
+            emojis: unresolved(&found_references.emojis, |name| {
+                !matches!(slack_references.emojis.get(name), Some(Some(_)))
+            }),
+            ..SlackReferences::default()
+        };
+        RenderReport {
+            unresolved_references,
+            unsupported_blocks: self.unsupported_blocks,
+            unsupported_elements: self.unsupported_elements,
+            broken_emoji_aliases,
+        }
+    }
+}
+
+impl Visitor for RenderReportFinder<'_> {
+    fn visit_slack_rich_text_block(&mut self, slack_rich_text_block: &SlackRichTextBlock) {
+        if let Some(serde_json::Value::Array(elements)) =
+            slack_rich_text_block.json_value.get("elements")
+        {
+            self.find_in_rich_text_elements(elements);
+        }
+    }
+
+    fn visit_slack_block_mark_down_text(
+        &mut self,
+        slack_block_mark_down_text: &SlackBlockMarkDownText,
+    ) {
+        find_slack_references_in_mrkdwn(
+            &slack_block_mark_down_text.text,
+            &mut self.found_references,
+        );
+    }
+
+    fn visit_slack_unsupported_block(&mut self, slack_unsupported_block: &SlackUnsupportedBlock) {
+        push_unique(
+            &mut self.unsupported_blocks,
+            slack_unsupported_block.block_type,
+        );
+    }
+}
+
+fn unresolved<K: Clone + Eq + Hash, V, T>(
+    found: &HashMap<K, V>,
+    is_unresolved: impl Fn(&K) -> bool,
+) -> HashMap<K, Option<T>> {
+    found
+        .keys()
+        .filter(|key| is_unresolved(key))
+        .map(|key| (key.clone(), None))
+        .collect()
+}
+
+fn push_unique(types: &mut Vec<String>, element_type: &str) {
+    if !types.iter().any(|t| t == element_type) {
+        types.push(element_type.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        options::UserDisplayPolicy, references::SlackUserReference, test_utils::rich_text_block,
+        text::render_blocks_as_text_with_options,
+    };
+
+    #[test]
+    fn test_empty_report() {
+        let blocks = vec![SlackBlock::Section(SlackSectionBlock::new().with_text(
+            SlackBlockText::Plain(SlackBlockPlainText::new("Hello".to_string())),
+        ))];
+        assert!(build_render_report(
+            &blocks,
+            &SlackReferences::default(),
+            &RenderOptions::default()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_report_unresolved_references() {
+        let blocks = vec![rich_text_block(serde_json::json!({
+            "type": "rich_text",
+            "elements": [
+                {
+                    "type": "rich_text_section",
+                    "elements": [
+                        { "type": "user", "user_id": "U1" },
+                        { "type": "user", "user_id": "U2" },
+                        { "type": "channel", "channel_id": "C1" },
+                        { "type": "usergroup", "usergroup_id": "S1" },
+                        { "type": "emoji", "name": "partyparrot" },
+                        { "type": "emoji", "name": "smile" }
+                    ]
+                }
+            ]
+        }))];
+        let slack_references = SlackReferences {
+            users: HashMap::from([
+                (SlackUserId("U1".to_string()), Some("alice".to_string())),
+                (SlackUserId("U2".to_string()), None),
+            ]),
+            usergroups: HashMap::from([(
+                SlackUserGroupId("S1".to_string()),
+                Some("admins".to_string()),
+            )]),
+            ..SlackReferences::default()
+        };
+
+        assert_eq!(
+            build_render_report(&blocks, &slack_references, &RenderOptions::default()),
+            RenderReport {
+                unresolved_references: SlackReferences {
+                    users: HashMap::from([(SlackUserId("U2".to_string()), None)]),
+                    channels: HashMap::from([(SlackChannelId("C1".to_string()), None)]),
+                    emojis: HashMap::from([(SlackEmojiName("partyparrot".to_string()), None)]),
+                    ..SlackReferences::default()
+                },
+                ..RenderReport::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_report_emojis_with_skin_tones_in_mrkdwn_text() {
+        let blocks = vec![SlackBlock::Section(SlackSectionBlock::new().with_text(
+            SlackBlockText::MarkDown(SlackBlockMarkDownText::new(
+                ":+1::skin-tone-3: :man-facepalming::skin-tone-2: :partyparrot:".to_string(),
+            )),
+        ))];
+
+        assert_eq!(
+            build_render_report(
+                &blocks,
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            RenderReport {
+                unresolved_references: SlackReferences {
+                    emojis: HashMap::from([(SlackEmojiName("partyparrot".to_string()), None)]),
+                    ..SlackReferences::default()
+                },
+                ..RenderReport::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_report_unresolved_users_with_display_policy() {
+        let blocks = vec![SlackBlock::Section(SlackSectionBlock::new().with_text(
+            SlackBlockText::MarkDown(SlackBlockMarkDownText::new(
+                "<@U1> and <@U2|bob>".to_string(),
+            )),
+        ))];
+        let slack_references = SlackReferences {
+            users: HashMap::from([(SlackUserId("U1".to_string()), None)]),
+            user_profiles: HashMap::from([(
+                SlackUserId("U1".to_string()),
+                SlackUserReference {
+                    display_name: Some("ally".to_string()),
+                    ..SlackUserReference::default()
+                },
+            )]),
+            ..SlackReferences::default()
+        };
+        let options = RenderOptions {
+            user_display_policy: UserDisplayPolicy::DisplayName,
+            ..RenderOptions::default()
+        };
+
+        assert_eq!(
+            build_render_report(&blocks, &slack_references, &options),
+            RenderReport {
+                unresolved_references: SlackReferences {
+                    users: HashMap::from([(SlackUserId("U2".to_string()), None)]),
+                    ..SlackReferences::default()
+                },
+                ..RenderReport::default()
+            }
+        );
+        assert_eq!(
+            render_blocks_as_text_with_options(&blocks, &slack_references, &options),
+            "@ally and @bob"
+        );
+    }
+
+    #[test]
+    fn test_report_broken_emoji_aliases() {
+        let blocks = vec![rich_text_block(serde_json::json!({
+            "type": "rich_text",
+            "elements": [
+                {
+                    "type": "rich_text_section",
+                    "elements": [
+                        { "type": "emoji", "name": "ping" },
+                        { "type": "emoji", "name": "gone" },
+                        { "type": "emoji", "name": "yes" }
+                    ]
+                }
+            ]
+        }))];
+        let alias = |name: &str| Some(SlackEmojiRef::Alias(SlackEmojiName(name.to_string())));
+        let slack_references = SlackReferences {
+            emojis: HashMap::from([
+                (SlackEmojiName("ping".to_string()), alias("pong")),
+                (SlackEmojiName("pong".to_string()), alias("ping")),
+                (SlackEmojiName("gone".to_string()), alias("deleted")),
+                (SlackEmojiName("yes".to_string()), alias("thumbsup")),
+            ]),
+            ..SlackReferences::default()
+        };
+
+        assert_eq!(
+            build_render_report(&blocks, &slack_references, &RenderOptions::default()),
+            RenderReport {
+                broken_emoji_aliases: vec![
+                    SlackEmojiName("gone".to_string()),
+                    SlackEmojiName("ping".to_string())
+                ],
+                ..RenderReport::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_report_unsupported_blocks_and_elements() {
+        let blocks = vec![
+            SlackBlock::Actions(SlackActionsBlock::new(vec![])),
+            rich_text_block(serde_json::json!({
+                "type": "rich_text",
+                "elements": [
+                    {
+                        "type": "rich_text_section",
+                        "elements": [
+                            { "type": "date", "timestamp": 1720000000, "format": "{date}" },
+                            { "type": "text", "text": "Hello" }
+                        ]
+                    },
+                    {
+                        "type": "rich_text_list",
+                        "style": "bullet",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    { "type": "color", "value": "#FF0000" },
+                                    { "type": "date", "timestamp": 1720000000, "format": "{date}" }
+                                ]
+                            }
+                        ]
+                    }
+                ]
+            })),
+            SlackBlock::Actions(SlackActionsBlock::new(vec![])),
+        ];
+
+        assert_eq!(
+            build_render_report(
+                &blocks,
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            RenderReport {
+                unsupported_blocks: vec!["actions".to_string()],
+                unsupported_elements: vec!["color".to_string()],
+                ..RenderReport::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_report_emojis_in_mrkdwn_text() {
+        let blocks = vec![SlackBlock::Section(
+            SlackSectionBlock::new().with_text(SlackBlockText::MarkDown(
+                SlackBlockMarkDownText::new(
+                    ":partyparrot: :smile: at 10:30, see <https://example.com|link> and `:code:`"
+                        .to_string(),
+                ),
+            )),
+        )];
+
+        assert_eq!(
+            build_render_report(
+                &blocks,
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            RenderReport {
+                unresolved_references: SlackReferences {
+                    emojis: HashMap::from([(SlackEmojiName("partyparrot".to_string()), None)]),
+                    ..SlackReferences::default()
+                },
+                ..RenderReport::default()
+            }
+        );
+    }
+}
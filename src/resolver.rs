@@ -0,0 +1,464 @@
+use std::{collections::HashMap, convert::Infallible, hash::Hash, sync::Mutex};
+
+use async_trait::async_trait;
+use slack_morphism::prelude::*;
+
+use crate::references::{
+    find_slack_references_in_messages, SlackReferenceKind, SlackReferences, SlackUserReference,
+};
+
+/// Number of IDs sent to a resolver in a single call by default.
+pub const DEFAULT_RESOLVER_BATCH_SIZE: usize = 100;
+
+/// Resolve Slack IDs into names, usually by calling the Slack Web API.
+///
+/// Each method receives a batch of unique IDs and returns the resolved entries. IDs mapped to
+/// `None` are confirmed as not found, while IDs missing from the returned map could not be
+/// resolved this time, and may be requested again.
+#[async_trait]
+pub trait SlackReferenceResolver: Send + Sync {
+    type Error: Send;
+
+    async fn resolve_users(
+        &self,
+        user_ids: &[SlackUserId],
+    ) -> Result<HashMap<SlackUserId, Option<String>>, Self::Error>;
+
+    async fn resolve_channels(
+        &self,
+        channel_ids: &[SlackChannelId],
+    ) -> Result<HashMap<SlackChannelId, Option<String>>, Self::Error>;
+
+    async fn resolve_usergroups(
+        &self,
+        usergroup_ids: &[SlackUserGroupId],
+    ) -> Result<HashMap<SlackUserGroupId, Option<String>>, Self::Error>;
+
+    async fn resolve_emojis(
+        &self,
+        emoji_names: &[SlackEmojiName],
+    ) -> Result<HashMap<SlackEmojiName, Option<SlackEmojiRef>>, Self::Error>;
+
+    async fn resolve_teams(
+        &self,
+        team_ids: &[SlackTeamId],
+    ) -> Result<HashMap<SlackTeamId, Option<String>>, Self::Error>;
+
+    /// Profiles of users, with their avatar and real name. No profile is resolved by default.
+    async fn resolve_user_profiles(
+        &self,
+        _user_ids: &[SlackUserId],
+    ) -> Result<HashMap<SlackUserId, Option<SlackUserReference>>, Self::Error> {
+        Ok(HashMap::new())
+    }
+}
+
+/// Resolve every unresolved entry of `slack_references` with `resolver`, then the profiles of
+/// its resolved users.
+/// Unresolved IDs are sent in batches of at most `batch_size` IDs, already resolved entries are
+/// kept as is and IDs the resolver does not know about stay unresolved.
+pub async fn resolve_slack_references<R>(
+    slack_references: &SlackReferences,
+    resolver: &R,
+    batch_size: usize,
+) -> Result<SlackReferences, R::Error>
+where
+    R: SlackReferenceResolver + ?Sized,
+{
+    let mut resolved = slack_references.clone();
+    resolve_in_batches(&mut resolved.users, batch_size, async |ids| {
+        resolver.resolve_users(ids).await
+    })
+    .await?;
+    resolve_in_batches(&mut resolved.channels, batch_size, async |ids| {
+        resolver.resolve_channels(ids).await
+    })
+    .await?;
+    resolve_in_batches(&mut resolved.usergroups, batch_size, async |ids| {
+        resolver.resolve_usergroups(ids).await
+    })
+    .await?;
+    resolve_in_batches(&mut resolved.emojis, batch_size, async |ids| {
+        resolver.resolve_emojis(ids).await
+    })
+    .await?;
+    resolve_in_batches(&mut resolved.teams, batch_size, async |ids| {
+        resolver.resolve_teams(ids).await
+    })
+    .await?;
+
+    let mut user_profiles = resolved
+        .users
+        .iter()
+        .filter(|(user_id, name)| name.is_some() && !resolved.user_profiles.contains_key(*user_id))
+        .map(|(user_id, _)| (user_id.clone(), None))
+        .collect::<HashMap<_, _>>();
+    resolve_in_batches(&mut user_profiles, batch_size, async |ids| {
+        resolver.resolve_user_profiles(ids).await
+    })
+    .await?;
+    resolved.user_profiles.extend(
+        user_profiles
+            .into_iter()
+            .filter_map(|(user_id, profile)| Some((user_id, profile?))),
+    );
+    Ok(resolved)
+}
+
+/// Find and resolve all Slack references used in `messages`.
+/// References shared by several messages are only resolved once.
+pub async fn resolve_slack_references_in_messages<R>(
+    messages: &[SlackHistoryMessage],
+    resolver: &R,
+    batch_size: usize,
+) -> Result<SlackReferences, R::Error>
+where
+    R: SlackReferenceResolver + ?Sized,
+{
+    let slack_references = find_slack_references_in_messages(messages);
+    resolve_slack_references(&slack_references, resolver, batch_size).await
+}
+
+async fn resolve_in_batches<K, V, E>(
+    entries: &mut HashMap<K, Option<V>>,
+    batch_size: usize,
+    resolve: impl AsyncFn(&[K]) -> Result<HashMap<K, Option<V>>, E>,
+) -> Result<(), E>
+where
+    K: Eq + Hash + Clone,
+{
+    let unresolved_ids = entries
+        .iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(id, _)| id.clone())
+        .collect::<Vec<K>>();
+    for batch in unresolved_ids.chunks(batch_size.max(1)) {
+        for (id, value) in resolve(batch).await? {
+            if value.is_some() && entries.contains_key(&id) {
+                entries.insert(id, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// In-memory [`SlackReferenceResolver`] resolving IDs from a [`SlackReferences`], the IDs it
+/// does not know about being reported as not found.
+/// It records the size of every batch it receives, which is useful in tests.
+#[derive(Debug, Default)]
+pub struct InMemorySlackReferenceResolver {
+    pub slack_references: SlackReferences,
+    batches: Mutex<Vec<(SlackReferenceKind, usize)>>,
+}
+
+impl InMemorySlackReferenceResolver {
+    pub fn new(slack_references: SlackReferences) -> InMemorySlackReferenceResolver {
+        InMemorySlackReferenceResolver {
+            slack_references,
+            batches: Mutex::new(vec![]),
+        }
+    }
+
+    /// Kind and size of every batch received so far, in call order.
+    pub fn batches(&self) -> Vec<(SlackReferenceKind, usize)> {
+        self.batches
+            .lock()
+            .map(|batches| batches.clone())
+            .unwrap_or_default()
+    }
+
+    fn lookup<K, V>(
+        &self,
+        kind: SlackReferenceKind,
+        ids: &[K],
+        entries: &HashMap<K, Option<V>>,
+    ) -> HashMap<K, Option<V>>
+    where
+        K: Eq + Hash + Clone,
+        V: Clone,
+    {
+        if let Ok(mut batches) = self.batches.lock() {
+            batches.push((kind, ids.len()));
+        }
+        ids.iter()
+            .map(|id| (id.clone(), entries.get(id).cloned().flatten()))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl SlackReferenceResolver for InMemorySlackReferenceResolver {
+    type Error = Infallible;
+
+    async fn resolve_users(
+        &self,
+        user_ids: &[SlackUserId],
+    ) -> Result<HashMap<SlackUserId, Option<String>>, Self::Error> {
+        Ok(self.lookup(
+            SlackReferenceKind::User,
+            user_ids,
+            &self.slack_references.users,
+        ))
+    }
+
+    async fn resolve_channels(
+        &self,
+        channel_ids: &[SlackChannelId],
+    ) -> Result<HashMap<SlackChannelId, Option<String>>, Self::Error> {
+        Ok(self.lookup(
+            SlackReferenceKind::Channel,
+            channel_ids,
+            &self.slack_references.channels,
+        ))
+    }
+
+    async fn resolve_usergroups(
+        &self,
+        usergroup_ids: &[SlackUserGroupId],
+    ) -> Result<HashMap<SlackUserGroupId, Option<String>>, Self::Error> {
+        Ok(self.lookup(
+            SlackReferenceKind::Usergroup,
+            usergroup_ids,
+            &self.slack_references.usergroups,
+        ))
+    }
+
+    async fn resolve_emojis(
+        &self,
+        emoji_names: &[SlackEmojiName],
+    ) -> Result<HashMap<SlackEmojiName, Option<SlackEmojiRef>>, Self::Error> {
+        Ok(self.lookup(
+            SlackReferenceKind::Emoji,
+            emoji_names,
+            &self.slack_references.emojis,
+        ))
+    }
+
+    async fn resolve_teams(
+        &self,
+        team_ids: &[SlackTeamId],
+    ) -> Result<HashMap<SlackTeamId, Option<String>>, Self::Error> {
+        Ok(self.lookup(
+            SlackReferenceKind::Team,
+            team_ids,
+            &self.slack_references.teams,
+        ))
+    }
+
+    async fn resolve_user_profiles(
+        &self,
+        user_ids: &[SlackUserId],
+    ) -> Result<HashMap<SlackUserId, Option<SlackUserReference>>, Self::Error> {
+        let user_profiles = self
+            .slack_references
+            .user_profiles
+            .iter()
+            .map(|(user_id, profile)| (user_id.clone(), Some(profile.clone())))
+            .collect();
+        Ok(self.lookup(SlackReferenceKind::UserProfile, user_ids, &user_profiles))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known_references() -> SlackReferences {
+        SlackReferences {
+            users: HashMap::from([
+                (SlackUserId("U1".to_string()), Some("alice".to_string())),
+                (SlackUserId("U2".to_string()), Some("bob".to_string())),
+                (SlackUserId("U3".to_string()), Some("carol".to_string())),
+            ]),
+            channels: HashMap::from([(
+                SlackChannelId("C1".to_string()),
+                Some("general".to_string()),
+            )]),
+            emojis: HashMap::from([(
+                SlackEmojiName("partyparrot".to_string()),
+                Some(SlackEmojiRef::Url(
+                    "https://emoji.example.com/partyparrot.gif".parse().unwrap(),
+                )),
+            )]),
+            teams: HashMap::from([(SlackTeamId("T1".to_string()), Some("Acme".to_string()))]),
+            ..SlackReferences::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_slack_references() {
+        let resolver = InMemorySlackReferenceResolver::new(known_references());
+        let slack_references = SlackReferences {
+            users: HashMap::from([
+                (SlackUserId("U1".to_string()), None),
+                (SlackUserId("U404".to_string()), None),
+            ]),
+            channels: HashMap::from([(SlackChannelId("C1".to_string()), None)]),
+            emojis: HashMap::from([(SlackEmojiName("partyparrot".to_string()), None)]),
+            teams: HashMap::from([(SlackTeamId("T1".to_string()), None)]),
+            ..SlackReferences::default()
+        };
+
+        let resolved =
+            resolve_slack_references(&slack_references, &resolver, DEFAULT_RESOLVER_BATCH_SIZE)
+                .await
+                .unwrap();
+
+        assert_eq!(
+            resolved,
+            SlackReferences {
+                users: HashMap::from([
+                    (SlackUserId("U1".to_string()), Some("alice".to_string())),
+                    (SlackUserId("U404".to_string()), None),
+                ]),
+                channels: HashMap::from([(
+                    SlackChannelId("C1".to_string()),
+                    Some("general".to_string())
+                )]),
+                emojis: HashMap::from([(
+                    SlackEmojiName("partyparrot".to_string()),
+                    Some(SlackEmojiRef::Url(
+                        "https://emoji.example.com/partyparrot.gif".parse().unwrap()
+                    ))
+                )]),
+                teams: HashMap::from([(SlackTeamId("T1".to_string()), Some("Acme".to_string()))]),
+                ..SlackReferences::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_slack_references_keeps_resolved_entries() {
+        let resolver = InMemorySlackReferenceResolver::new(known_references());
+        let slack_references = SlackReferences {
+            users: HashMap::from([(
+                SlackUserId("U1".to_string()),
+                Some("alice.cached".to_string()),
+            )]),
+            ..SlackReferences::default()
+        };
+
+        let resolved = resolve_slack_references(&slack_references, &resolver, 10)
+            .await
+            .unwrap();
+
+        assert_eq!(resolved, slack_references);
+        // Only the profile of the cached user is requested
+        assert_eq!(
+            resolver.batches(),
+            vec![(SlackReferenceKind::UserProfile, 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_slack_references_in_batches() {
+        let resolver = InMemorySlackReferenceResolver::new(known_references());
+        let slack_references = SlackReferences {
+            users: HashMap::from([
+                (SlackUserId("U1".to_string()), None),
+                (SlackUserId("U2".to_string()), None),
+                (SlackUserId("U3".to_string()), None),
+            ]),
+            ..SlackReferences::default()
+        };
+
+        let resolved = resolve_slack_references(&slack_references, &resolver, 2)
+            .await
+            .unwrap();
+
+        assert_eq!(resolved.users, known_references().users);
+        assert_eq!(
+            resolver.batches(),
+            vec![
+                (SlackReferenceKind::User, 2),
+                (SlackReferenceKind::User, 1),
+                (SlackReferenceKind::UserProfile, 2),
+                (SlackReferenceKind::UserProfile, 1)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_slack_references_in_messages_deduplicates_ids() {
+        let resolver = InMemorySlackReferenceResolver::new(known_references());
+        let messages: Vec<SlackHistoryMessage> = serde_json::from_value(serde_json::json!([
+            {
+                "ts": "1700000000.000100",
+                "user": "U1",
+                "blocks": [
+                    {
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    {
+                                        "type": "user",
+                                        "user_id": "U2"
+                                    },
+                                    {
+                                        "type": "channel",
+                                        "channel_id": "C1"
+                                    }
+                                ]
+                            }
+                        ]
+                    }
+                ]
+            },
+            {
+                "ts": "1700000000.000200",
+                "user": "U2",
+                "blocks": [
+                    {
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    {
+                                        "type": "user",
+                                        "user_id": "U1"
+                                    },
+                                    {
+                                        "type": "channel",
+                                        "channel_id": "C1"
+                                    }
+                                ]
+                            }
+                        ]
+                    }
+                ]
+            }
+        ]))
+        .unwrap();
+
+        let resolved =
+            resolve_slack_references_in_messages(&messages, &resolver, DEFAULT_RESOLVER_BATCH_SIZE)
+                .await
+                .unwrap();
+
+        assert_eq!(
+            resolved,
+            SlackReferences {
+                users: HashMap::from([
+                    (SlackUserId("U1".to_string()), Some("alice".to_string())),
+                    (SlackUserId("U2".to_string()), Some("bob".to_string())),
+                ]),
+                channels: HashMap::from([(
+                    SlackChannelId("C1".to_string()),
+                    Some("general".to_string())
+                )]),
+                ..SlackReferences::default()
+            }
+        );
+        assert_eq!(
+            resolver.batches(),
+            vec![
+                (SlackReferenceKind::User, 2),
+                (SlackReferenceKind::Channel, 1),
+                (SlackReferenceKind::UserProfile, 2)
+            ]
+        );
+    }
+}
@@ -0,0 +1,774 @@
+use std::{collections::HashMap, future::Future, hash::Hash, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use slack_morphism::{errors::SlackClientError, prelude::*};
+use tokio::{
+    sync::OnceCell,
+    time::{sleep_until, Instant},
+};
+
+use crate::{references::SlackUserReference, resolver::SlackReferenceResolver};
+
+/// Number of times a request is retried after being rate limited by default.
+pub const DEFAULT_MAX_RETRIES: usize = 3;
+
+/// Number of `users.info`, `conversations.info` or `team.info` calls in flight at once by default.
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 4;
+
+/// Delay before retrying a rate limited request when Slack does not send a `Retry-After` header.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// [`SlackReferenceResolver`] calling the Slack Web API through a slack-morphism session.
+///
+/// Users, channels and teams are resolved with one `users.info`, `conversations.info` or
+/// `team.info` call per ID, user profiles reusing the `users.info` responses, at most
+/// `max_concurrent_requests` calls of a batch being in flight at once. Usergroups and emojis are
+/// resolved from a single `usergroups.list` and `emoji.list` call, fetched on first use.
+/// When a call is rate limited, every call is paused for the delay requested by Slack before
+/// being retried. IDs Slack does not know about are left unresolved, other errors (including
+/// rate limits outlasting the retries) are returned.
+pub struct SlackApiReferenceResolver<'a, SCHC>
+where
+    SCHC: SlackClientHttpConnector + Send + Sync,
+{
+    session: SlackClientSession<'a, SCHC>,
+    max_retries: usize,
+    max_concurrent_requests: usize,
+    /// Calls are paused until then after being rate limited.
+    paused_until: Mutex<Option<Instant>>,
+    /// `users.info` responses, shared by user names and profiles.
+    users: Mutex<HashMap<SlackUserId, Option<SlackUser>>>,
+    usergroups: OnceCell<HashMap<SlackUserGroupId, String>>,
+    emojis: OnceCell<HashMap<SlackEmojiName, SlackEmojiRef>>,
+}
+
+impl<'a, SCHC> SlackApiReferenceResolver<'a, SCHC>
+where
+    SCHC: SlackClientHttpConnector + Send + Sync,
+{
+    pub fn new(session: SlackClientSession<'a, SCHC>) -> Self {
+        SlackApiReferenceResolver {
+            session,
+            max_retries: DEFAULT_MAX_RETRIES,
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+            paused_until: Mutex::new(None),
+            users: Mutex::new(HashMap::new()),
+            usergroups: OnceCell::new(),
+            emojis: OnceCell::new(),
+        }
+    }
+
+    /// Set how many times a rate limited request is retried before giving up.
+    pub fn with_max_retries(self, max_retries: usize) -> Self {
+        SlackApiReferenceResolver {
+            max_retries,
+            ..self
+        }
+    }
+
+    /// Set how many `users.info`, `conversations.info` or `team.info` calls are in flight at once.
+    pub fn with_max_concurrent_requests(self, max_concurrent_requests: usize) -> Self {
+        SlackApiReferenceResolver {
+            max_concurrent_requests: max_concurrent_requests.max(1),
+            ..self
+        }
+    }
+
+    async fn with_retries<T, F, Fut>(&self, request: F) -> ClientResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = ClientResult<T>>,
+    {
+        let mut retries = 0;
+        loop {
+            self.wait_until_unpaused().await;
+            match request().await {
+                Err(SlackClientError::RateLimitError(error)) if retries < self.max_retries => {
+                    retries += 1;
+                    self.pause(error.retry_after.unwrap_or(DEFAULT_RETRY_AFTER));
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Pause every call for `delay`, unless they are already paused for longer.
+    fn pause(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        if let Ok(mut paused_until) = self.paused_until.lock() {
+            if paused_until.is_none_or(|paused_until| paused_until < until) {
+                *paused_until = Some(until);
+            }
+        }
+    }
+
+    async fn wait_until_unpaused(&self) {
+        loop {
+            let paused_until = self
+                .paused_until
+                .lock()
+                .ok()
+                .and_then(|paused_until| *paused_until);
+            match paused_until {
+                Some(until) if until > Instant::now() => sleep_until(until).await,
+                _ => return,
+            }
+        }
+    }
+
+    /// Look up `ids` with at most `max_concurrent_requests` calls in flight, stopping at the
+    /// first failed lookup.
+    async fn resolve_each<K, V, F, Fut>(
+        &self,
+        ids: &[K],
+        lookup: F,
+    ) -> ClientResult<HashMap<K, Option<V>>>
+    where
+        K: Eq + Hash + Clone,
+        F: Fn(K) -> Fut,
+        Fut: Future<Output = (K, ClientResult<Option<V>>)>,
+    {
+        stream::iter(ids.iter().cloned().map(lookup))
+            .buffer_unordered(self.max_concurrent_requests)
+            .map(|(id, result)| result.map(|value| (id, value)))
+            .try_collect()
+            .await
+    }
+
+    async fn user(&self, user_id: &SlackUserId) -> ClientResult<Option<SlackUser>> {
+        let cached = self
+            .users
+            .lock()
+            .ok()
+            .and_then(|users| users.get(user_id).cloned());
+        if let Some(user) = cached {
+            return Ok(user);
+        }
+        let request = SlackApiUsersInfoRequest::new(user_id.clone());
+        let response = self
+            .with_retries(|| self.session.users_info(&request))
+            .await;
+        let user = not_found_as_none(
+            response.map(|response| Some(response.user)),
+            "user_not_found",
+        )?;
+        if let Ok(mut users) = self.users.lock() {
+            users.insert(user_id.clone(), user.clone());
+        }
+        Ok(user)
+    }
+
+    async fn user_name(&self, user_id: &SlackUserId) -> ClientResult<Option<String>> {
+        Ok(self.user(user_id).await?.as_ref().and_then(user_name))
+    }
+
+    async fn user_profile(
+        &self,
+        user_id: &SlackUserId,
+    ) -> ClientResult<Option<SlackUserReference>> {
+        Ok(self
+            .user(user_id)
+            .await?
+            .as_ref()
+            .map(SlackUserReference::from))
+    }
+
+    async fn channel_name(&self, channel_id: &SlackChannelId) -> ClientResult<Option<String>> {
+        let request = SlackApiConversationsInfoRequest::new(channel_id.clone());
+        let response = self
+            .with_retries(|| self.session.conversations_info(&request))
+            .await;
+        not_found_as_none(
+            response.map(|response| response.channel.name),
+            "channel_not_found",
+        )
+    }
+
+    async fn team_name(&self, team_id: &SlackTeamId) -> ClientResult<Option<String>> {
+        let request = SlackApiTeamInfoRequest::new().with_team(team_id.clone());
+        let response = self.with_retries(|| self.session.team_info(&request)).await;
+        not_found_as_none(
+            response.map(|response| response.team.name),
+            "team_not_found",
+        )
+    }
+
+    async fn all_usergroups(&self) -> ClientResult<&HashMap<SlackUserGroupId, String>> {
+        self.usergroups
+            .get_or_try_init(|| async {
+                let request = SlackApiUserGroupsListRequest::new();
+                let response = self
+                    .with_retries(|| self.session.usergroups_list(&request))
+                    .await?;
+                Ok(response
+                    .usergroups
+                    .into_iter()
+                    .map(|usergroup| (usergroup.id, usergroup.handle))
+                    .collect())
+            })
+            .await
+    }
+
+    async fn all_emojis(&self) -> ClientResult<&HashMap<SlackEmojiName, SlackEmojiRef>> {
+        self.emojis
+            .get_or_try_init(|| async {
+                let response = self.with_retries(|| self.session.emoji_list()).await?;
+                Ok(response.emoji)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<SCHC> SlackReferenceResolver for SlackApiReferenceResolver<'_, SCHC>
+where
+    SCHC: SlackClientHttpConnector + Send + Sync,
+{
+    type Error = SlackClientError;
+
+    async fn resolve_users(
+        &self,
+        user_ids: &[SlackUserId],
+    ) -> Result<HashMap<SlackUserId, Option<String>>, Self::Error> {
+        self.resolve_each(user_ids, |user_id| async {
+            let name = self.user_name(&user_id).await;
+            (user_id, name)
+        })
+        .await
+    }
+
+    async fn resolve_channels(
+        &self,
+        channel_ids: &[SlackChannelId],
+    ) -> Result<HashMap<SlackChannelId, Option<String>>, Self::Error> {
+        self.resolve_each(channel_ids, |channel_id| async {
+            let name = self.channel_name(&channel_id).await;
+            (channel_id, name)
+        })
+        .await
+    }
+
+    async fn resolve_usergroups(
+        &self,
+        usergroup_ids: &[SlackUserGroupId],
+    ) -> Result<HashMap<SlackUserGroupId, Option<String>>, Self::Error> {
+        let usergroups = self.all_usergroups().await?;
+        Ok(usergroup_ids
+            .iter()
+            .map(|id| (id.clone(), usergroups.get(id).cloned()))
+            .collect())
+    }
+
+    async fn resolve_emojis(
+        &self,
+        emoji_names: &[SlackEmojiName],
+    ) -> Result<HashMap<SlackEmojiName, Option<SlackEmojiRef>>, Self::Error> {
+        let emojis = self.all_emojis().await?;
+        Ok(emoji_names
+            .iter()
+            .map(|name| (name.clone(), emojis.get(name).cloned()))
+            .collect())
+    }
+
+    async fn resolve_teams(
+        &self,
+        team_ids: &[SlackTeamId],
+    ) -> Result<HashMap<SlackTeamId, Option<String>>, Self::Error> {
+        self.resolve_each(team_ids, |team_id| async {
+            let name = self.team_name(&team_id).await;
+            (team_id, name)
+        })
+        .await
+    }
+
+    async fn resolve_user_profiles(
+        &self,
+        user_ids: &[SlackUserId],
+    ) -> Result<HashMap<SlackUserId, Option<SlackUserReference>>, Self::Error> {
+        self.resolve_each(user_ids, |user_id| async {
+            let profile = self.user_profile(&user_id).await;
+            (user_id, profile)
+        })
+        .await
+    }
+}
+
+/// Name displayed in mentions: the display name if set, the real name otherwise.
+fn user_name(user: &SlackUser) -> Option<String> {
+    let profile = user.profile.as_ref();
+    [
+        profile.and_then(|profile| profile.display_name.clone()),
+        profile.and_then(|profile| profile.real_name.clone()),
+        user.real_name.clone(),
+        user.name.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|name| !name.is_empty())
+}
+
+fn not_found_as_none<T>(
+    result: ClientResult<Option<T>>,
+    not_found_code: &str,
+) -> ClientResult<Option<T>> {
+    match result {
+        Err(SlackClientError::ApiError(error)) if error.code == not_found_code => Ok(None),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use hyper_util::client::legacy::connect::HttpConnector;
+    use slack_morphism::hyper_tokio::SlackClientHyperConnector;
+    use wiremock::{
+        matchers::{method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::{
+        references::SlackReferences,
+        resolver::{resolve_slack_references, DEFAULT_RESOLVER_BATCH_SIZE},
+    };
+
+    fn slack_client(
+        mock_server: &MockServer,
+    ) -> SlackClient<SlackClientHyperConnector<HttpConnector>> {
+        SlackClient::new(
+            SlackClientHyperConnector::with_connector(HttpConnector::new())
+                .with_slack_api_url(&format!("{}/api", mock_server.uri())),
+        )
+    }
+
+    fn token() -> SlackApiToken {
+        SlackApiToken::new("xoxb-test".into())
+    }
+
+    fn user_response(user_id: &str, display_name: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "ok": true,
+            "user": {
+                "id": user_id,
+                "name": "login",
+                "profile": {
+                    "display_name": display_name,
+                    "real_name": "Real Name"
+                }
+            }
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_resolve_users() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .and(query_param("user", "U1"))
+            .respond_with(user_response("U1", "alice"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .and(query_param("user", "U404"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ok": false,
+                "error": "user_not_found"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = slack_client(&mock_server);
+        let token = token();
+        let resolver = SlackApiReferenceResolver::new(client.open_session(&token));
+
+        let resolved = resolver
+            .resolve_users(&[
+                SlackUserId("U1".to_string()),
+                SlackUserId("U404".to_string()),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            resolved,
+            HashMap::from([
+                (SlackUserId("U1".to_string()), Some("alice".to_string())),
+                (SlackUserId("U404".to_string()), None),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_user_profiles_with_flags() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .and(query_param("user", "B1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ok": true,
+                "user": {
+                    "id": "B1",
+                    "name": "deploy",
+                    "deleted": true,
+                    "is_bot": true,
+                    "profile": { "real_name": "Deploy Bot" }
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = slack_client(&mock_server);
+        let token = token();
+        let resolver = SlackApiReferenceResolver::new(client.open_session(&token));
+
+        assert_eq!(
+            resolver
+                .resolve_user_profiles(&[SlackUserId("B1".to_string())])
+                .await
+                .unwrap(),
+            HashMap::from([(
+                SlackUserId("B1".to_string()),
+                Some(SlackUserReference {
+                    real_name: Some("Deploy Bot".to_string()),
+                    deleted: true,
+                    bot: true,
+                    ..SlackUserReference::default()
+                })
+            )])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_users_retries_when_rate_limited() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", "0")
+                    .set_body_json(serde_json::json!({
+                        "ok": false,
+                        "error": "ratelimited"
+                    })),
+            )
+            .up_to_n_times(1)
+            .expect(1)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .respond_with(user_response("U1", "alice"))
+            .expect(1)
+            .with_priority(2)
+            .mount(&mock_server)
+            .await;
+
+        let client = slack_client(&mock_server);
+        let token = token();
+        let resolver = SlackApiReferenceResolver::new(client.open_session(&token));
+
+        let resolved = resolver
+            .resolve_users(&[SlackUserId("U1".to_string())])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            resolved,
+            HashMap::from([(SlackUserId("U1".to_string()), Some("alice".to_string()))])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_users_gives_up_after_max_retries() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", "0")
+                    .set_body_json(serde_json::json!({
+                        "ok": false,
+                        "error": "ratelimited"
+                    })),
+            )
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let client = slack_client(&mock_server);
+        let token = token();
+        let resolver =
+            SlackApiReferenceResolver::new(client.open_session(&token)).with_max_retries(1);
+
+        let error = resolver
+            .resolve_users(&[SlackUserId("U1".to_string())])
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, SlackClientError::RateLimitError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_pauses_every_call() {
+        let mock_server = MockServer::start().await;
+        let client = slack_client(&mock_server);
+        let token = token();
+        let resolver = SlackApiReferenceResolver::new(client.open_session(&token));
+
+        resolver.pause(Duration::from_secs(30));
+        let paused_until = *resolver.paused_until.lock().unwrap();
+        // A shorter Retry-After of another call does not shorten the pause
+        resolver.pause(Duration::from_secs(1));
+        assert_eq!(*resolver.paused_until.lock().unwrap(), paused_until);
+        assert!(paused_until.unwrap() > Instant::now() + Duration::from_secs(20));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_users_returns_errors_and_keeps_lookups() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .and(query_param("user", "U1"))
+            .respond_with(user_response("U1", "alice"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .and(query_param("user", "U2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ok": false,
+                "error": "invalid_auth"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let client = slack_client(&mock_server);
+        let token = token();
+        let resolver = SlackApiReferenceResolver::new(client.open_session(&token))
+            .with_max_concurrent_requests(1);
+
+        let error = resolver
+            .resolve_users(&[SlackUserId("U1".to_string()), SlackUserId("U2".to_string())])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, SlackClientError::ApiError(error) if error.code == "invalid_auth"));
+
+        // The lookup of U1 done before the error is not sent again
+        let resolved = resolver
+            .resolve_users(&[SlackUserId("U1".to_string())])
+            .await
+            .unwrap();
+        assert_eq!(
+            resolved,
+            HashMap::from([(SlackUserId("U1".to_string()), Some("alice".to_string()))])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_call_pauses_concurrent_calls() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .and(query_param("user", "U1"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", "1")
+                    .set_body_json(serde_json::json!({
+                        "ok": false,
+                        "error": "ratelimited"
+                    })),
+            )
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .and(query_param("user", "U1"))
+            .respond_with(user_response("U1", "alice"))
+            .mount(&mock_server)
+            .await;
+        // U2 is in flight while U1 is rate limited: U3 only starts once U2 is done
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .and(query_param("user", "U2"))
+            .respond_with(user_response("U2", "bob").set_delay(Duration::from_millis(200)))
+            .mount(&mock_server)
+            .await;
+        let u3_requested_at = Arc::new(Mutex::new(None));
+        let requested_at = u3_requested_at.clone();
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .and(query_param("user", "U3"))
+            .respond_with(move |_: &wiremock::Request| {
+                *requested_at.lock().unwrap() = Some(Instant::now());
+                user_response("U3", "carol")
+            })
+            .mount(&mock_server)
+            .await;
+
+        let client = slack_client(&mock_server);
+        let token = token();
+        let resolver = SlackApiReferenceResolver::new(client.open_session(&token))
+            .with_max_concurrent_requests(2);
+
+        let started_at = Instant::now();
+        let resolved = resolver
+            .resolve_users(&[
+                SlackUserId("U1".to_string()),
+                SlackUserId("U2".to_string()),
+                SlackUserId("U3".to_string()),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(resolved.len(), 3);
+        assert!(resolved.values().all(Option::is_some));
+        let u3_requested_at = u3_requested_at.lock().unwrap().unwrap();
+        assert!(u3_requested_at - started_at >= Duration::from_millis(900));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_slack_references() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/users.info"))
+            .and(query_param("user", "U1"))
+            .respond_with(user_response("U1", ""))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/conversations.info"))
+            .and(query_param("channel", "C1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ok": true,
+                "channel": {
+                    "id": "C1",
+                    "name": "general",
+                    "created": 1700000000
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/usergroups.list"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ok": true,
+                "usergroups": [
+                    {
+                        "id": "S1",
+                        "team_id": "T1",
+                        "name": "Admins",
+                        "handle": "admins",
+                        "is_external": false,
+                        "date_create": 1700000000,
+                        "created_by": "U1",
+                        "prefs": {
+                            "channels": [],
+                            "groups": []
+                        },
+                        "user_count": 2
+                    }
+                ]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/emoji.list"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ok": true,
+                "emoji": {
+                    "partyparrot": "https://emoji.example.com/partyparrot.gif",
+                    "parrot": "alias:partyparrot"
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = slack_client(&mock_server);
+        let token = token();
+        let resolver = SlackApiReferenceResolver::new(client.open_session(&token));
+        let slack_references = SlackReferences {
+            users: HashMap::from([(SlackUserId("U1".to_string()), None)]),
+            channels: HashMap::from([(SlackChannelId("C1".to_string()), None)]),
+            usergroups: HashMap::from([
+                (SlackUserGroupId("S1".to_string()), None),
+                (SlackUserGroupId("S2".to_string()), None),
+            ]),
+            emojis: HashMap::from([
+                (SlackEmojiName("partyparrot".to_string()), None),
+                (SlackEmojiName("parrot".to_string()), None),
+            ]),
+            ..SlackReferences::default()
+        };
+
+        // A batch size of 1 sends one request per ID, list endpoints are still called once
+        let resolved = resolve_slack_references(&slack_references, &resolver, 1)
+            .await
+            .unwrap();
+        assert_eq!(
+            resolved,
+            SlackReferences {
+                users: HashMap::from([(
+                    SlackUserId("U1".to_string()),
+                    Some("Real Name".to_string())
+                )]),
+                channels: HashMap::from([(
+                    SlackChannelId("C1".to_string()),
+                    Some("general".to_string())
+                )]),
+                usergroups: HashMap::from([
+                    (
+                        SlackUserGroupId("S1".to_string()),
+                        Some("admins".to_string())
+                    ),
+                    (SlackUserGroupId("S2".to_string()), None),
+                ]),
+                emojis: HashMap::from([
+                    (
+                        SlackEmojiName("partyparrot".to_string()),
+                        Some(SlackEmojiRef::Url(
+                            "https://emoji.example.com/partyparrot.gif".parse().unwrap()
+                        ))
+                    ),
+                    (
+                        SlackEmojiName("parrot".to_string()),
+                        Some(SlackEmojiRef::Alias(SlackEmojiName(
+                            "partyparrot".to_string()
+                        )))
+                    ),
+                ]),
+                // The profile comes from the same users.info response as the name
+                user_profiles: HashMap::from([(
+                    SlackUserId("U1".to_string()),
+                    SlackUserReference {
+                        display_name: Some(String::new()),
+                        real_name: Some("Real Name".to_string()),
+                        ..SlackUserReference::default()
+                    }
+                )]),
+                ..SlackReferences::default()
+            }
+        );
+        assert_eq!(
+            resolve_slack_references(&resolved, &resolver, DEFAULT_RESOLVER_BATCH_SIZE)
+                .await
+                .unwrap()
+                .usergroups
+                .len(),
+            2
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use slack_morphism::prelude::*;
 
+use crate::attachment::{find_attachments_in_message, SlackAttachment};
+
 pub(crate) fn rich_text_block(value: serde_json::Value) -> SlackBlock {
     let mut value = value;
     if let Some(obj) = value.as_object_mut() {
@@ -9,3 +11,66 @@ pub(crate) fn rich_text_block(value: serde_json::Value) -> SlackBlock {
     }
     SlackBlock::RichText(serde_json::from_value(value).unwrap())
 }
+
+pub(crate) fn history_message(value: serde_json::Value) -> SlackHistoryMessage {
+    serde_json::from_value(value).unwrap()
+}
+
+/// Message without blocks, with a text fallback and a legacy attachment.
+pub(crate) fn message_with_attachment() -> SlackHistoryMessage {
+    history_message(serde_json::json!({
+        "ts": "1720000000.000100",
+        "text": "Deploy *finished*",
+        "attachments": [
+            {
+                "color": "good",
+                "title": "api v1.2.0",
+                "text": "All checks passed",
+                "fields": [
+                    { "title": "Environment", "value": "production", "short": true },
+                    { "title": "Duration", "value": "3m", "short": true }
+                ]
+            },
+            {
+                "fallback": "Build log"
+            }
+        ]
+    }))
+}
+
+/// Legacy attachment with the fields missing from the slack-morphism model.
+pub(crate) fn raw_attachments() -> Vec<SlackAttachment> {
+    find_attachments_in_message(&serde_json::json!({
+        "ts": "1720000000.000100",
+        "attachments": [
+            {
+                "color": "danger",
+                "pretext": "Build *failed*",
+                "title": "api v1.2.1",
+                "title_link": "https://ci.example.com/builds/2",
+                "text": "1 check failed",
+                "image_url": "https://ci.example.com/builds/2.png",
+                "footer": "CI"
+            }
+        ]
+    }))
+}
+
+/// Message sharing a link, with its unfurl and a regular attachment.
+pub(crate) fn message_json_with_unfurl() -> serde_json::Value {
+    serde_json::json!({
+        "ts": "1720000000.000100",
+        "text": "See <https://github.com/dax/slack-blocks-render>",
+        "attachments": [
+            {
+                "from_url": "https://github.com/dax/slack-blocks-render",
+                "original_url": "https://github.com/dax/slack-blocks-render",
+                "service_name": "GitHub",
+                "title": "dax/slack-blocks-render",
+                "title_link": "https://github.com/dax/slack-blocks-render",
+                "text": "Render Slack blocks"
+            },
+            { "title": "api v1.2.0" }
+        ]
+    })
+}
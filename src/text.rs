@@ -1,7 +1,15 @@
 use slack_morphism::prelude::*;
 
 use crate::{
-    references::SlackReferences,
+    attachment::SlackAttachment,
+    emoji::{self, split_mrkdwn_emojis, MrkdwnPart},
+    files::render_files_as_text,
+    message::MessageParts,
+    options::RenderOptions,
+    references::{mrkdwn_mention_label, replace_slack_mrkdwn_tokens, SlackReferences},
+    report::{build_message_render_report, build_render_report, RenderReport},
+    time::format_rich_text_date,
+    unfurl::render_unfurls_as_text,
     visitor::{
         visit_slack_block_mark_down_text, visit_slack_block_plain_text, visit_slack_context_block,
         visit_slack_divider_block, visit_slack_header_block, visit_slack_markdown_block,
@@ -12,30 +20,192 @@ use crate::{
 /// TODO: document this function
 ///
 pub fn render_blocks_as_text(blocks: Vec<SlackBlock>, slack_references: SlackReferences) -> String {
-    let mut block_renderer = TextRenderer::new(slack_references);
+    render_blocks_as_text_with_options(&blocks, &slack_references, &RenderOptions::default())
+}
+
+/// Render borrowed Slack blocks as plain text.
+/// Neither the blocks nor the references are cloned.
+pub fn render_blocks_as_text_with_options(
+    blocks: &[SlackBlock],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let mut block_renderer = TextRenderer::new(slack_references, options);
     for block in blocks {
-        block_renderer.visit_slack_block(&block);
+        block_renderer.visit_slack_block(block);
+    }
+    block_renderer.finish()
+}
+
+/// Render borrowed Slack blocks as plain text, along with the report of what could not be rendered
+/// faithfully.
+pub fn render_blocks_as_text_with_report(
+    blocks: &[SlackBlock],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> (String, RenderReport) {
+    (
+        render_blocks_as_text_with_options(blocks, slack_references, options),
+        build_render_report(blocks, slack_references, options),
+    )
+}
+
+/// Render a Slack message as plain text: its blocks, or its mrkdwn `text` when it has no
+/// blocks, then its files, its legacy attachments and its reactions.
+pub fn render_message_as_text(
+    message: &SlackHistoryMessage,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    render_message_parts_as_text(
+        &MessageParts::new(&message.content),
+        slack_references,
+        options,
+    )
+}
+
+/// Render a Slack message in the JSON format of the Slack API as plain text, like
+/// [`render_message_as_text`] but with the attachments and files read from the raw JSON, and
+/// the unfurls rendered after the attachments.
+pub fn render_message_json_as_text(
+    message: &serde_json::Value,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    render_message_parts_as_text(&MessageParts::from_json(message), slack_references, options)
+}
+
+/// Render a Slack message in the JSON format of the Slack API as plain text, with the report of what
+/// could not be rendered, including the blocks which could not be read.
+pub fn render_message_json_as_text_with_report(
+    message: &serde_json::Value,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> (String, RenderReport) {
+    let message = MessageParts::from_json(message);
+    (
+        render_message_parts_as_text(&message, slack_references, options),
+        build_message_render_report(&message, slack_references, options),
+    )
+}
+
+/// Render the parts of a message.
+fn render_message_parts_as_text(
+    message: &MessageParts,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let blocks = render_blocks_as_text_with_options(&message.blocks, slack_references, options);
+    let mut parts = vec![
+        blocks,
+        render_files_as_text(&message.files, options),
+        render_attachments_as_text(&message.attachments, slack_references, options),
+        render_unfurls_as_text(&message.unfurls),
+    ];
+    // Matrix has its own reactions
+    if options.matrix.is_none() {
+        parts.push(render_reactions_as_text(
+            &message.reactions,
+            &TextRenderer::new(slack_references, options),
+        ));
+    }
+    parts
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Render reactions as their emoji followed by their count, e.g. `👍 3  🎉 1`.
+fn render_reactions_as_text(reactions: &[SlackReaction], renderer: &TextRenderer) -> String {
+    reactions
+        .iter()
+        .map(|reaction| {
+            let emoji = render_emoji(&SlackEmojiName(reaction.name.0.clone()), renderer);
+            format!("{emoji} {}", reaction.count)
+        })
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+/// Render legacy attachments as plain text: their pretext, title, text, fields, blocks and footer.
+/// Unfurls are left to [`render_unfurls_as_text`].
+pub fn render_attachments_as_text(
+    attachments: &[SlackAttachment],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    attachments
+        .iter()
+        .filter(|attachment| !attachment.is_unfurl())
+        .map(|attachment| render_attachment_as_text(attachment, slack_references, options))
+        .filter(|attachment| !attachment.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn render_attachment_as_text(
+    attachment: &SlackAttachment,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let mut parts = vec![];
+    if attachment.is_fallback_only() {
+        parts.extend(attachment.fallback.clone());
     }
-    block_renderer.sub_texts.join("")
+    parts.extend(attachment.pretext.clone());
+    parts.extend(attachment.title.clone());
+    parts.extend(attachment.text.clone());
+    for field in attachment.fields.iter().flatten() {
+        let value = field.value.as_deref().unwrap_or_default();
+        match &field.title {
+            Some(title) => parts.push(format!("{title}: {value}")),
+            None => parts.push(value.to_string()),
+        }
+    }
+    if let Some(blocks) = &attachment.blocks {
+        parts.push(render_blocks_as_text_with_options(
+            blocks,
+            slack_references,
+            options,
+        ));
+    }
+    parts.extend(attachment.footer.clone());
+    parts
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-struct TextRenderer {
+pub(crate) struct TextRenderer<'a> {
     pub sub_texts: Vec<String>,
-    pub slack_references: SlackReferences,
+    pub slack_references: &'a SlackReferences,
+    pub options: &'a RenderOptions,
 }
 
-impl TextRenderer {
-    pub fn new(slack_references: SlackReferences) -> Self {
+impl<'a> TextRenderer<'a> {
+    pub fn new(slack_references: &'a SlackReferences, options: &'a RenderOptions) -> Self {
         TextRenderer {
             sub_texts: vec![],
             slack_references,
+            options,
         }
     }
+
+    /// A renderer for nested content, with the same references and options.
+    pub fn nested(&self) -> Self {
+        TextRenderer::new(self.slack_references, self.options)
+    }
+
+    pub fn finish(self) -> String {
+        self.sub_texts.join("")
+    }
 }
 
-impl Visitor for TextRenderer {
+impl Visitor for TextRenderer<'_> {
     fn visit_slack_section_block(&mut self, slack_section_block: &SlackSectionBlock) {
-        let mut section_renderer = TextRenderer::new(self.slack_references.clone());
+        let mut section_renderer = self.nested();
         visit_slack_section_block(&mut section_renderer, slack_section_block);
         self.sub_texts.push(section_renderer.sub_texts.join(""));
     }
@@ -46,7 +216,7 @@ impl Visitor for TextRenderer {
     }
 
     fn visit_slack_header_block(&mut self, slack_header_block: &SlackHeaderBlock) {
-        let mut header_renderer = TextRenderer::new(self.slack_references.clone());
+        let mut header_renderer = self.nested();
         visit_slack_header_block(&mut header_renderer, slack_header_block);
         self.sub_texts.push(header_renderer.sub_texts.join(""));
     }
@@ -60,20 +230,32 @@ impl Visitor for TextRenderer {
         &mut self,
         slack_block_mark_down_text: &SlackBlockMarkDownText,
     ) {
-        self.sub_texts.push(slack_block_mark_down_text.text.clone());
+        let text = split_mrkdwn_emojis(&slack_block_mark_down_text.text)
+            .into_iter()
+            .map(|part| match part {
+                MrkdwnPart::Text(text) => replace_slack_mrkdwn_tokens(
+                    text,
+                    &self.options.time,
+                    |date, _| date,
+                    |element| render_rich_text_section_element(element, self),
+                ),
+                MrkdwnPart::Emoji(name) => render_emoji(&SlackEmojiName(name.to_string()), self),
+            })
+            .collect::<String>();
+        self.sub_texts.push(text);
         visit_slack_block_mark_down_text(self, slack_block_mark_down_text);
     }
 
     fn visit_slack_context_block(&mut self, slack_context_block: &SlackContextBlock) {
-        let mut section_renderer = TextRenderer::new(self.slack_references.clone());
+        let mut section_renderer = self.nested();
         visit_slack_context_block(&mut section_renderer, slack_context_block);
         self.sub_texts.push(section_renderer.sub_texts.join(""));
     }
 
     fn visit_slack_rich_text_block(&mut self, slack_rich_text_block: &SlackRichTextBlock) {
         self.sub_texts.push(render_rich_text_block_as_text(
-            slack_rich_text_block.json_value.clone(),
-            &self.slack_references,
+            &slack_rich_text_block.json_value,
+            self,
         ));
     }
 
@@ -104,8 +286,8 @@ impl Visitor for TextRenderer {
 }
 
 fn render_rich_text_block_as_text(
-    json_value: serde_json::Value,
-    slack_references: &SlackReferences,
+    json_value: &serde_json::Value,
+    renderer: &TextRenderer,
 ) -> String {
     match json_value.get("elements") {
         Some(serde_json::Value::Array(elements)) => elements
@@ -120,22 +302,22 @@ fn render_rich_text_block_as_text(
                         Some(Some("rich_text_section")),
                         None,
                         Some(serde_json::Value::Array(elements)),
-                    ) => render_rich_text_section_elements(elements, slack_references),
+                    ) => render_rich_text_section_elements(elements, renderer),
                     (
                         Some(Some("rich_text_list")),
                         Some(serde_json::Value::String(style)),
                         Some(serde_json::Value::Array(elements)),
-                    ) => render_rich_text_list_elements(elements, style, slack_references),
+                    ) => render_rich_text_list_elements(elements, style, renderer),
                     (
                         Some(Some("rich_text_preformatted")),
                         None,
                         Some(serde_json::Value::Array(elements)),
-                    ) => render_rich_text_preformatted_elements(elements, slack_references),
+                    ) => render_rich_text_preformatted_elements(elements, renderer),
                     (
                         Some(Some("rich_text_quote")),
                         None,
                         Some(serde_json::Value::Array(elements)),
-                    ) => render_rich_text_quote_elements(elements, slack_references),
+                    ) => render_rich_text_quote_elements(elements, renderer),
                     _ => "".to_string(),
                 }
             })
@@ -147,11 +329,11 @@ fn render_rich_text_block_as_text(
 
 fn render_rich_text_section_elements(
     elements: &[serde_json::Value],
-    slack_references: &SlackReferences,
+    renderer: &TextRenderer,
 ) -> String {
     elements
         .iter()
-        .map(|e| render_rich_text_section_element(e, slack_references))
+        .map(|e| render_rich_text_section_element(e, renderer))
         .collect::<Vec<String>>()
         .join("")
 }
@@ -159,17 +341,14 @@ fn render_rich_text_section_elements(
 fn render_rich_text_list_elements(
     elements: &[serde_json::Value],
     style: &str,
-    slack_references: &SlackReferences,
+    renderer: &TextRenderer,
 ) -> String {
     let list_style = if style == "ordered" { "1." } else { "-" };
     elements
         .iter()
         .filter_map(|element| {
             if let Some(serde_json::Value::Array(elements)) = element.get("elements") {
-                Some(render_rich_text_section_elements(
-                    elements,
-                    slack_references,
-                ))
+                Some(render_rich_text_section_elements(elements, renderer))
             } else {
                 None
             }
@@ -181,21 +360,21 @@ fn render_rich_text_list_elements(
 
 fn render_rich_text_preformatted_elements(
     elements: &[serde_json::Value],
-    slack_references: &SlackReferences,
+    renderer: &TextRenderer,
 ) -> String {
-    render_rich_text_section_elements(elements, slack_references)
+    render_rich_text_section_elements(elements, renderer)
 }
 
 fn render_rich_text_quote_elements(
     elements: &[serde_json::Value],
-    slack_references: &SlackReferences,
+    renderer: &TextRenderer,
 ) -> String {
-    render_rich_text_section_elements(elements, slack_references)
+    render_rich_text_section_elements(elements, renderer)
 }
 
 fn render_rich_text_section_element(
     element: &serde_json::Value,
-    slack_references: &SlackReferences,
+    renderer: &TextRenderer,
 ) -> String {
     match element.get("type").map(|t| t.as_str()) {
         Some(Some("text")) => {
@@ -208,64 +387,71 @@ fn render_rich_text_section_element(
             let Some(serde_json::Value::String(channel_id)) = element.get("channel_id") else {
                 return "".to_string();
             };
-            let channel_rendered = if let Some(Some(channel_name)) = slack_references
-                .channels
-                .get(&SlackChannelId(channel_id.clone()))
-            {
-                channel_name
-            } else {
-                channel_id
-            };
-            format!("#{channel_rendered}")
+            let slack_channel_id = SlackChannelId(channel_id.clone());
+            match renderer.slack_references.channels.get(&slack_channel_id) {
+                Some(Some(channel_name)) => format!("#{channel_name}"),
+                _ => mrkdwn_mention_label(element, '#')
+                    .or_else(|| {
+                        renderer
+                            .options
+                            .unresolved_mention_placeholders
+                            .channel(&slack_channel_id)
+                    })
+                    .unwrap_or_default(),
+            }
         }
         Some(Some("user")) => {
             let Some(serde_json::Value::String(user_id)) = element.get("user_id") else {
                 return "".to_string();
             };
-            let user_rendered = if let Some(Some(user_name)) =
-                slack_references.users.get(&SlackUserId(user_id.clone()))
+            let slack_user_id = SlackUserId(user_id.clone());
+            match renderer
+                .slack_references
+                .user_name(&slack_user_id, renderer.options.user_display_policy)
             {
-                user_name
-            } else {
-                user_id
-            };
-            format!("@{user_rendered}")
+                Some(user_name) => format!("@{user_name}"),
+                None => mrkdwn_mention_label(element, '@')
+                    .or_else(|| {
+                        renderer
+                            .options
+                            .unresolved_mention_placeholders
+                            .user(&slack_user_id)
+                    })
+                    .unwrap_or_default(),
+            }
         }
         Some(Some("usergroup")) => {
             let Some(serde_json::Value::String(usergroup_id)) = element.get("usergroup_id") else {
                 return "".to_string();
             };
-            let usergroup_rendered = if let Some(Some(usergroup_name)) = slack_references
+            let slack_usergroup_id = SlackUserGroupId(usergroup_id.clone());
+            match renderer
+                .slack_references
                 .usergroups
-                .get(&SlackUserGroupId(usergroup_id.clone()))
+                .get(&slack_usergroup_id)
             {
-                usergroup_name
-            } else {
-                usergroup_id
+                Some(Some(usergroup_name)) => format!("@{usergroup_name}"),
+                _ => mrkdwn_mention_label(element, '@')
+                    .or_else(|| {
+                        renderer
+                            .options
+                            .unresolved_mention_placeholders
+                            .usergroup(&slack_usergroup_id)
+                    })
+                    .unwrap_or_default(),
+            }
+        }
+        Some(Some("broadcast")) => {
+            let Some(serde_json::Value::String(range)) = element.get("range") else {
+                return "".to_string();
             };
-            format!("@{usergroup_rendered}")
+            format!("@{range}")
         }
         Some(Some("emoji")) => {
             let Some(serde_json::Value::String(name)) = element.get("name") else {
                 return "".to_string();
             };
-            let splitted = name.split("::skin-tone-").collect::<Vec<&str>>();
-            let Some(first) = splitted.first() else {
-                return "".to_string();
-            };
-            let Some(emoji) = emojis::get_by_shortcode(first) else {
-                return "".to_string();
-            };
-            let Some(skin_tone) = splitted.get(1).and_then(|s| s.parse::<usize>().ok()) else {
-                return emoji.to_string();
-            };
-            let Some(mut skin_tones) = emoji.skin_tones() else {
-                return emoji.to_string();
-            };
-            let Some(skinned_emoji) = skin_tones.nth(skin_tone - 1) else {
-                return emoji.to_string();
-            };
-            skinned_emoji.to_string()
+            render_emoji(&SlackEmojiName(name.to_string()), renderer)
         }
         Some(Some("link")) => {
             let Some(serde_json::Value::String(text)) = element.get("text") else {
@@ -273,10 +459,30 @@ fn render_rich_text_section_element(
             };
             text.to_string()
         }
+        Some(Some("date")) => {
+            format_rich_text_date(element, &renderer.options.time).unwrap_or_default()
+        }
         _ => "".to_string(),
     }
 }
 
+/// Render an emoji without markup: the emoji policy only applies to Markdown and HTML, so standard
+/// emoji are rendered as Unicode and other emoji as their `:shortcode:` or description.
+fn render_emoji(emoji_name: &SlackEmojiName, renderer: &TextRenderer) -> String {
+    if let Some(unicode) =
+        emoji::find_standard_emoji_with_aliases(emoji_name, renderer.slack_references)
+    {
+        return unicode.to_string();
+    }
+    let name = &emoji_name.0;
+    if renderer.options.text_emoji_descriptions {
+        let name = name.split("::skin-tone-").next().unwrap_or(name);
+        format!("({})", name.replace(['_', '-'], " "))
+    } else {
+        format!(":{name}:")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -284,7 +490,155 @@ mod tests {
     use url::Url;
 
     use super::*;
-    use crate::test_utils::rich_text_block;
+    use crate::{
+        options::{
+            EmojiCallback, EmojiImageCdn, EmojiPolicy, UnresolvedMentionPlaceholder,
+            UnresolvedMentionPlaceholders, UserDisplayPolicy,
+        },
+        references::SlackUserReference,
+        test_utils::{
+            history_message, message_json_with_unfurl, message_with_attachment, raw_attachments,
+            rich_text_block,
+        },
+    };
+
+    #[test]
+    fn test_attachments_with_pretext_image_and_footer() {
+        assert_eq!(
+            render_attachments_as_text(
+                &raw_attachments(),
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "Build *failed*\napi v1.2.1\n1 check failed\nCI"
+        );
+    }
+
+    #[test]
+    fn test_message_with_text_fallback_and_attachments() {
+        assert_eq!(
+            render_message_as_text(
+                &message_with_attachment(),
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "Deploy *finished*\n\napi v1.2.0\nAll checks passed\nEnvironment: production\nDuration: 3m\n\nBuild log"
+        );
+    }
+
+    #[test]
+    fn test_message_json_with_unfurl() {
+        assert_eq!(
+            render_message_json_as_text(
+                &message_json_with_unfurl(),
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "See https://github.com/dax/slack-blocks-render\n\napi v1.2.0\n\ndax/slack-blocks-render — GitHub"
+        );
+    }
+
+    #[test]
+    fn test_message_json_with_unparsable_block() {
+        let message = serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "Join the call",
+            "blocks": [{ "type": "call", "call_id": "R1" }],
+            "attachments": [{ "title": "api" }]
+        });
+        let (text, report) = render_message_json_as_text_with_report(
+            &message,
+            &SlackReferences::default(),
+            &RenderOptions::default(),
+        );
+        assert_eq!(text, "Join the call\n\napi");
+        assert_eq!(report.unsupported_blocks, vec!["call".to_string()]);
+    }
+
+    #[test]
+    fn test_message_json_with_reactions_without_users() {
+        let message = serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "Ship it?",
+            "reactions": [{ "name": "+1", "count": 3 }]
+        });
+        assert_eq!(
+            render_message_json_as_text(
+                &message,
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "Ship it?\n\n👍 3"
+        );
+    }
+
+    #[test]
+    fn test_message_with_reactions() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "Ship it?",
+            "reactions": [
+                { "name": "+1", "count": 3, "users": ["U1", "U2"] },
+                { "name": "partyparrot", "count": 1, "users": ["U2"] }
+            ]
+        }));
+        assert_eq!(
+            render_message_as_text(
+                &message,
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "Ship it?\n\n👍 3  :partyparrot: 1"
+        );
+    }
+
+    #[test]
+    fn test_message_without_blocks_with_mentions_and_links() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "<@U1> see <https://example.com|the docs> &amp; <#C1> `a &lt; b`"
+        }));
+        let refs = SlackReferences {
+            users: HashMap::from([(SlackUserId("U1".to_string()), Some("alice".to_string()))]),
+            channels: HashMap::from([(SlackChannelId("C1".to_string()), Some("ops".to_string()))]),
+            ..SlackReferences::default()
+        };
+        assert_eq!(
+            render_message_as_text(&message, &refs, &RenderOptions::default()),
+            "@alice see the docs & #ops `a < b`"
+        );
+    }
+
+    #[test]
+    fn test_message_without_blocks_with_unresolved_mention_labels() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "<@U2|bob> <#C2|general> <!subteam^S1|@oncall> <@U3> <#C3|>"
+        }));
+        assert_eq!(
+            render_message_as_text(
+                &message,
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "@bob #general @oncall @U3 #C3"
+        );
+    }
+
+    #[test]
+    fn test_message_with_blocks() {
+        let message = history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "fallback",
+            "blocks": [{ "type": "header", "text": { "type": "plain_text", "text": "Hello" } }]
+        }));
+        assert!(!render_message_as_text(
+            &message,
+            &SlackReferences::default(),
+            &RenderOptions::default()
+        )
+        .contains("fallback"));
+    }
 
     #[test]
     fn test_empty_input() {
@@ -498,6 +852,80 @@ Video description"#
                 "Text1Text11Text12Text2Text21Text22".to_string()
             );
         }
+
+        #[test]
+        fn test_with_emoji() {
+            let blocks = vec![SlackBlock::Section(SlackSectionBlock::new().with_text(
+                SlackBlockText::MarkDown(SlackBlockMarkDownText::new(
+                    "Shipped :rocket: :party_parrot: at 10:30".to_string(),
+                )),
+            ))];
+            assert_eq!(
+                render_blocks_as_text_with_report(
+                    &blocks,
+                    &SlackReferences::default(),
+                    &RenderOptions::default()
+                ),
+                (
+                    "Shipped 🚀 :party_parrot: at 10:30".to_string(),
+                    RenderReport {
+                        unresolved_references: SlackReferences {
+                            emojis: HashMap::from([(
+                                SlackEmojiName("party_parrot".to_string()),
+                                None
+                            )]),
+                            ..SlackReferences::default()
+                        },
+                        ..RenderReport::default()
+                    }
+                )
+            );
+            assert_eq!(
+                render_blocks_as_text_with_options(
+                    &blocks,
+                    &SlackReferences::default(),
+                    &RenderOptions {
+                        text_emoji_descriptions: true,
+                        ..RenderOptions::default()
+                    }
+                ),
+                "Shipped 🚀 (party parrot) at 10:30".to_string()
+            );
+        }
+
+        #[test]
+        fn test_with_emoji_with_skin_tones() {
+            let blocks = vec![SlackBlock::Section(
+                SlackSectionBlock::new()
+                    .with_text(SlackBlockText::MarkDown(SlackBlockMarkDownText::new(
+                    ":+1::skin-tone-3: :man-facepalming::skin-tone-2: :partyparrot::skin-tone-4:"
+                        .to_string(),
+                ))),
+            )];
+            assert_eq!(
+                render_blocks_as_text(blocks, SlackReferences::default()),
+                "👍🏼 🤦🏻‍♂️ :partyparrot::skin-tone-4:".to_string()
+            );
+        }
+
+        #[test]
+        fn test_with_date_tokens() {
+            let blocks = vec![SlackBlock::Section(SlackSectionBlock::new().with_text(
+                SlackBlockText::MarkDown(SlackBlockMarkDownText::new(
+                    "Posted <!date^1392734382^{date_short} at {time}^https://example.com/|Feb 18>, \
+                    <!date^1392734382^{unknown}|fallback> <!date^oops>"
+                        .to_string(),
+                )),
+            ))];
+            assert_eq!(
+                render_blocks_as_text_with_options(
+                    &blocks,
+                    &SlackReferences::default(),
+                    &RenderOptions::default()
+                ),
+                "Posted Feb 18, 2014 at 14:39, {unknown} <!date^oops>".to_string()
+            );
+        }
     }
 
     mod context {
@@ -885,6 +1313,103 @@ Video description"#
                         "@John Doe".to_string()
                     );
                 }
+
+                #[test]
+                fn test_with_broadcast() {
+                    let blocks = vec![rich_text_block(serde_json::json!({
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    { "type": "broadcast", "range": "everyone" }
+                                ]
+                            }
+                        ]
+                    }))];
+                    assert_eq!(
+                        render_blocks_as_text(blocks, SlackReferences::default()),
+                        "@everyone".to_string()
+                    );
+                }
+
+                #[test]
+                fn test_with_unresolved_mention_placeholders() {
+                    let blocks = vec![rich_text_block(serde_json::json!({
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    { "type": "text", "text": "Hi " },
+                                    { "type": "user", "user_id": "U123" },
+                                    { "type": "text", "text": " in " },
+                                    { "type": "channel", "channel_id": "C123" },
+                                    { "type": "usergroup", "usergroup_id": "S123" }
+                                ]
+                            }
+                        ]
+                    }))];
+                    let options = RenderOptions {
+                        unresolved_mention_placeholders: UnresolvedMentionPlaceholders {
+                            user: UnresolvedMentionPlaceholder::Label("unknown user".to_string()),
+                            channel: UnresolvedMentionPlaceholder::SlackToken,
+                            usergroup: UnresolvedMentionPlaceholder::Omit,
+                        },
+                        ..RenderOptions::default()
+                    };
+                    assert_eq!(
+                        render_blocks_as_text_with_options(
+                            &blocks,
+                            &SlackReferences::default(),
+                            &options
+                        ),
+                        "Hi unknown user in <#C123>".to_string()
+                    );
+                }
+
+                #[test]
+                fn test_with_user_id_and_user_profile_and_real_name_policy() {
+                    let blocks = vec![rich_text_block(serde_json::json!({
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    {
+                                        "type": "user",
+                                        "user_id": "user1"
+                                    }
+                                ]
+                            }
+                        ]
+                    }))];
+                    assert_eq!(
+                        render_blocks_as_text_with_options(
+                            &blocks,
+                            &SlackReferences {
+                                users: HashMap::from([(
+                                    SlackUserId("user1".to_string()),
+                                    Some("jdoe".to_string())
+                                )]),
+                                user_profiles: HashMap::from([(
+                                    SlackUserId("user1".to_string()),
+                                    SlackUserReference {
+                                        display_name: Some("johnny".to_string()),
+                                        real_name: Some("John Doe".to_string()),
+                                        ..SlackUserReference::default()
+                                    }
+                                )]),
+                                ..SlackReferences::default()
+                            },
+                            &RenderOptions {
+                                user_display_policy: UserDisplayPolicy::RealName,
+                                ..RenderOptions::default()
+                            }
+                        ),
+                        "@John Doe".to_string()
+                    );
+                }
             }
 
             mod usergroup_element {
@@ -1058,10 +1583,144 @@ Video description"#
                     }))];
                     assert_eq!(
                         render_blocks_as_text(blocks, SlackReferences::default()),
-                        "".to_string()
+                        ":bbb:".to_string()
+                    );
+                }
+
+                #[test]
+                fn test_with_custom_emoji_and_alias() {
+                    let blocks = vec![rich_text_block(serde_json::json!({
+                        "type": "rich_text",
+                        "elements": [
+                            {
+                                "type": "rich_text_section",
+                                "elements": [
+                                    { "type": "emoji", "name": "party_parrot" },
+                                    { "type": "text", "text": " shipped! " },
+                                    { "type": "emoji", "name": "yes" }
+                                ]
+                            }
+                        ]
+                    }))];
+                    let slack_references = SlackReferences {
+                        emojis: HashMap::from([
+                            (
+                                SlackEmojiName("party_parrot".to_string()),
+                                Some(SlackEmojiRef::Url(
+                                    "https://emoji.com/party_parrot.gif".parse().unwrap(),
+                                )),
+                            ),
+                            (
+                                SlackEmojiName("yes".to_string()),
+                                Some(SlackEmojiRef::Alias(SlackEmojiName("+1".to_string()))),
+                            ),
+                        ]),
+                        ..SlackReferences::default()
+                    };
+                    assert_eq!(
+                        render_blocks_as_text_with_options(
+                            &blocks,
+                            &slack_references,
+                            &RenderOptions::default()
+                        ),
+                        ":party_parrot: shipped! 👍".to_string()
+                    );
+                    assert_eq!(
+                        render_blocks_as_text_with_options(
+                            &blocks,
+                            &slack_references,
+                            &RenderOptions {
+                                text_emoji_descriptions: true,
+                                ..RenderOptions::default()
+                            }
+                        ),
+                        "(party parrot) shipped! 👍".to_string()
                     );
                 }
             }
+
+            #[test]
+            fn test_emoji_ignore_the_emoji_policy() {
+                let blocks = vec![rich_text_block(serde_json::json!({
+                    "type": "rich_text",
+                    "elements": [
+                        {
+                            "type": "rich_text_section",
+                            "elements": [
+                                { "type": "emoji", "name": "rocket" },
+                                { "type": "text", "text": " " },
+                                { "type": "emoji", "name": "party_parrot" }
+                            ]
+                        }
+                    ]
+                }))];
+                let slack_references = SlackReferences {
+                    emojis: HashMap::from([(
+                        SlackEmojiName("party_parrot".to_string()),
+                        Some(SlackEmojiRef::Url(
+                            "https://emoji.com/party_parrot.gif".parse().unwrap(),
+                        )),
+                    )]),
+                    ..SlackReferences::default()
+                };
+                for emoji_policy in [
+                    EmojiPolicy::Image(EmojiImageCdn::twemoji()),
+                    EmojiPolicy::Custom(EmojiCallback::new(|name, _| {
+                        Some(format!("<img alt=\"{}\">", name.0))
+                    })),
+                ] {
+                    assert_eq!(
+                        render_blocks_as_text_with_options(
+                            &blocks,
+                            &slack_references,
+                            &RenderOptions {
+                                emoji_policy,
+                                ..RenderOptions::default()
+                            }
+                        ),
+                        "🚀 :party_parrot:".to_string()
+                    );
+                }
+            }
+
+            #[test]
+            fn test_with_date() {
+                let blocks = vec![rich_text_block(serde_json::json!({
+                    "type": "rich_text",
+                    "elements": [
+                        {
+                            "type": "rich_text_section",
+                            "elements": [
+                                { "type": "text", "text": "Due " },
+                                {
+                                    "type": "date",
+                                    "timestamp": 1392734382,
+                                    "format": "{date_short} at {time}",
+                                    "style": { "bold": true }
+                                },
+                                { "type": "text", "text": " (" },
+                                { "type": "date", "timestamp": 1392734382, "format": "{date_num}" },
+                                { "type": "text", "text": ")" }
+                            ]
+                        }
+                    ]
+                }))];
+                let options = RenderOptions {
+                    time: crate::time::TimeOptions {
+                        timezone: chrono_tz::Europe::Paris,
+                        ..crate::time::TimeOptions::default()
+                    },
+                    ..RenderOptions::default()
+                };
+                assert_eq!(
+                    render_blocks_as_text_with_options(
+                        &blocks,
+                        &SlackReferences::default(),
+                        &options
+                    ),
+                    "Due Feb 18, 2014 at 15:39 (2014-02-18)".to_string()
+                );
+            }
         }
 
         mod rich_text_list {
@@ -0,0 +1,512 @@
+use std::{fmt, sync::Arc};
+
+use chrono::{DateTime, Datelike, Utc};
+use chrono_tz::Tz;
+use slack_morphism::prelude::*;
+
+/// Formatting of message times, rich text `date` elements and mrkdwn `<!date^...>` tokens.
+#[derive(PartialEq, Debug, Clone)]
+pub struct TimeOptions {
+    /// Timezone of the rendered times, with its daylight saving time changes.
+    pub timezone: Tz,
+    pub locale: TimeLocale,
+    /// Render recent dates relative to now, like `yesterday at 14:02`.
+    pub relative: bool,
+    /// Current time used by relative dates, the system clock by default.
+    pub clock: Clock,
+}
+
+impl Default for TimeOptions {
+    fn default() -> Self {
+        TimeOptions {
+            timezone: Tz::UTC,
+            locale: TimeLocale::default(),
+            relative: false,
+            clock: Clock::system(),
+        }
+    }
+}
+
+/// Language and conventions of rendered dates and times.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum TimeLocale {
+    /// English with a 24-hour clock: `February 18th, 2014`, `14:02`.
+    #[default]
+    English,
+    /// American English with a 12-hour clock: `February 18th, 2014`, `2:02 PM`.
+    AmericanEnglish,
+    /// French: `18 février 2014`, `14:02`.
+    French,
+}
+
+/// Source of the current time.
+#[derive(Clone, Default)]
+pub enum Clock {
+    /// The system clock.
+    #[default]
+    System,
+    /// Clock always returning the same time, for deterministic renderings.
+    Fixed(DateTime<Utc>),
+    Custom(Arc<ClockFn>),
+}
+
+type ClockFn = dyn Fn() -> DateTime<Utc> + Send + Sync;
+
+impl Clock {
+    pub fn new(now: impl Fn() -> DateTime<Utc> + Send + Sync + 'static) -> Self {
+        Clock::Custom(Arc::new(now))
+    }
+
+    pub fn system() -> Self {
+        Clock::System
+    }
+
+    /// Clock always returning `now`, for deterministic renderings.
+    pub fn fixed(now: DateTime<Utc>) -> Self {
+        Clock::Fixed(now)
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        match self {
+            Clock::System => Utc::now(),
+            Clock::Fixed(now) => *now,
+            Clock::Custom(now) => now(),
+        }
+    }
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Clock::System => f.write_str("System"),
+            Clock::Fixed(now) => f.debug_tuple("Fixed").field(now).finish(),
+            Clock::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+/// Custom clocks are only equal to their clones.
+impl PartialEq for Clock {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Clock::System, Clock::System) => true,
+            (Clock::Fixed(now), Clock::Fixed(other_now)) => now == other_now,
+            (Clock::Custom(now), Clock::Custom(other_now)) => {
+                std::ptr::addr_eq(Arc::as_ptr(now), Arc::as_ptr(other_now))
+            }
+            _ => false,
+        }
+    }
+}
+
+struct LocaleData {
+    months: [&'static str; 12],
+    short_months: [&'static str; 12],
+    weekdays: [&'static str; 7],
+    today: &'static str,
+    yesterday: &'static str,
+    tomorrow: &'static str,
+    at: &'static str,
+}
+
+const ENGLISH: LocaleData = LocaleData {
+    months: [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ],
+    short_months: [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ],
+    weekdays: [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ],
+    today: "today",
+    yesterday: "yesterday",
+    tomorrow: "tomorrow",
+    at: "at",
+};
+
+const FRENCH: LocaleData = LocaleData {
+    months: [
+        "janvier",
+        "février",
+        "mars",
+        "avril",
+        "mai",
+        "juin",
+        "juillet",
+        "août",
+        "septembre",
+        "octobre",
+        "novembre",
+        "décembre",
+    ],
+    short_months: [
+        "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.",
+        "déc.",
+    ],
+    weekdays: [
+        "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+    ],
+    today: "aujourd'hui",
+    yesterday: "hier",
+    tomorrow: "demain",
+    at: "à",
+};
+
+impl TimeLocale {
+    fn data(self) -> &'static LocaleData {
+        match self {
+            TimeLocale::English | TimeLocale::AmericanEnglish => &ENGLISH,
+            TimeLocale::French => &FRENCH,
+        }
+    }
+
+    fn format_date(self, date_time: &DateTime<Tz>, short: bool, long: bool) -> String {
+        let data = self.data();
+        let month = date_time.month0() as usize;
+        let day = date_time.day();
+        let year = date_time.year();
+        let weekday = data.weekdays[date_time.weekday().num_days_from_monday() as usize];
+        match self {
+            TimeLocale::English | TimeLocale::AmericanEnglish if short => {
+                format!("{} {day}, {year}", data.short_months[month])
+            }
+            TimeLocale::English | TimeLocale::AmericanEnglish => {
+                let date = format!("{} {day}{}, {year}", data.months[month], ordinal(day));
+                if long {
+                    format!("{weekday}, {date}")
+                } else {
+                    date
+                }
+            }
+            TimeLocale::French => {
+                let day = if day == 1 {
+                    "1er".to_string()
+                } else {
+                    day.to_string()
+                };
+                if short {
+                    format!("{day} {} {year}", data.short_months[month])
+                } else if long {
+                    format!("{weekday} {day} {} {year}", data.months[month])
+                } else {
+                    format!("{day} {} {year}", data.months[month])
+                }
+            }
+        }
+    }
+
+    fn format_time(self, date_time: &DateTime<Tz>, seconds: bool) -> String {
+        match (self, seconds) {
+            (TimeLocale::AmericanEnglish, false) => date_time.format("%-I:%M %p").to_string(),
+            (TimeLocale::AmericanEnglish, true) => date_time.format("%-I:%M:%S %p").to_string(),
+            (_, false) => date_time.format("%H:%M").to_string(),
+            (_, true) => date_time.format("%H:%M:%S").to_string(),
+        }
+    }
+
+    fn format_ago(self, seconds: i64) -> String {
+        let past = seconds >= 0;
+        let seconds = seconds.unsigned_abs();
+        let (count, unit) = match seconds {
+            0..60 => {
+                return match self {
+                    TimeLocale::French => "à l'instant".to_string(),
+                    _ => "just now".to_string(),
+                }
+            }
+            60..3600 => (seconds / 60, 0),
+            3600..86400 => (seconds / 3600, 1),
+            86400..2_592_000 => (seconds / 86400, 2),
+            2_592_000..31_536_000 => (seconds / 2_592_000, 3),
+            _ => (seconds / 31_536_000, 4),
+        };
+        let duration = match self {
+            TimeLocale::French => {
+                let unit = ["minute", "heure", "jour", "mois", "an"][unit];
+                let plural = if count > 1 && unit != "mois" { "s" } else { "" };
+                format!("{count} {unit}{plural}")
+            }
+            _ => {
+                let unit = ["minute", "hour", "day", "month", "year"][unit];
+                let plural = if count > 1 { "s" } else { "" };
+                format!("{count} {unit}{plural}")
+            }
+        };
+        match (self, past) {
+            (TimeLocale::French, true) => format!("il y a {duration}"),
+            (TimeLocale::French, false) => format!("dans {duration}"),
+            (_, true) => format!("{duration} ago"),
+            (_, false) => format!("in {duration}"),
+        }
+    }
+}
+
+fn ordinal(day: u32) -> &'static str {
+    match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// Format a Unix timestamp with the tokens of Slack date formatting: `{date_num}`, `{date}`,
+/// `{date_short}`, `{date_long}`, their `_pretty` variants showing `today`, `yesterday` and
+/// `tomorrow`, `{time}`, `{time_secs}` and `{ago}`. Other text is kept as is.
+///
+/// Returns `None` if the timestamp is out of range.
+pub fn format_slack_date(timestamp: i64, format: &str, options: &TimeOptions) -> Option<String> {
+    let date_time = DateTime::from_timestamp(timestamp, 0)?.with_timezone(&options.timezone);
+    let now = options.clock.now();
+    let locale = options.locale;
+    let data = locale.data();
+    let relative_day = match (date_time.date_naive()
+        - now.with_timezone(&options.timezone).date_naive())
+    .num_days()
+    {
+        0 => Some(data.today),
+        -1 => Some(data.yesterday),
+        1 => Some(data.tomorrow),
+        _ => None,
+    };
+
+    let mut output = String::new();
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let token = &rest[start + 1..start + end];
+        let formatted = match token {
+            "date_num" => Some(date_time.format("%Y-%m-%d").to_string()),
+            "date" => Some(locale.format_date(&date_time, false, false)),
+            "date_short" => Some(locale.format_date(&date_time, true, false)),
+            "date_long" => Some(locale.format_date(&date_time, false, true)),
+            "date_pretty" | "date_short_pretty" | "date_long_pretty" => {
+                Some(relative_day.map(str::to_string).unwrap_or_else(|| {
+                    locale.format_date(
+                        &date_time,
+                        token == "date_short_pretty",
+                        token == "date_long_pretty",
+                    )
+                }))
+            }
+            "time" => Some(locale.format_time(&date_time, false)),
+            "time_secs" => Some(locale.format_time(&date_time, true)),
+            "ago" => Some(locale.format_ago((now - date_time.to_utc()).num_seconds())),
+            _ => None,
+        };
+        match formatted {
+            Some(formatted) => output.push_str(&formatted),
+            None => output.push_str(&rest[start..start + end + 1]),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+    Some(output)
+}
+
+/// Seconds of a Slack message timestamp (`seconds.micros`).
+pub(crate) fn ts_seconds(ts: &SlackTs) -> Option<i64> {
+    ts.0.split('.').next()?.parse().ok()
+}
+
+/// Date and time of a message, like `Feb 18, 2014 at 14:02`, or `yesterday at 14:02` with
+/// relative dates.
+pub(crate) fn format_date_time(ts: &SlackTs, options: &TimeOptions) -> Option<String> {
+    let at = options.locale.data().at;
+    let format = if options.relative {
+        format!("{{date_short_pretty}} {at} {{time}}")
+    } else {
+        format!("{{date_short}} {at} {{time}}")
+    };
+    format_slack_date(ts_seconds(ts)?, &format, options)
+}
+
+/// Day of a message, like `2014-02-18`, or `Yesterday` with relative dates.
+pub(crate) fn format_day(ts: &SlackTs, options: &TimeOptions) -> Option<String> {
+    let format = if options.relative {
+        "{date_long_pretty}"
+    } else {
+        "{date_num}"
+    };
+    let day = format_slack_date(ts_seconds(ts)?, format, options)?;
+    let mut chars = day.chars();
+    let first = chars.next()?;
+    Some(first.to_uppercase().chain(chars).collect())
+}
+
+/// Time of a message, like `14:02`.
+pub(crate) fn format_time(ts: &SlackTs, options: &TimeOptions) -> Option<String> {
+    format_slack_date(ts_seconds(ts)?, "{time}", options)
+}
+
+/// RFC 3339 date and time of a Unix timestamp in the configured timezone, for HTML `datetime`
+/// attributes.
+pub(crate) fn format_rfc3339(timestamp: i64, options: &TimeOptions) -> Option<String> {
+    let date_time = DateTime::from_timestamp(timestamp, 0)?.with_timezone(&options.timezone);
+    Some(date_time.to_rfc3339())
+}
+
+/// Date formatted from a mrkdwn `<!date^timestamp^format^optional_link|fallback>` token
+/// (without its angle brackets), and its optional link.
+pub(crate) fn format_slack_date_token(
+    token: &str,
+    options: &TimeOptions,
+) -> Option<(String, Option<String>)> {
+    let token = token.strip_prefix("!date^")?;
+    let (token, fallback) = match token.split_once('|') {
+        Some((token, fallback)) => (token, Some(fallback)),
+        None => (token, None),
+    };
+    let mut parts = token.splitn(3, '^');
+    let formatted = parts
+        .next()
+        .and_then(|timestamp| timestamp.parse().ok())
+        .zip(parts.next())
+        .and_then(|(timestamp, format)| format_slack_date(timestamp, format, options));
+    let link = parts.next().map(|link| link.to_string());
+    Some((
+        formatted.or(fallback.map(|fallback| fallback.to_string()))?,
+        link,
+    ))
+}
+
+/// Date formatted from a rich text `date` element, or its fallback text.
+pub(crate) fn format_rich_text_date(
+    element: &serde_json::Value,
+    options: &TimeOptions,
+) -> Option<String> {
+    element
+        .get("timestamp")
+        .and_then(|timestamp| timestamp.as_i64())
+        .zip(element.get("format").and_then(|format| format.as_str()))
+        .and_then(|(timestamp, format)| format_slack_date(timestamp, format, options))
+        .or_else(|| {
+            element
+                .get("fallback")
+                .and_then(|fallback| fallback.as_str())
+                .map(|fallback| fallback.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2014-02-18T14:39:42Z, a Tuesday
+    const TIMESTAMP: i64 = 1392734382;
+
+    fn options(locale: TimeLocale) -> TimeOptions {
+        TimeOptions {
+            locale,
+            clock: Clock::fixed(DateTime::from_timestamp(TIMESTAMP + 86400 + 600, 0).unwrap()),
+            ..TimeOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_format_slack_date_tokens() {
+        let options = options(TimeLocale::English);
+        let format = |format: &str| format_slack_date(TIMESTAMP, format, &options).unwrap();
+        assert_eq!(format("{date_num}"), "2014-02-18");
+        assert_eq!(format("{date}"), "February 18th, 2014");
+        assert_eq!(format("{date_short} at {time}"), "Feb 18, 2014 at 14:39");
+        assert_eq!(format("{date_long}"), "Tuesday, February 18th, 2014");
+        assert_eq!(
+            format("{date_pretty} at {time_secs}"),
+            "yesterday at 14:39:42"
+        );
+        assert_eq!(format("{ago}"), "1 day ago");
+        assert_eq!(format("{unknown} {date_num"), "{unknown} {date_num");
+    }
+
+    #[test]
+    fn test_format_slack_date_locales_and_timezone() {
+        let american = options(TimeLocale::AmericanEnglish);
+        assert_eq!(
+            format_slack_date(TIMESTAMP, "{date_short} {time}", &american).unwrap(),
+            "Feb 18, 2014 2:39 PM"
+        );
+        let french = TimeOptions {
+            timezone: chrono_tz::Europe::Paris,
+            ..options(TimeLocale::French)
+        };
+        assert_eq!(
+            format_slack_date(TIMESTAMP, "{date_long} à {time}, {ago}", &french).unwrap(),
+            "mardi 18 février 2014 à 15:39, il y a 1 jour"
+        );
+    }
+
+    #[test]
+    fn test_format_slack_date_with_daylight_saving_time() {
+        let options = TimeOptions {
+            timezone: chrono_tz::Europe::Paris,
+            ..options(TimeLocale::English)
+        };
+        assert_eq!(
+            format_slack_date(TIMESTAMP, "{time}", &options).unwrap(),
+            "15:39"
+        );
+        // 2014-07-18T14:39:42Z
+        assert_eq!(
+            format_slack_date(1405694382, "{time}", &options).unwrap(),
+            "16:39"
+        );
+    }
+
+    #[test]
+    fn test_clock_equality() {
+        assert_eq!(
+            crate::options::RenderOptions::default(),
+            crate::options::RenderOptions::default()
+        );
+        let now = DateTime::from_timestamp(TIMESTAMP, 0).unwrap();
+        assert_eq!(Clock::fixed(now), Clock::fixed(now));
+        assert_ne!(Clock::fixed(now), Clock::system());
+        let clock = Clock::new(move || now);
+        assert_eq!(clock, clock.clone());
+        assert_ne!(clock, Clock::new(move || now));
+    }
+
+    #[test]
+    fn test_format_message_times() {
+        let ts = SlackTs(format!("{TIMESTAMP}.000100"));
+        let absolute = options(TimeLocale::English);
+        let relative = TimeOptions {
+            relative: true,
+            ..absolute.clone()
+        };
+        assert_eq!(
+            format_date_time(&ts, &absolute),
+            Some("Feb 18, 2014 at 14:39".to_string())
+        );
+        assert_eq!(
+            format_date_time(&ts, &relative),
+            Some("yesterday at 14:39".to_string())
+        );
+        assert_eq!(format_day(&ts, &absolute), Some("2014-02-18".to_string()));
+        assert_eq!(format_day(&ts, &relative), Some("Yesterday".to_string()));
+        assert_eq!(format_time(&ts, &absolute), Some("14:39".to_string()));
+    }
+}
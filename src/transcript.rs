@@ -0,0 +1,756 @@
+use std::collections::HashMap;
+
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use slack_morphism::prelude::*;
+
+use crate::{
+    html::{class_attribute, render_link_html, render_message_as_html},
+    markdown::{escape_markdown, render_message_as_markdown},
+    options::RenderOptions,
+    references::SlackReferences,
+    text::render_message_as_text,
+    time::{format_date_time, format_day, format_rfc3339, format_time, ts_seconds},
+};
+
+/// Labels of the headers of transcripts, in English by default.
+///
+/// `{time}` is replaced by the time of a thread, and `{count}` by a number of replies.
+#[derive(PartialEq, Debug, Clone)]
+pub struct TranscriptLabels {
+    /// Marker of edited messages, followed by the time of the edit when known.
+    pub edited: String,
+    /// Marker of replies rendered as top-level messages, as their parent message is not in the
+    /// transcript.
+    pub reply_to_thread: String,
+    /// Number of replies of a thread that are not in the transcript, when none of them is.
+    pub replies: String,
+    /// Same as `replies` for a single reply.
+    pub one_reply: String,
+    /// Number of replies of a thread that are not in the transcript, when some of them are.
+    pub more_replies: String,
+    /// Same as `more_replies` for a single reply.
+    pub one_more_reply: String,
+    /// Author of messages without a user, a username or a bot.
+    pub unknown_author: String,
+}
+
+impl Default for TranscriptLabels {
+    fn default() -> Self {
+        TranscriptLabels {
+            edited: "edited".to_string(),
+            reply_to_thread: "reply to a thread from {time}".to_string(),
+            replies: "{count} replies".to_string(),
+            one_reply: "{count} reply".to_string(),
+            more_replies: "{count} more replies".to_string(),
+            one_more_reply: "{count} more reply".to_string(),
+            unknown_author: "unknown".to_string(),
+        }
+    }
+}
+
+/// Top-level message and the replies of its thread found in the transcript.
+struct Thread<'a> {
+    message: &'a SlackHistoryMessage,
+    replies: Vec<&'a SlackHistoryMessage>,
+}
+
+/// Render messages of a conversation as a Markdown transcript: grouped by day, with their
+/// author, time and edited marker, and with thread replies quoted under their parent message.
+/// The labels of the headers are set by `options.transcript_labels`.
+///
+/// References of all the messages can be found and resolved at once with
+/// `resolve_slack_references_in_messages`.
+pub fn render_transcript_as_markdown(
+    messages: &[SlackHistoryMessage],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let mut parts = vec![];
+    let mut current_day = None;
+    for thread in build_threads(messages) {
+        let day = message_day(thread.message, options);
+        if day != current_day {
+            if let Some(day) = &day {
+                parts.push(format!("## {day}"));
+            }
+            current_day = day;
+        }
+        parts.push(render_transcript_message_as_markdown(
+            thread.message,
+            orphan_thread_ts(thread.message),
+            slack_references,
+            options,
+        ));
+        for reply in &thread.replies {
+            parts.push(
+                render_transcript_message_as_markdown(reply, None, slack_references, options)
+                    .lines()
+                    .map(|line| format!("> {line}").trim_end().to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        }
+        if let Some(replies) = missing_replies(&thread, options) {
+            parts.push(format!("_{}_", escape_markdown(&replies)));
+        }
+    }
+    parts.join("\n\n")
+}
+
+/// Render messages of a conversation as an HTML transcript: grouped by day, with their
+/// author, time and edited marker, and with thread replies nested under their parent message.
+/// The labels of the headers are set by `options.transcript_labels`.
+pub fn render_transcript_as_html(
+    messages: &[SlackHistoryMessage],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let mut html = String::new();
+    let mut current_day = None;
+    for thread in build_threads(messages) {
+        let day = message_day(thread.message, options);
+        if day != current_day {
+            if let Some(day) = &day {
+                html.push_str(&format!("<h2>{day}</h2>\n"));
+            }
+            current_day = day;
+        }
+        let mut thread_html = String::new();
+        for reply in &thread.replies {
+            thread_html.push_str(&render_transcript_message_as_html(
+                reply,
+                None,
+                "",
+                slack_references,
+                options,
+            ));
+        }
+        if let Some(replies) = missing_replies(&thread, options) {
+            thread_html.push_str(&format!(
+                "<p{}>{}</p>\n",
+                class_attribute("slack-replies", options),
+                encode_text(&replies)
+            ));
+        }
+        if !thread_html.is_empty() {
+            // Matrix HTML has no classes: the replies are quoted instead
+            thread_html = if options.matrix.is_some() {
+                format!("<blockquote>\n{thread_html}</blockquote>\n")
+            } else {
+                format!("<div class=\"slack-thread\">\n{thread_html}</div>\n")
+            };
+        }
+        html.push_str(&render_transcript_message_as_html(
+            thread.message,
+            orphan_thread_ts(thread.message),
+            &thread_html,
+            slack_references,
+            options,
+        ));
+    }
+    html
+}
+
+/// Render messages of a conversation as a plain text transcript: grouped by day, with their
+/// author, time and edited marker, and with thread replies indented under their parent message.
+/// The labels of the headers are set by `options.transcript_labels`.
+pub fn render_transcript_as_text(
+    messages: &[SlackHistoryMessage],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let mut parts = vec![];
+    let mut current_day = None;
+    for thread in build_threads(messages) {
+        let day = message_day(thread.message, options);
+        if day != current_day {
+            if let Some(day) = &day {
+                parts.push(day.clone());
+            }
+            current_day = day;
+        }
+        parts.push(render_transcript_message_as_text(
+            thread.message,
+            orphan_thread_ts(thread.message),
+            slack_references,
+            options,
+        ));
+        for reply in &thread.replies {
+            parts.push(
+                render_transcript_message_as_text(reply, None, slack_references, options)
+                    .lines()
+                    .map(|line| format!("    {line}").trim_end().to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        }
+        if let Some(replies) = missing_replies(&thread, options) {
+            parts.push(format!("    {replies}"));
+        }
+    }
+    parts.join("\n\n")
+}
+
+fn render_transcript_message_as_markdown(
+    message: &SlackHistoryMessage,
+    orphan_thread_ts: Option<&SlackTs>,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let labels = &options.transcript_labels;
+    let mut header = format!(
+        "*{}*",
+        escape_markdown(&author_name(message, slack_references, options))
+    );
+    if let Some(time) = message_time(message, options) {
+        match message_permalink(message, options) {
+            Some(permalink) => header.push_str(&format!(" [{time}]({permalink})")),
+            None => header.push_str(&format!(" {time}")),
+        }
+    }
+    if let Some(edited) = &message.edited {
+        match format_date_time(&edited.ts, &options.time) {
+            Some(edited_time) => header.push_str(&format!(
+                " _({} {edited_time})_",
+                escape_markdown(&labels.edited)
+            )),
+            None => header.push_str(&format!(" _({})_", escape_markdown(&labels.edited))),
+        }
+    }
+    if let Some(thread_time) = orphan_thread_ts.and_then(|ts| format_date_time(ts, &options.time)) {
+        let thread_time = match thread_permalink(message, options) {
+            Some(permalink) => format!("[{thread_time}]({permalink})"),
+            None => thread_time,
+        };
+        header.push_str(&format!(
+            " _({})_",
+            escape_markdown(&labels.reply_to_thread).replace("{time}", &thread_time)
+        ));
+    }
+    let body = render_message_as_markdown(message, slack_references, options);
+    if body.is_empty() {
+        header
+    } else {
+        format!("{header}\n{body}")
+    }
+}
+
+fn render_transcript_message_as_html(
+    message: &SlackHistoryMessage,
+    orphan_thread_ts: Option<&SlackTs>,
+    thread_html: &str,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let labels = &options.transcript_labels;
+    let mut header = format!(
+        "<strong{}>{}</strong>",
+        class_attribute("slack-author", options),
+        encode_text(&author_name(message, slack_references, options))
+    );
+    if let Some(time) = message_time(message, options) {
+        // Matrix HTML has no `<time>` element
+        let date_time = ts_seconds(&message.origin.ts)
+            .filter(|_| options.matrix.is_none())
+            .and_then(|seconds| format_rfc3339(seconds, &options.time));
+        let time = match date_time {
+            Some(date_time) => format!(
+                "<time datetime=\"{}\">{time}</time>",
+                encode_double_quoted_attribute(&date_time)
+            ),
+            None => time,
+        };
+        match message_permalink(message, options) {
+            Some(permalink) if options.matrix.is_some() => {
+                header.push_str(&format!(" {}", render_link_html(&permalink, &time, options)))
+            }
+            Some(permalink) => header.push_str(&format!(
+                " <a class=\"slack-permalink\" target=\"_blank\" rel=\"noopener noreferrer\" href=\"{}\">{time}</a>",
+                encode_double_quoted_attribute(&permalink)
+            )),
+            None => header.push_str(&format!(" {time}")),
+        }
+    }
+    if let Some(edited) = &message.edited {
+        match format_date_time(&edited.ts, &options.time) {
+            // Matrix HTML has no tooltips: the time of the edit is shown instead
+            Some(edited_time) if options.matrix.is_some() => header.push_str(&format!(
+                " <em>({} {})</em>",
+                encode_text(&labels.edited),
+                encode_text(&edited_time)
+            )),
+            Some(edited_time) => header.push_str(&format!(
+                " <span class=\"slack-edited\" title=\"{}\">({})</span>",
+                encode_double_quoted_attribute(&format!(
+                    "{} {edited_time}",
+                    capitalize(&labels.edited)
+                )),
+                encode_text(&labels.edited)
+            )),
+            None if options.matrix.is_some() => {
+                header.push_str(&format!(" <em>({})</em>", encode_text(&labels.edited)))
+            }
+            None => header.push_str(&format!(
+                " <span class=\"slack-edited\">({})</span>",
+                encode_text(&labels.edited)
+            )),
+        }
+    }
+    if let Some(thread_time) = orphan_thread_ts.and_then(|ts| format_date_time(ts, &options.time)) {
+        let thread_time = match thread_permalink(message, options) {
+            Some(permalink) => render_link_html(&permalink, &encode_text(&thread_time), options),
+            None => encode_text(&thread_time).to_string(),
+        };
+        let reply_to_thread = encode_text(&labels.reply_to_thread).replace("{time}", &thread_time);
+        if options.matrix.is_some() {
+            header.push_str(&format!(" <em>({reply_to_thread})</em>"));
+        } else {
+            header.push_str(&format!(
+                " <span class=\"slack-thread-reply\">({reply_to_thread})</span>"
+            ));
+        }
+    }
+    let body = render_message_as_html(message, slack_references, options);
+    if options.matrix.is_some() {
+        return format!("<div>\n<p>{header}</p>\n{body}{thread_html}</div>\n");
+    }
+    format!(
+        "<div class=\"slack-message\">\n<p class=\"slack-message-header\">{header}</p>\n{body}{thread_html}</div>\n"
+    )
+}
+
+fn render_transcript_message_as_text(
+    message: &SlackHistoryMessage,
+    orphan_thread_ts: Option<&SlackTs>,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let labels = &options.transcript_labels;
+    let mut header = author_name(message, slack_references, options);
+    if let Some(time) = message_time(message, options) {
+        header.push_str(&format!(" {time}"));
+    }
+    if let Some(edited) = &message.edited {
+        match format_date_time(&edited.ts, &options.time) {
+            Some(edited_time) => header.push_str(&format!(" ({} {edited_time})", labels.edited)),
+            None => header.push_str(&format!(" ({})", labels.edited)),
+        }
+    }
+    if let Some(thread_time) = orphan_thread_ts.and_then(|ts| format_date_time(ts, &options.time)) {
+        header.push_str(&format!(
+            " ({})",
+            labels.reply_to_thread.replace("{time}", &thread_time)
+        ));
+    }
+    let body = render_message_as_text(message, slack_references, options);
+    if body.is_empty() {
+        header
+    } else {
+        format!("{header}\n{body}")
+    }
+}
+
+/// Group messages by thread, in chronological order. Replies whose parent message is not in
+/// `messages` are rendered as top-level messages.
+fn build_threads(messages: &[SlackHistoryMessage]) -> Vec<Thread<'_>> {
+    let mut sorted = messages.iter().collect::<Vec<_>>();
+    sorted.sort_by(|a, b| compare_ts(&a.origin.ts, &b.origin.ts));
+
+    // Parents come before their replies, so they are indexed by the time their replies are met
+    let mut threads: Vec<Thread> = vec![];
+    let mut thread_indexes: HashMap<&SlackTs, usize> = HashMap::new();
+    for message in sorted {
+        let parent_index = message
+            .origin
+            .thread_ts
+            .as_ref()
+            .filter(|thread_ts| **thread_ts != message.origin.ts)
+            .and_then(|thread_ts| thread_indexes.get(thread_ts));
+        match parent_index {
+            Some(&index) => threads[index].replies.push(message),
+            None => {
+                thread_indexes
+                    .entry(&message.origin.ts)
+                    .or_insert(threads.len());
+                threads.push(Thread {
+                    message,
+                    replies: vec![],
+                });
+            }
+        }
+    }
+    threads
+}
+
+/// Timestamp of the thread of a reply rendered as a top-level message, because its parent
+/// message is not in the transcript.
+fn orphan_thread_ts(message: &SlackHistoryMessage) -> Option<&SlackTs> {
+    message
+        .origin
+        .thread_ts
+        .as_ref()
+        .filter(|thread_ts| **thread_ts != message.origin.ts)
+}
+
+fn message_permalink(message: &SlackHistoryMessage, options: &RenderOptions) -> Option<String> {
+    options.permalinks.as_ref()?.message_permalink(message)
+}
+
+fn thread_permalink(message: &SlackHistoryMessage, options: &RenderOptions) -> Option<String> {
+    options
+        .permalinks
+        .as_ref()?
+        .message_thread_permalink(message)
+}
+
+/// Compare Slack timestamps (`seconds.micros`) numerically.
+fn compare_ts(a: &SlackTs, b: &SlackTs) -> std::cmp::Ordering {
+    let parse = |ts: &SlackTs| {
+        let (seconds, micros) = ts.0.split_once('.').unwrap_or((&ts.0, "0"));
+        (
+            seconds.parse::<u64>().unwrap_or_default(),
+            micros.parse::<u64>().unwrap_or_default(),
+        )
+    };
+    parse(a).cmp(&parse(b))
+}
+
+/// Number of replies of a thread that are not in the transcript, e.g. `3 replies`.
+fn missing_replies(thread: &Thread, options: &RenderOptions) -> Option<String> {
+    let labels = &options.transcript_labels;
+    let reply_count = thread.message.parent.reply_count?;
+    let missing = reply_count.saturating_sub(thread.replies.len());
+    let label = match (missing, thread.replies.is_empty()) {
+        (0, _) => return None,
+        (1, true) => &labels.one_reply,
+        (_, true) => &labels.replies,
+        (1, false) => &labels.one_more_reply,
+        (_, false) => &labels.more_replies,
+    };
+    Some(label.replace("{count}", &missing.to_string()))
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn author_name(
+    message: &SlackHistoryMessage,
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    let sender = &message.sender;
+    [
+        sender
+            .user
+            .as_ref()
+            .and_then(|user_id| slack_references.user_name(user_id, options.user_display_policy)),
+        sender.username.as_deref(),
+        sender
+            .user_profile
+            .as_ref()
+            .and_then(|profile| profile.display_name.as_deref()),
+        sender
+            .bot_profile
+            .as_ref()
+            .map(|bot_profile| bot_profile.name.as_str()),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|name| !name.is_empty())
+    .map(|name| name.to_string())
+    .or(sender.user.as_ref().map(|user_id| user_id.0.clone()))
+    .unwrap_or_else(|| options.transcript_labels.unknown_author.clone())
+}
+
+fn message_day(message: &SlackHistoryMessage, options: &RenderOptions) -> Option<String> {
+    format_day(&message.origin.ts, &options.time)
+}
+
+fn message_time(message: &SlackHistoryMessage, options: &RenderOptions) -> Option<String> {
+    format_time(&message.origin.ts, &options.time)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::DateTime;
+
+    use super::*;
+    use crate::{
+        matrix::MatrixOptions,
+        permalink::SlackPermalinkBuilder,
+        test_utils::history_message,
+        time::{Clock, TimeLocale, TimeOptions},
+    };
+
+    fn messages() -> Vec<SlackHistoryMessage> {
+        vec![
+            history_message(serde_json::json!({
+                "ts": "1720000200.000100",
+                "thread_ts": "1720000000.000100",
+                "user": "U2",
+                "text": "Rolling back"
+            })),
+            history_message(serde_json::json!({
+                "ts": "1720000000.000100",
+                "thread_ts": "1720000000.000100",
+                "reply_count": 2,
+                "user": "U1",
+                "text": "API is down",
+                "edited": { "user": "U1", "ts": "1720000050.000100" }
+            })),
+            history_message(serde_json::json!({
+                "ts": "1720090000.000100",
+                "user": "U3",
+                "text": "Postmortem tomorrow",
+                "reply_count": 1
+            })),
+        ]
+    }
+
+    fn slack_references() -> SlackReferences {
+        SlackReferences {
+            users: HashMap::from([
+                (SlackUserId("U1".to_string()), Some("alice".to_string())),
+                (SlackUserId("U2".to_string()), Some("bob".to_string())),
+            ]),
+            ..SlackReferences::default()
+        }
+    }
+
+    #[test]
+    fn test_render_transcript_as_markdown() {
+        assert_eq!(
+            render_transcript_as_markdown(
+                &messages(),
+                &slack_references(),
+                &RenderOptions::default()
+            ),
+            "## 2024-07-03\n\n\
+            *alice* 09:46 _(edited Jul 3, 2024 at 09:47)_\nAPI is down\n\n\
+            > *bob* 09:50\n> Rolling back\n\n\
+            _1 more reply_\n\n\
+            ## 2024-07-04\n\n\
+            *U3* 10:46\nPostmortem tomorrow\n\n\
+            _1 reply_"
+        );
+    }
+
+    #[test]
+    fn test_render_transcript_as_html() {
+        assert_eq!(
+            render_transcript_as_html(&messages()[..2], &slack_references(), &RenderOptions::default()),
+            "<h2>2024-07-03</h2>\n\
+            <div class=\"slack-message\">\n\
+            <p class=\"slack-message-header\"><strong class=\"slack-author\">alice</strong> \
+            <time datetime=\"2024-07-03T09:46:40+00:00\">09:46</time> <span class=\"slack-edited\" title=\"Edited Jul 3, 2024 at 09:47\">(edited)</span></p>\n\
+            <p>API is down</p>\n\
+            <div class=\"slack-thread\">\n\
+            <div class=\"slack-message\">\n\
+            <p class=\"slack-message-header\"><strong class=\"slack-author\">bob</strong> \
+            <time datetime=\"2024-07-03T09:50:00+00:00\">09:50</time></p>\n\
+            <p>Rolling back</p>\n\
+            </div>\n\
+            <p class=\"slack-replies\">1 more reply</p>\n\
+            </div>\n\
+            </div>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_transcript_as_text() {
+        assert_eq!(
+            render_transcript_as_text(
+                &messages()[..2],
+                &slack_references(),
+                &RenderOptions::default()
+            ),
+            "2024-07-03\n\n\
+            alice 09:46 (edited Jul 3, 2024 at 09:47)\nAPI is down\n\n    \
+            bob 09:50\n    Rolling back\n\n    \
+            1 more reply"
+        );
+    }
+
+    #[test]
+    fn test_render_transcript_with_labels() {
+        let options = RenderOptions {
+            transcript_labels: TranscriptLabels {
+                edited: "modifié".to_string(),
+                reply_to_thread: "réponse au fil du {time}".to_string(),
+                replies: "{count} réponses".to_string(),
+                one_reply: "{count} réponse".to_string(),
+                more_replies: "{count} autres réponses".to_string(),
+                one_more_reply: "{count} autre réponse".to_string(),
+                unknown_author: "inconnu".to_string(),
+            },
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render_transcript_as_text(&messages(), &slack_references(), &options),
+            "2024-07-03\n\n\
+            alice 09:46 (modifié Jul 3, 2024 at 09:47)\nAPI is down\n\n    \
+            bob 09:50\n    Rolling back\n\n    \
+            1 autre réponse\n\n\
+            2024-07-04\n\n\
+            U3 10:46\nPostmortem tomorrow\n\n    \
+            1 réponse"
+        );
+        assert_eq!(
+            render_transcript_as_markdown(&messages()[..1], &slack_references(), &options),
+            "## 2024-07-03\n\n\
+            *bob* 09:50 _(réponse au fil du Jul 3, 2024 at 09:46)_\nRolling back"
+        );
+    }
+
+    #[test]
+    fn test_author_names() {
+        let messages = vec![
+            history_message(serde_json::json!({
+                "ts": "1720000000.000100",
+                "username": "",
+                "bot_profile": { "id": "B1", "name": "deploy_bot", "app_id": "A1" },
+                "text": "Deployed"
+            })),
+            history_message(serde_json::json!({
+                "ts": "1720000060.000100",
+                "username": "*ops*",
+                "text": "Rolled back"
+            })),
+        ];
+        assert_eq!(
+            render_transcript_as_markdown(
+                &messages,
+                &slack_references(),
+                &RenderOptions::default()
+            ),
+            "## 2024-07-03\n\n\
+            *deploy\\_bot* 09:46\nDeployed\n\n\
+            *\\*ops\\** 09:47\nRolled back"
+        );
+    }
+
+    #[test]
+    fn test_unknown_author_label() {
+        let messages = vec![history_message(serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "Deployed"
+        }))];
+        assert_eq!(
+            render_transcript_as_text(&messages, &slack_references(), &RenderOptions::default()),
+            "2024-07-03\n\nunknown 09:46\nDeployed"
+        );
+        let options = RenderOptions {
+            transcript_labels: TranscriptLabels {
+                unknown_author: "inconnu".to_string(),
+                ..TranscriptLabels::default()
+            },
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render_transcript_as_text(&messages, &slack_references(), &options),
+            "2024-07-03\n\ninconnu 09:46\nDeployed"
+        );
+    }
+
+    #[test]
+    fn test_orphan_replies_are_top_level() {
+        let messages = messages()[..1].to_vec();
+        assert_eq!(
+            render_transcript_as_text(&messages, &slack_references(), &RenderOptions::default()),
+            "2024-07-03\n\nbob 09:50 (reply to a thread from Jul 3, 2024 at 09:46)\nRolling back"
+        );
+    }
+
+    #[test]
+    fn test_render_transcript_with_time_options() {
+        let options = RenderOptions {
+            time: TimeOptions {
+                timezone: chrono_tz::America::Bogota,
+                locale: TimeLocale::AmericanEnglish,
+                relative: true,
+                clock: Clock::fixed(DateTime::from_timestamp(1720090000, 0).unwrap()),
+            },
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render_transcript_as_text(&messages()[1..], &slack_references(), &options),
+            "Yesterday\n\n\
+            alice 4:46 AM (edited yesterday at 4:47 AM)\nAPI is down\n\n    \
+            2 replies\n\n\
+            Today\n\n\
+            U3 5:46 AM\nPostmortem tomorrow\n\n    \
+            1 reply"
+        );
+    }
+
+    #[test]
+    fn test_render_transcript_with_permalinks() {
+        let options = RenderOptions {
+            permalinks: Some(
+                SlackPermalinkBuilder::new("acme")
+                    .with_channel_id(SlackChannelId("C1".to_string())),
+            ),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render_transcript_as_markdown(&messages()[..2], &slack_references(), &options),
+            "## 2024-07-03\n\n\
+            *alice* [09:46](https://acme.slack.com/archives/C1/p1720000000000100) \
+            _(edited Jul 3, 2024 at 09:47)_\nAPI is down\n\n\
+            > *bob* [09:50](https://acme.slack.com/archives/C1/p1720000200000100?thread_ts=1720000000.000100&cid=C1)\n\
+            > Rolling back\n\n\
+            _1 more reply_"
+        );
+        assert_eq!(
+            render_transcript_as_html(&messages()[..1], &slack_references(), &options),
+            "<h2>2024-07-03</h2>\n\
+            <div class=\"slack-message\">\n\
+            <p class=\"slack-message-header\"><strong class=\"slack-author\">bob</strong> \
+            <a class=\"slack-permalink\" target=\"_blank\" rel=\"noopener noreferrer\" \
+            href=\"https://acme.slack.com/archives/C1/p1720000200000100?thread_ts=1720000000.000100&amp;cid=C1\">\
+            <time datetime=\"2024-07-03T09:50:00+00:00\">09:50</time></a> \
+            <span class=\"slack-thread-reply\">(reply to a thread from \
+            <a target=\"_blank\" rel=\"noopener noreferrer\" href=\"https://acme.slack.com/archives/C1/p1720000000000100\">\
+            Jul 3, 2024 at 09:46</a>)</span></p>\n\
+            <p>Rolling back</p>\n\
+            </div>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_transcript_as_matrix_html() {
+        let options = RenderOptions {
+            matrix: Some(MatrixOptions::default()),
+            permalinks: Some(
+                SlackPermalinkBuilder::new("acme")
+                    .with_channel_id(SlackChannelId("C1".to_string())),
+            ),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render_transcript_as_html(&messages()[..2], &slack_references(), &options),
+            "<h2>2024-07-03</h2>\n\
+            <div>\n\
+            <p><strong>alice</strong> \
+            <a href=\"https://acme.slack.com/archives/C1/p1720000000000100\">09:46</a> \
+            <em>(edited Jul 3, 2024 at 09:47)</em></p>\n\
+            <p>API is down</p>\n\
+            <blockquote>\n\
+            <div>\n\
+            <p><strong>bob</strong> \
+            <a href=\"https://acme.slack.com/archives/C1/p1720000200000100?thread_ts=1720000000.000100&amp;cid=C1\">09:50</a></p>\n\
+            <p>Rolling back</p>\n\
+            </div>\n\
+            <p>1 more reply</p>\n\
+            </blockquote>\n\
+            </div>\n"
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    files::escape_link_text,
+    html::{
+        class_attribute, is_image_allowed, render_blocks_as_html_with_options, render_image_html,
+        render_link_html,
+    },
+    markdown::{escape_markdown, render_blocks_as_markdown_with_options},
+    message::mrkdwn_section,
+    options::RenderOptions,
+    references::SlackReferences,
+};
+
+/// Preview of a link shared in a message, sent by Slack as a message attachment.
+///
+/// The slack-morphism attachment model lacks these fields, so unfurls are deserialized from
+/// the raw attachments, e.g. with [`find_unfurls_in_message`].
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlackUnfurl {
+    #[serde(default)]
+    pub service_name: Option<String>,
+    #[serde(default)]
+    pub service_icon: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub title_link: Option<String>,
+    /// URL that was unfurled.
+    #[serde(default)]
+    pub from_url: Option<String>,
+    #[serde(default)]
+    pub original_url: Option<String>,
+    /// mrkdwn description.
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub image_url: Option<String>,
+    #[serde(default)]
+    pub thumb_url: Option<String>,
+}
+
+impl SlackUnfurl {
+    fn url(&self) -> Option<&str> {
+        self.title_link
+            .as_deref()
+            .or(self.from_url.as_deref())
+            .or(self.original_url.as_deref())
+    }
+
+    /// Name of the service, or host of the unfurled URL.
+    fn site(&self) -> Option<String> {
+        self.service_name.clone().or_else(|| {
+            let url = url::Url::parse(self.url()?).ok()?;
+            url.host_str().map(|host| host.to_string())
+        })
+    }
+
+    fn image(&self) -> Option<&str> {
+        self.image_url.as_deref().or(self.thumb_url.as_deref())
+    }
+}
+
+/// Find the unfurls in the attachments of a message in the JSON format of the Slack API:
+/// the attachments having a `from_url` or an `original_url`.
+pub fn find_unfurls_in_message(message: &serde_json::Value) -> Vec<SlackUnfurl> {
+    let Some(serde_json::Value::Array(attachments)) = message.get("attachments") else {
+        return vec![];
+    };
+    attachments
+        .iter()
+        .filter(|attachment| {
+            attachment.get("from_url").is_some() || attachment.get("original_url").is_some()
+        })
+        .filter_map(|attachment| serde_json::from_value(attachment.clone()).ok())
+        .collect()
+}
+
+/// Render unfurls as Markdown quotes with their service, linked title, description and image.
+pub fn render_unfurls_as_markdown(
+    unfurls: &[SlackUnfurl],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    unfurls
+        .iter()
+        .map(|unfurl| {
+            let mut lines = vec![];
+            if let Some(service_name) = &unfurl.service_name {
+                lines.push(format!("*{}*", escape_markdown(service_name)));
+            }
+            match (&unfurl.title, unfurl.url()) {
+                (Some(title), Some(url)) => {
+                    lines.push(format!("[{}]({url})", escape_markdown(title)))
+                }
+                (Some(title), None) => lines.push(escape_markdown(title)),
+                (None, _) => {}
+            }
+            if let Some(text) = &unfurl.text {
+                lines.push(render_blocks_as_markdown_with_options(
+                    &[mrkdwn_section(text)],
+                    slack_references,
+                    options,
+                ));
+            }
+            if let Some(image) = unfurl.image() {
+                let alt = unfurl.title.as_deref().unwrap_or_default();
+                lines.push(format!("![{}]({image})", escape_link_text(alt)));
+            }
+            lines
+                .join("\n")
+                .lines()
+                .map(|line| format!("> {line}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Render unfurls as `slack-unfurl` cards with their service, linked title, description and
+/// image, or as quotes in Matrix HTML which has no classes.
+pub fn render_unfurls_as_html(
+    unfurls: &[SlackUnfurl],
+    slack_references: &SlackReferences,
+    options: &RenderOptions,
+) -> String {
+    unfurls
+        .iter()
+        .map(|unfurl| {
+            let mut html = String::new();
+            if let Some(service_name) = &unfurl.service_name {
+                let icon = unfurl
+                    .service_icon
+                    .as_deref()
+                    .filter(|icon| is_image_allowed(icon, options))
+                    .map(|icon| {
+                        format!(
+                            "<img{} src=\"{}\" alt=\"\" /> ",
+                            class_attribute("slack-unfurl-icon", options),
+                            encode_double_quoted_attribute(icon)
+                        )
+                    })
+                    .unwrap_or_default();
+                html.push_str(&format!(
+                    "<p{}>{icon}{}</p>\n",
+                    class_attribute("slack-unfurl-service", options),
+                    encode_text(service_name)
+                ));
+            }
+            let title_class = class_attribute("slack-unfurl-title", options);
+            match (&unfurl.title, unfurl.url()) {
+                (Some(title), Some(url)) => html.push_str(&format!(
+                    "<p{title_class}>{}</p>\n",
+                    render_link_html(url, &encode_text(title), options)
+                )),
+                (Some(title), None) => {
+                    html.push_str(&format!("<p{title_class}>{}</p>\n", encode_text(title)))
+                }
+                (None, _) => {}
+            }
+            if let Some(text) = &unfurl.text {
+                html.push_str(&render_blocks_as_html_with_options(
+                    &[mrkdwn_section(text)],
+                    slack_references,
+                    options,
+                ));
+            }
+            if let Some(image) = unfurl.image() {
+                let alt = unfurl.title.as_deref().unwrap_or_default();
+                if options.matrix.is_some() {
+                    html.push_str(&format!(
+                        "<p>{}</p>\n",
+                        render_image_html(image, alt, options)
+                    ));
+                } else {
+                    html.push_str(&format!(
+                        "<img class=\"slack-unfurl-image\" src=\"{}\" alt=\"{}\" />\n",
+                        encode_double_quoted_attribute(image),
+                        encode_double_quoted_attribute(alt)
+                    ));
+                }
+            }
+            // Matrix HTML has no classes: the unfurl is quoted instead of being a card
+            if options.matrix.is_some() {
+                format!("<blockquote>\n{html}</blockquote>\n")
+            } else {
+                format!("<div class=\"slack-unfurl\">\n{html}</div>\n")
+            }
+        })
+        .collect()
+}
+
+/// Render unfurls as plain text, one `title — site` line per unfurl.
+pub fn render_unfurls_as_text(unfurls: &[SlackUnfurl]) -> String {
+    unfurls
+        .iter()
+        .filter_map(|unfurl| {
+            let title = unfurl
+                .title
+                .as_deref()
+                .or(unfurl.url())
+                .map(|title| title.to_string());
+            match (title, unfurl.site()) {
+                (Some(title), Some(site)) => Some(format!("{title} — {site}")),
+                (title, site) => title.or(site),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::MatrixOptions;
+
+    fn unfurls() -> Vec<SlackUnfurl> {
+        find_unfurls_in_message(&serde_json::json!({
+            "ts": "1720000000.000100",
+            "text": "<https://github.com/dax/slack-blocks-render>",
+            "attachments": [
+                {
+                    "from_url": "https://github.com/dax/slack-blocks-render",
+                    "service_name": "GitHub",
+                    "service_icon": "https://github.com/favicon.ico",
+                    "title": "dax/slack-blocks-render",
+                    "title_link": "https://github.com/dax/slack-blocks-render",
+                    "text": "Render Slack blocks :rocket:",
+                    "thumb_url": "https://opengraph.githubassets.com/1/dax/slack-blocks-render"
+                },
+                {
+                    "color": "good",
+                    "title": "Not an unfurl"
+                }
+            ]
+        }))
+    }
+
+    #[test]
+    fn test_find_unfurls_in_message() {
+        let unfurls = unfurls();
+        assert_eq!(unfurls.len(), 1);
+        assert_eq!(unfurls[0].service_name, Some("GitHub".to_string()));
+    }
+
+    #[test]
+    fn test_render_unfurls_as_markdown() {
+        assert_eq!(
+            render_unfurls_as_markdown(
+                &unfurls(),
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "> *GitHub*\n\
+            > [dax/slack-blocks-render](https://github.com/dax/slack-blocks-render)\n\
+            > Render Slack blocks 🚀\n\
+            > ![dax/slack-blocks-render](https://opengraph.githubassets.com/1/dax/slack-blocks-render)"
+        );
+    }
+
+    #[test]
+    fn test_render_unfurls_as_markdown_escapes_titles() {
+        let unfurls = find_unfurls_in_message(&serde_json::json!({
+            "attachments": [
+                {
+                    "from_url": "https://example.com/post",
+                    "service_name": "*News*",
+                    "title": "[Draft] snake_case *rules*",
+                    "image_url": "https://example.com/post.png"
+                }
+            ]
+        }));
+        assert_eq!(
+            render_unfurls_as_markdown(
+                &unfurls,
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "> *\\*News\\**\n\
+            > [\\[Draft\\] snake\\_case \\*rules\\*](https://example.com/post)\n\
+            > ![\\[Draft\\] snake_case *rules*](https://example.com/post.png)"
+        );
+    }
+
+    #[test]
+    fn test_render_unfurls_as_html() {
+        assert_eq!(
+            render_unfurls_as_html(
+                &unfurls(),
+                &SlackReferences::default(),
+                &RenderOptions::default()
+            ),
+            "<div class=\"slack-unfurl\">\n\
+            <p class=\"slack-unfurl-service\"><img class=\"slack-unfurl-icon\" src=\"https://github.com/favicon.ico\" alt=\"\" /> GitHub</p>\n\
+            <p class=\"slack-unfurl-title\"><a target=\"_blank\" rel=\"noopener noreferrer\" href=\"https://github.com/dax/slack-blocks-render\">dax/slack-blocks-render</a></p>\n\
+            <p>Render Slack blocks 🚀</p>\n\
+            <img class=\"slack-unfurl-image\" src=\"https://opengraph.githubassets.com/1/dax/slack-blocks-render\" alt=\"dax/slack-blocks-render\" />\n\
+            </div>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_unfurls_as_matrix_html() {
+        let options = RenderOptions {
+            matrix: Some(MatrixOptions::default()),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render_unfurls_as_html(&unfurls(), &SlackReferences::default(), &options),
+            "<blockquote>\n\
+            <p>GitHub</p>\n\
+            <p><a href=\"https://github.com/dax/slack-blocks-render\">dax/slack-blocks-render</a></p>\n\
+            <p>Render Slack blocks 🚀</p>\n\
+            <p><a href=\"https://opengraph.githubassets.com/1/dax/slack-blocks-render\">dax/slack-blocks-render</a></p>\n\
+            </blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_unfurls_as_text() {
+        let unfurls = vec![
+            unfurls().remove(0),
+            SlackUnfurl {
+                title: Some("Release notes".to_string()),
+                from_url: Some("https://example.com/releases".to_string()),
+                ..SlackUnfurl::default()
+            },
+        ];
+        assert_eq!(
+            render_unfurls_as_text(&unfurls),
+            "dax/slack-blocks-render — GitHub\nRelease notes — example.com"
+        );
+    }
+}
@@ -10,6 +10,11 @@ pub(crate) struct SlackRichTextBlock {
     pub json_value: serde_json::Value,
 }
 
+/// A block without a rendering, visited by default for the blocks no visitor renders.
+pub(crate) struct SlackUnsupportedBlock {
+    pub block_type: &'static str,
+}
+
 visitor!(
     #[
         helper_tmpl = {
@@ -26,10 +31,11 @@ visitor!(
                 SlackBlock::RichText(rich_text_block) => visitor.visit_slack_rich_text_block(&SlackRichTextBlock { json_value: serde_json::to_value(rich_text_block).unwrap_or_default() }),
                 SlackBlock::Event(json_value) => visitor.visit_slack_event_block(&SlackEventBlock { json_value: json_value.clone() }),
                 SlackBlock::Markdown(markdown) => visitor.visit_slack_markdown_block(markdown),
-                SlackBlock::ShareShortcut(_) => {},
+                SlackBlock::ShareShortcut(_) => visitor.visit_slack_unsupported_block(&SlackUnsupportedBlock { block_type: "share_shortcut" }),
                 // Table and TaskCard blocks (added in slack-morphism 2.22) carry no
-                // plain-text/markdown representation we can render, so emit nothing.
-                SlackBlock::Table(_) | SlackBlock::TaskCard(_) => {}
+                // plain-text/markdown representation we can render, so they are unsupported.
+                SlackBlock::Table(_) => visitor.visit_slack_unsupported_block(&SlackUnsupportedBlock { block_type: "table" }),
+                SlackBlock::TaskCard(_) => visitor.visit_slack_unsupported_block(&SlackUnsupportedBlock { block_type: "task_card" }),
             }
         },
     ]
@@ -67,6 +73,12 @@ visitor!(
     SlackDividerBlock,
     SlackImageBlock,
     SlackBlockImageElement,
+    #[
+        helper_tmpl = {
+            let _ = slack_actions_block;
+            visitor.visit_slack_unsupported_block(&SlackUnsupportedBlock { block_type: "actions" });
+        },
+    ]
     SlackActionsBlock,
     #[
         helper_tmpl = {
@@ -81,10 +93,29 @@ visitor!(
     ]
     SlackContextBlock,
     SlackBlockMarkDownText,
+    #[
+        helper_tmpl = {
+            let _ = slack_input_block;
+            visitor.visit_slack_unsupported_block(&SlackUnsupportedBlock { block_type: "input" });
+        },
+    ]
     SlackInputBlock,
+    #[
+        helper_tmpl = {
+            let _ = slack_file_block;
+            visitor.visit_slack_unsupported_block(&SlackUnsupportedBlock { block_type: "file" });
+        },
+    ]
     SlackFileBlock,
     SlackVideoBlock,
+    #[
+        helper_tmpl = {
+            let _ = slack_event_block;
+            visitor.visit_slack_unsupported_block(&SlackUnsupportedBlock { block_type: "event" });
+        },
+    ]
     SlackEventBlock,
     SlackRichTextBlock,
     SlackMarkdownBlock,
+    SlackUnsupportedBlock,
 );